@@ -5,7 +5,7 @@ use std::sync::{
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossterm::{
     event::{self, Event},
 };
@@ -14,36 +14,81 @@ use signal_hook::{consts::signal::*, iterator::Signals};
 use crate::{
     audio::AudioOutput,
     config::Config,
-    library::{default_library_path, discover_tracks},
+    library::{default_library_path, discover_tracks, LibraryScan, LibraryWatcher, TagTemplate},
     mpris::{self, MprisCommand, MprisState},
     player::{PlayState, Player},
     term::{hide_to_shell_toggleable, init_terminal, TerminalCleanup},
-    ui::{draw_ui, handle_key, UiAction, UiState},
+    ui::{draw_ui, handle_key, handle_mouse, AlbumArt, UiAction, UiState},
 };
 
+mod stats;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    /// Directory (or file) to play.
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Directories (or files) to play. Can be given more than once.
     ///
-    /// If omitted: uses XDG music dir (if configured), else ~/Music, else the current directory.
+    /// If omitted: uses `[library] roots` from the config, else the XDG music dir
+    /// (if configured), else ~/Music, else the current directory.
     #[arg(value_name = "PATH")]
-    path: Option<std::path::PathBuf>,
+    paths: Vec<std::path::PathBuf>,
+
+    /// Skip paths matching this glob (e.g. '**/Samples/**'). Can be given more than once.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Don't descend more than this many directories below each root.
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Don't follow symbolic links while scanning.
+    #[arg(long)]
+    no_follow_symlinks: bool,
 
     /// Start at this track index (0-based)
     #[arg(long, default_value_t = 0)]
     index: usize,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print listening statistics from the play history.
+    Stats {
+        /// Print them as JSON, with every period and top list in full.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 pub(crate) fn run() -> Result<()> {
     TerminalCleanup::install_panic_hook();
 
     let args = Args::parse();
+    if let Some(Command::Stats { json }) = args.command {
+        return stats::print_stats(json);
+    }
 
     let config = Config::load();
     let theme = config.theme;
 
-    let library_path = args.path.unwrap_or_else(default_library_path);
+    let roots = if !args.paths.is_empty() {
+        args.paths
+    } else if !config.library.roots.is_empty() {
+        config.library.roots.clone()
+    } else {
+        vec![default_library_path()]
+    };
+    let mut exclude = config.library.exclude.clone();
+    exclude.extend(args.exclude);
+    let library = LibraryScan::new(
+        roots,
+        &exclude,
+        args.max_depth.or(config.library.max_depth),
+        config.library.follow_symlinks && !args.no_follow_symlinks,
+    );
 
     // Low-latency audio output (small fixed buffers) so stop is immediate.
     let audio = AudioOutput::new_low_latency().context("Failed to initialize audio output")?;
@@ -71,8 +116,24 @@ pub(crate) fn run() -> Result<()> {
         });
     }
 
-    let tracks = discover_tracks(&library_path)?;
-    let mut player = Player::new(tracks, args.index, audio, library_path.clone())?;
+    let discovery = discover_tracks(&library)?;
+    // Keep the list in sync with files added, removed, renamed or re-tagged on disk.
+    let mut watcher = LibraryWatcher::spawn(&library);
+    let mut player = Player::new(discovery.tracks, args.index, audio, library)?;
+    player.skipped = discovery.skipped;
+    let display = config.library.display.as_deref().and_then(|d| {
+        TagTemplate::parse(d)
+            .map_err(|e| eprintln!("trix: ignoring library.display {d:?}: {e}"))
+            .ok()
+    });
+    player.set_display_template(display);
+    if let Some(o) = config.library.organize.as_deref() {
+        match TagTemplate::parse(o) {
+            Ok(template) => player.organize_template = template,
+            Err(e) => eprintln!("trix: ignoring library.organize {o:?}: {e}"),
+        }
+    }
+    player.hard_delete = config.library.hard_delete;
 
     // MPRIS: expose play/pause/stop via D-Bus so external controllers work.
     let mpris_state = MprisState::new();
@@ -83,13 +144,15 @@ pub(crate) fn run() -> Result<()> {
 
     // Auto-start first track if any
     if player.has_tracks() {
-        player.start_track(Duration::ZERO)?;
+        player.start_track()?;
     }
 
     let mut terminal = init_terminal()?;
     let _cleanup = TerminalCleanup;
 
     let mut ui = UiState::new();
+    ui.art = AlbumArt::new(config.ui.album_art);
+    ui.keys = config.keys;
 
     let tick_rate = Duration::from_millis(50);
     loop {
@@ -128,12 +191,15 @@ pub(crate) fn run() -> Result<()> {
 
         player.refresh_volume();
 
+        ui.art.update(&player);
         if terminal.draw(|f| draw_ui(f, &player, &ui, &theme)).is_err() {
             // Terminal likely closed (broken pipe / pty hangup). Treat as a clean quit.
             audio_ctl.shutdown_now();
             player.stop_playback();
             break;
         }
+        // Graphics protocols draw the cover outside of ratatui's buffer.
+        let _ = ui.art.present(&mut terminal);
 
         // Auto-advance
         if !player.loop_current {
@@ -144,21 +210,38 @@ pub(crate) fn run() -> Result<()> {
             }
         }
 
-        // Refresh library when a YouTube download completes, then close the menu after 1s.
+        // When a YouTube download completes, the result moves to the command
+        // line message after 1s (an error at once). The library watcher picks
+        // up the new file.
         {
             use crate::ui::YtDlStatus;
             let mut status = ui.youtube_dl_status.lock().unwrap();
-            if let YtDlStatus::Done(_, finished_at) = &*status {
-                if finished_at.elapsed() >= Duration::from_secs(1) {
+            match &*status {
+                YtDlStatus::Done(msg, finished_at)
+                    if finished_at.elapsed() >= Duration::from_secs(1) =>
+                {
+                    ui.command_message = Some((msg.clone(), Instant::now()));
+                    *status = YtDlStatus::Idle;
+                }
+                YtDlStatus::Error(msg) => {
+                    ui.command_message = Some((format!("yt: {msg}"), Instant::now()));
                     *status = YtDlStatus::Idle;
-                    drop(status);
-                    player.refresh_tracks();
-                    ui.youtube_dl_mode = false;
-                    ui.youtube_dl_url.clear();
                 }
+                _ => {}
             }
         }
 
+        if let Some((changes, discovery)) = watcher.take_changes() {
+            player.apply_library_changes(changes, discovery);
+        }
+        player.poll_loaders();
+        if let Some(search) = &mut ui.search {
+            search.refresh_if_stale(&player);
+        }
+        if let Some(stats) = &mut ui.stats {
+            stats.refresh_if_stale(&player);
+        }
+
         // Sync MPRIS playback state for D-Bus property queries.
         mpris_state.set(player.state);
 
@@ -208,8 +291,13 @@ pub(crate) fn run() -> Result<()> {
                             eprintln!("trix: hide failed: {e:#}");
                             break;
                         }
+                        ui.art.invalidate();
                     }
                 }
+            } else if let Event::Mouse(mouse) = ev {
+                handle_mouse(mouse, &mut player, &mut ui);
+            } else if let Event::Resize(..) = ev {
+                ui.art.invalidate();
             }
         }
 
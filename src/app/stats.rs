@@ -0,0 +1,79 @@
+//! `trix stats`: the listening statistics on the command line.
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write as _},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+
+use crate::{
+    library::{self, load_history, ListeningStats, PlayedFile, Ranked},
+    meta,
+    util::fmt_span,
+};
+
+/// Top entries listed per category in the text output.
+const SHOWN: usize = 5;
+
+pub(super) fn print_stats(json: bool) -> Result<()> {
+    let history = load_history();
+    let stats = ListeningStats::compute(&history, SystemTime::now(), |path, start| {
+        let mut tags = meta::probe_track_meta(path).ok();
+        if let (Some(tags), Some(cue)) = (&mut tags, library::find_cue_track(path, start)) {
+            meta::apply_cue(tags, &cue);
+        }
+        PlayedFile::new(path, tags.as_ref())
+    });
+    let out = if json {
+        serde_json::to_string_pretty(&stats)? + "\n"
+    } else {
+        text(&stats)
+    };
+    // Piping into `head` and the like closes stdout early; that's not an error.
+    match io::stdout().lock().write_all(out.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn text(stats: &ListeningStats) -> String {
+    let mut out = format!(
+        "Streak: {} (longest {})\n",
+        days(stats.current_streak),
+        days(stats.longest_streak)
+    );
+    for period in &stats.periods {
+        let _ = writeln!(
+            out,
+            "\n{}: {} listens, {}",
+            period.period.label(),
+            period.listens,
+            fmt_span(Duration::from_secs(period.listened_secs))
+        );
+        push_top(&mut out, "Top tracks", &period.top_tracks, "plays");
+        push_top(&mut out, "Top artists", &period.top_artists, "plays");
+        push_top(&mut out, "Top albums", &period.top_albums, "plays");
+        push_top(&mut out, "Most skipped", &period.most_skipped, "skips");
+    }
+    out
+}
+
+fn push_top(out: &mut String, title: &str, ranked: &[Ranked], unit: &str) {
+    if ranked.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "  {title} ({unit}):");
+    for r in ranked.iter().take(SHOWN) {
+        let _ = writeln!(out, "    {:>4}  {}", r.count, r.name);
+    }
+}
+
+fn days(n: u32) -> String {
+    if n == 1 {
+        "1 day".to_string()
+    } else {
+        format!("{n} days")
+    }
+}
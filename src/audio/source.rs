@@ -391,9 +391,15 @@ fn open_format(path: &Path) -> Result<(Box<dyn FormatReader>, u32)> {
         &MetadataOptions::default(),
     )?;
 
+    // Prefer the first track we can actually decode: video containers (MP4/WebM/MKV)
+    // may list a video or unsupported stream as the default.
+    let codecs = symphonia::default::get_codecs();
     let track_id = probed
         .format
-        .default_track()
+        .tracks()
+        .iter()
+        .find(|t| codecs.get_codec(t.codec_params.codec).is_some())
+        .or_else(|| probed.format.default_track())
         .or_else(|| probed.format.tracks().first())
         .ok_or_else(|| anyhow!("no tracks in container"))?
         .id;
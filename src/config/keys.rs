@@ -0,0 +1,1009 @@
+//! Key bindings: the actions keys trigger in the main view and in the views
+//! that read some keys their own way, and the `[keys]` config section that
+//! remaps them.
+
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Help,
+    Quit,
+    CommandLine,
+    Search,
+    Filter,
+    ClearFilter,
+    MoveToTime,
+    YoutubeDownload,
+    Delete,
+    UndoDelete,
+    SkippedFiles,
+    TrackInfo,
+    History,
+    Stats,
+    EditTags,
+    Mark,
+    Organize,
+    AddToPlaylist,
+    MoveToFolder,
+
+    PlayPause,
+    PrevTrack,
+    NextTrack,
+    Restart,
+    Loop,
+    Shuffle,
+    SeekBack,
+    SeekForward,
+    SeekBackLong,
+    SeekForwardLong,
+    VolumeMode,
+
+    LyricsView,
+    LyricsSync,
+    LyricsScrollDown,
+    LyricsScrollUp,
+    LyricsSooner,
+    LyricsLater,
+    SaveLyricsOffset,
+
+    SelectUp,
+    SelectDown,
+    SelectFirst,
+    SelectLast,
+    VisualMode,
+    ExtendUp,
+    ExtendDown,
+    PlaySelected,
+    Enqueue,
+    CycleSort,
+    ReverseSort,
+    Favorite,
+    FavoritesView,
+
+    SearchNext,
+    SearchPrev,
+    SearchPlayAll,
+    SearchEnqueueAll,
+    SearchEdit,
+
+    StatsWeek,
+    StatsMonth,
+    StatsAllTime,
+
+    SyncStamp,
+    SyncUndo,
+    SyncWrite,
+
+    TagClearField,
+
+    OrganizeConflicts,
+}
+
+/// Help overlay sections, in display order.
+pub const SECTIONS: [&str; 10] = [
+    "General",
+    "Playback",
+    "Lyrics",
+    "Library",
+    "Favourites",
+    "Search",
+    "Listening stats",
+    "Sync recorder",
+    "Tag editor",
+    "Organize",
+];
+
+/// Where a binding applies: the main view, or a view with keys of its own.
+/// Keys a view doesn't bind go on to the main bindings; the views also move
+/// with the select keys and close with `quit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyScope {
+    Main,
+    Search,
+    Stats,
+    LyricsSync,
+    TagEditor,
+    Organize,
+}
+
+impl KeyScope {
+    const VIEWS: [KeyScope; 5] = [
+        KeyScope::Search,
+        KeyScope::Stats,
+        KeyScope::LyricsSync,
+        KeyScope::TagEditor,
+        KeyScope::Organize,
+    ];
+
+    /// The `[keys.<name>]` table that remaps the view's keys.
+    fn name(self) -> &'static str {
+        match self {
+            KeyScope::Main => "keys",
+            KeyScope::Search => "search",
+            KeyScope::Stats => "stats",
+            KeyScope::LyricsSync => "lyrics_sync",
+            KeyScope::TagEditor => "tag_editor",
+            KeyScope::Organize => "organize",
+        }
+    }
+
+    /// What the view is called in warnings.
+    fn label(self) -> &'static str {
+        match self {
+            KeyScope::Main => "the main view",
+            KeyScope::Search => "the search results",
+            KeyScope::Stats => "the listening stats",
+            KeyScope::LyricsSync => "the sync recorder",
+            KeyScope::TagEditor => "the tag editor",
+            KeyScope::Organize => "the organize preview",
+        }
+    }
+}
+
+impl Action {
+    pub const ALL: [Action; 63] = [
+        Action::Help,
+        Action::Quit,
+        Action::CommandLine,
+        Action::Search,
+        Action::Filter,
+        Action::ClearFilter,
+        Action::MoveToTime,
+        Action::YoutubeDownload,
+        Action::Delete,
+        Action::UndoDelete,
+        Action::SkippedFiles,
+        Action::TrackInfo,
+        Action::History,
+        Action::Stats,
+        Action::EditTags,
+        Action::Mark,
+        Action::Organize,
+        Action::AddToPlaylist,
+        Action::MoveToFolder,
+        Action::PlayPause,
+        Action::PrevTrack,
+        Action::NextTrack,
+        Action::Restart,
+        Action::Loop,
+        Action::Shuffle,
+        Action::SeekBack,
+        Action::SeekForward,
+        Action::SeekBackLong,
+        Action::SeekForwardLong,
+        Action::VolumeMode,
+        Action::LyricsView,
+        Action::LyricsSync,
+        Action::LyricsScrollDown,
+        Action::LyricsScrollUp,
+        Action::LyricsSooner,
+        Action::LyricsLater,
+        Action::SaveLyricsOffset,
+        Action::SelectUp,
+        Action::SelectDown,
+        Action::SelectFirst,
+        Action::SelectLast,
+        Action::VisualMode,
+        Action::ExtendUp,
+        Action::ExtendDown,
+        Action::PlaySelected,
+        Action::Enqueue,
+        Action::CycleSort,
+        Action::ReverseSort,
+        Action::Favorite,
+        Action::FavoritesView,
+        Action::SearchNext,
+        Action::SearchPrev,
+        Action::SearchPlayAll,
+        Action::SearchEnqueueAll,
+        Action::SearchEdit,
+        Action::StatsWeek,
+        Action::StatsMonth,
+        Action::StatsAllTime,
+        Action::SyncStamp,
+        Action::SyncUndo,
+        Action::SyncWrite,
+        Action::TagClearField,
+        Action::OrganizeConflicts,
+    ];
+
+    /// The name used in `[keys]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Help => "help",
+            Action::Quit => "quit",
+            Action::CommandLine => "command_line",
+            Action::Search => "search",
+            Action::Filter => "filter",
+            Action::ClearFilter => "clear_filter",
+            Action::MoveToTime => "move_to_time",
+            Action::YoutubeDownload => "youtube_download",
+            Action::Delete => "delete",
+            Action::UndoDelete => "undo_delete",
+            Action::SkippedFiles => "skipped_files",
+            Action::TrackInfo => "track_info",
+            Action::History => "history",
+            Action::Stats => "stats",
+            Action::EditTags => "edit_tags",
+            Action::Mark => "mark",
+            Action::Organize => "organize",
+            Action::AddToPlaylist => "add_to_playlist",
+            Action::MoveToFolder => "move_to_folder",
+            Action::PlayPause => "play_pause",
+            Action::PrevTrack => "prev_track",
+            Action::NextTrack => "next_track",
+            Action::Restart => "restart",
+            Action::Loop => "loop",
+            Action::Shuffle => "shuffle",
+            Action::SeekBack => "seek_back",
+            Action::SeekForward => "seek_forward",
+            Action::SeekBackLong => "seek_back_long",
+            Action::SeekForwardLong => "seek_forward_long",
+            Action::VolumeMode => "volume_mode",
+            Action::LyricsView => "lyrics_view",
+            Action::LyricsSync => "lyrics_sync",
+            Action::LyricsScrollDown => "lyrics_scroll_down",
+            Action::LyricsScrollUp => "lyrics_scroll_up",
+            Action::LyricsSooner => "lyrics_sooner",
+            Action::LyricsLater => "lyrics_later",
+            Action::SaveLyricsOffset => "save_lyrics_offset",
+            Action::SelectUp => "select_up",
+            Action::SelectDown => "select_down",
+            Action::SelectFirst => "select_first",
+            Action::SelectLast => "select_last",
+            Action::VisualMode => "visual_mode",
+            Action::ExtendUp => "extend_up",
+            Action::ExtendDown => "extend_down",
+            Action::PlaySelected => "play_selected",
+            Action::Enqueue => "enqueue",
+            Action::CycleSort => "cycle_sort",
+            Action::ReverseSort => "reverse_sort",
+            Action::Favorite => "favorite",
+            Action::FavoritesView => "favorites_view",
+            Action::SearchNext => "search_next",
+            Action::SearchPrev => "search_prev",
+            Action::SearchPlayAll => "search_play_all",
+            Action::SearchEnqueueAll => "search_enqueue_all",
+            Action::SearchEdit => "search_edit",
+            Action::StatsWeek => "stats_week",
+            Action::StatsMonth => "stats_month",
+            Action::StatsAllTime => "stats_all_time",
+            Action::SyncStamp => "sync_stamp",
+            Action::SyncUndo => "sync_undo",
+            Action::SyncWrite => "sync_write",
+            Action::TagClearField => "tag_clear_field",
+            Action::OrganizeConflicts => "organize_conflicts",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Action> {
+        let s = s.trim().to_ascii_lowercase().replace('-', "_");
+        Action::ALL.into_iter().find(|a| a.name() == s)
+    }
+
+    /// The help overlay section this action is listed under.
+    pub fn section(self) -> &'static str {
+        use Action::*;
+        match self {
+            Help | Quit | CommandLine | Search | Filter | ClearFilter | MoveToTime
+            | YoutubeDownload | Delete | UndoDelete | SkippedFiles | TrackInfo | History | Stats
+            | EditTags | Mark | Organize | AddToPlaylist | MoveToFolder => "General",
+            PlayPause | PrevTrack | NextTrack | Restart | Loop | Shuffle | SeekBack
+            | SeekForward | SeekBackLong | SeekForwardLong | VolumeMode => "Playback",
+            LyricsView | LyricsSync | LyricsScrollDown | LyricsScrollUp | LyricsSooner
+            | LyricsLater | SaveLyricsOffset => "Lyrics",
+            SelectUp | SelectDown | SelectFirst | SelectLast | VisualMode | ExtendUp
+            | ExtendDown | PlaySelected | Enqueue | CycleSort | ReverseSort => "Library",
+            Favorite | FavoritesView => "Favourites",
+            SearchNext | SearchPrev | SearchPlayAll | SearchEnqueueAll | SearchEdit => "Search",
+            StatsWeek | StatsMonth | StatsAllTime => "Listening stats",
+            SyncStamp | SyncUndo | SyncWrite => "Sync recorder",
+            TagClearField => "Tag editor",
+            OrganizeConflicts => "Organize",
+        }
+    }
+
+    /// The view the action is bound in.
+    pub fn scope(self) -> KeyScope {
+        use Action::*;
+        match self {
+            SearchNext | SearchPrev | SearchPlayAll | SearchEnqueueAll | SearchEdit => {
+                KeyScope::Search
+            }
+            StatsWeek | StatsMonth | StatsAllTime => KeyScope::Stats,
+            SyncStamp | SyncUndo | SyncWrite => KeyScope::LyricsSync,
+            TagClearField => KeyScope::TagEditor,
+            OrganizeConflicts => KeyScope::Organize,
+            _ => KeyScope::Main,
+        }
+    }
+
+    /// One line for the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Help => "toggle this help",
+            Action::Quit => "quit",
+            Action::CommandLine => {
+                "command line (:seek 1:30, :vol 60, :sort artist; Tab completes)"
+            }
+            Action::Search => "fuzzy search names, tags and lyrics",
+            Action::Filter => "filter the list and play order (e.g. artist:foo dur:>5m)",
+            Action::ClearFilter => "clear the filter",
+            Action::MoveToTime => "move to timestamp (opens :seek, e.g. 1:30)",
+            Action::YoutubeDownload => "youtube download (opens :yt, paste URL, Enter)",
+            Action::Delete => "move selected (or marked) tracks to the trash (press twice)",
+            Action::UndoDelete => "restore the tracks last moved to the trash",
+            Action::SkippedFiles => "show files that can't be decoded",
+            Action::TrackInfo => "track info (tags, codec, bitrate) for selected track",
+            Action::History => "play history (Enter plays, E enqueues)",
+            Action::Stats => "listening stats (Tab switches week, month, all time)",
+            Action::EditTags => "edit tags of selected (or marked) tracks",
+            Action::Mark => "mark/unmark selected track for batch actions",
+            Action::Organize => "organize selected (or marked) files into folders by tags",
+            Action::AddToPlaylist => "add selected (or marked) tracks to a playlist (opens :add)",
+            Action::MoveToFolder => "move selected (or marked) files to a folder (opens :mv)",
+            Action::PlayPause => "pause/resume",
+            Action::PrevTrack => "previous track",
+            Action::NextTrack => "next track",
+            Action::Restart => "restart current track",
+            Action::Loop => "loop selected/current",
+            Action::Shuffle => "toggle shuffle order",
+            Action::SeekBack => "seek -5s",
+            Action::SeekForward => "seek +5s",
+            Action::SeekBackLong => "seek -10s",
+            Action::SeekForwardLong => "seek +10s",
+            Action::VolumeMode => "enter/exit volume mode (select up/down then change volume)",
+            Action::LyricsView => "full-screen lyrics (↑/↓ pick a line, Enter seeks to it)",
+            Action::LyricsSync => "sync plain lyrics (track.txt) line by line into a .lrc",
+            Action::LyricsScrollDown => "scroll plain (unsynced) lyrics down",
+            Action::LyricsScrollUp => "scroll plain (unsynced) lyrics up",
+            Action::LyricsSooner => "show synced lyrics 100ms sooner",
+            Action::LyricsLater => "show synced lyrics 100ms later",
+            Action::SaveLyricsOffset => "save lyrics timing to the .lrc file",
+            Action::SelectUp => "select previous track",
+            Action::SelectDown => "select next track",
+            Action::SelectFirst => "select first track",
+            Action::SelectLast => "select last track",
+            Action::VisualMode => "visual selection: mark the range the selection moves over",
+            Action::ExtendUp => "extend the marked range up",
+            Action::ExtendDown => "extend the marked range down",
+            Action::PlaySelected => "play selected",
+            Action::Enqueue => "add selected (or marked) tracks to the play queue",
+            Action::CycleSort => {
+                "cycle sort (path, name, title, artist, album, duration, added, plays, skips, played, rating)"
+            }
+            Action::ReverseSort => "reverse sort order",
+            Action::Favorite => "add/remove selected (or marked) tracks from favourites",
+            Action::FavoritesView => "toggle favourites view",
+            Action::SearchNext => "next result, wrapping around",
+            Action::SearchPrev => "previous result, wrapping around",
+            Action::SearchPlayAll => "play all results (Ctrl+A while typing)",
+            Action::SearchEnqueueAll => "add all results to the queue (Ctrl+E while typing)",
+            Action::SearchEdit => "edit the query again",
+            Action::StatsWeek => "show the last 7 days",
+            Action::StatsMonth => "show the last 30 days",
+            Action::StatsAllTime => "show all time",
+            Action::SyncStamp => "stamp the next line as it starts (Enter too)",
+            Action::SyncUndo => "undo the last stamp (Backspace too)",
+            Action::SyncWrite => "write the stamps to track.lrc",
+            Action::TagClearField => "clear the field, which removes the tag on save",
+            Action::OrganizeConflicts => "switch between skipping and numbering clashing files",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Help => &["h", "?"],
+            Action::Quit => &["q"],
+            Action::CommandLine => &[":"],
+            Action::Search => &["S"],
+            Action::Filter => &["f"],
+            Action::ClearFilter => &["F"],
+            Action::MoveToTime => &["m"],
+            Action::YoutubeDownload => &["y"],
+            Action::Delete => &["D"],
+            Action::UndoDelete => &["u"],
+            Action::SkippedFiles => &["x"],
+            Action::TrackInfo => &["i"],
+            Action::History => &["H"],
+            Action::Stats => &["C"],
+            Action::EditTags => &["e"],
+            Action::Mark => &["M"],
+            Action::Organize => &["R"],
+            Action::AddToPlaylist => &["A"],
+            Action::MoveToFolder => &["d"],
+            Action::PlayPause => &["space"],
+            Action::PrevTrack => &["P"],
+            Action::NextTrack => &["N"],
+            Action::Restart => &["r"],
+            Action::Loop => &["l"],
+            Action::Shuffle => &["s"],
+            Action::SeekBack => &["left"],
+            Action::SeekForward => &["right"],
+            Action::SeekBackLong => &["p"],
+            Action::SeekForwardLong => &["n"],
+            Action::VolumeMode => &["v"],
+            Action::LyricsView => &["L"],
+            Action::LyricsSync => &["T"],
+            Action::LyricsScrollDown => &["J"],
+            Action::LyricsScrollUp => &["K"],
+            Action::LyricsSooner => &["["],
+            Action::LyricsLater => &["]"],
+            Action::SaveLyricsOffset => &["W"],
+            Action::SelectUp => &["up", "k"],
+            Action::SelectDown => &["down", "j"],
+            Action::SelectFirst => &["g g", "home"],
+            Action::SelectLast => &["G", "end"],
+            Action::VisualMode => &["V"],
+            Action::ExtendUp => &["shift+up"],
+            Action::ExtendDown => &["shift+down"],
+            Action::PlaySelected => &["enter"],
+            Action::Enqueue => &["E"],
+            Action::CycleSort => &["o"],
+            Action::ReverseSort => &["O"],
+            Action::Favorite => &["a"],
+            Action::FavoritesView => &["tab"],
+            Action::SearchNext => &["n"],
+            Action::SearchPrev => &["N"],
+            Action::SearchPlayAll => &["A"],
+            Action::SearchEnqueueAll => &["E"],
+            Action::SearchEdit => &["S", "/"],
+            Action::StatsWeek => &["w"],
+            Action::StatsMonth => &["m"],
+            Action::StatsAllTime => &["a"],
+            Action::SyncStamp => &["."],
+            Action::SyncUndo => &["u"],
+            Action::SyncWrite => &["w"],
+            Action::TagClearField => &["ctrl+u"],
+            Action::OrganizeConflicts => &["c"],
+        }
+    }
+}
+
+/// One key press with its modifiers. Shift is folded into the character for
+/// letter keys (`N`, not `shift+n`), as terminals report it that way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn from_event(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) => {
+                let shifted = modifiers.contains(KeyModifiers::SHIFT);
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(if shifted { c.to_ascii_uppercase() } else { c })
+            }
+            // Terminals send Shift+Tab as BackTab.
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses `q`, `N`, `space`, `ctrl+n`, `alt+enter`, `shift+tab`, `f5`, `pgdn`, ...
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // Split off `mod+`, but keep a lone `+` (or `ctrl++`) as the key.
+        while let Some((head, tail)) = rest.split_once('+').filter(|(_, t)| !t.is_empty()) {
+            modifiers |= match head.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "c" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {head:?} in {s:?}")),
+            };
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" | "bs" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "plus" => KeyCode::Char('+'),
+                "minus" => KeyCode::Char('-'),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=24) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key {rest:?}")),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    fn is_plain_char(self) -> bool {
+        matches!(self.code, KeyCode::Char(c) if c != ' ') && self.modifiers.is_empty()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::Delete => f.write_str("Del"),
+            KeyCode::Insert => f.write_str("Ins"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Parses a binding: one chord, or a sequence of them separated by spaces
+/// (`g g`). A run of plain characters is a sequence too (`gg`), except for
+/// key names like `end`.
+fn parse_sequence(s: &str) -> Result<Vec<KeyChord>, String> {
+    let mut out = Vec::new();
+    for token in s.split_whitespace() {
+        match KeyChord::parse(token) {
+            Ok(chord) => out.push(chord),
+            Err(_) if !token.contains('+') && token.chars().count() > 1 => {
+                out.extend(
+                    token
+                        .chars()
+                        .map(|c| KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE)),
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if out.is_empty() {
+        return Err("empty key".to_string());
+    }
+    Ok(out)
+}
+
+/// Shows a sequence the way it is typed: `gg`, `Ctrl+x`, `Space`.
+fn sequence_label(keys: &[KeyChord]) -> String {
+    let plain = keys.len() > 1 && keys.iter().all(|k| k.is_plain_char());
+    let labels: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    labels.join(if plain { "" } else { " " })
+}
+
+/// Keys that always do the same thing and can't be rebound.
+const RESERVED: [(KeyCode, KeyModifiers, &str); 2] = [
+    (KeyCode::Char('c'), KeyModifiers::CONTROL, "quits"),
+    (KeyCode::F(12), KeyModifiers::NONE, "hides to the shell"),
+];
+
+/// What the keys typed so far lead to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMatch {
+    Action(Action),
+    /// The start of a longer binding; wait for the next key.
+    Prefix,
+    None,
+}
+
+/// The active key bindings, in the order they are listed in help.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Vec<KeyChord>, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .flat_map(|action| {
+                action.default_keys().iter().map(move |key| {
+                    let seq = parse_sequence(key).expect("default key bindings are valid");
+                    (seq, action)
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    fn get(&self, scope: KeyScope, keys: &[KeyChord]) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(seq, action)| seq == keys && action.scope() == scope)
+            .map(|&(_, action)| action)
+    }
+
+    fn bind(&mut self, keys: Vec<KeyChord>, action: Action) -> Option<Action> {
+        let old = self.unbind(action.scope(), &keys);
+        self.bindings.push((keys, action));
+        old
+    }
+
+    fn unbind(&mut self, scope: KeyScope, keys: &[KeyChord]) -> Option<Action> {
+        let at = self
+            .bindings
+            .iter()
+            .position(|(seq, action)| seq == keys && action.scope() == scope)?;
+        Some(self.bindings.remove(at).1)
+    }
+
+    /// Looks up the keys typed so far among the bindings of `scope`.
+    pub(crate) fn resolve(&self, scope: KeyScope, keys: &[KeyChord]) -> KeyMatch {
+        if let Some(action) = self.get(scope, keys) {
+            return KeyMatch::Action(action);
+        }
+        let longer = self.bindings.iter().any(|(seq, action)| {
+            action.scope() == scope && seq.len() > keys.len() && seq.starts_with(keys)
+        });
+        if longer {
+            KeyMatch::Prefix
+        } else {
+            KeyMatch::None
+        }
+    }
+
+    /// Labels of the keys bound to `action`.
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, a)| a == action)
+            .map(|(seq, _)| sequence_label(seq))
+            .collect()
+    }
+
+    /// The first key bound to `action`, for hints; `-` when unbound.
+    pub fn label(&self, action: Action) -> String {
+        self.keys_for(action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    /// Applies a `[keys]` table of `"key" = "action"` entries on top of the
+    /// defaults; `"none"` unbinds a key. Views have tables of their own, like
+    /// `[keys.search]`. Problems are reported as warnings.
+    pub(super) fn apply(&mut self, mut table: toml::Table) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut views = Vec::new();
+        for scope in KeyScope::VIEWS {
+            if let Some(view) = table.remove(scope.name()) {
+                match view {
+                    toml::Value::Table(view) => views.push((scope, view)),
+                    _ => warnings.push(format!("keys: {:?}: expected a table", scope.name())),
+                }
+            }
+        }
+
+        let main_keys = self.apply_scope(KeyScope::Main, table, &mut warnings);
+        for (scope, view) in views {
+            self.apply_scope(scope, view, &mut warnings);
+        }
+
+        warnings.extend(self.conflicts());
+        warnings.extend(self.shadowed(&main_keys));
+        warnings
+    }
+
+    /// Applies the entries of one table; returns the keys it bound.
+    fn apply_scope(
+        &mut self,
+        scope: KeyScope,
+        table: toml::Table,
+        warnings: &mut Vec<String>,
+    ) -> Vec<Vec<KeyChord>> {
+        let prefix = match scope {
+            KeyScope::Main => "keys".to_string(),
+            view => format!("keys.{}", view.name()),
+        };
+        let mut bound = Vec::new();
+        let mut seen: Vec<(Vec<KeyChord>, String)> = Vec::new();
+
+        for (key, value) in table {
+            let Some(value) = value.as_str() else {
+                warnings.push(format!("{prefix}: {key:?}: expected an action name"));
+                continue;
+            };
+            let seq = match parse_sequence(&key) {
+                Ok(seq) => seq,
+                Err(e) => {
+                    warnings.push(format!("{prefix}: {e}"));
+                    continue;
+                }
+            };
+            if let Some(&(_, _, what)) = RESERVED
+                .iter()
+                .find(|(code, mods, _)| seq == [KeyChord::new(*code, *mods)])
+            {
+                warnings.push(format!(
+                    "{prefix}: {key:?} always {what} and can't be rebound"
+                ));
+                continue;
+            }
+            if let Some((_, other)) = seen.iter().find(|(s, _)| *s == seq) {
+                warnings.push(format!(
+                    "{prefix}: {other:?} and {key:?} are the same key; using {key:?} = {value:?}"
+                ));
+            }
+            seen.push((seq.clone(), key.clone()));
+
+            if matches!(value.trim(), "" | "none" | "unbound") {
+                self.unbind(scope, &seq);
+                continue;
+            }
+            let Some(action) = Action::parse(value) else {
+                warnings.push(format!("{prefix}: unknown action {value:?} for {key:?}"));
+                continue;
+            };
+            if action.scope() != scope {
+                let table = match action.scope() {
+                    KeyScope::Main => "[keys]".to_string(),
+                    view => format!("[keys.{}]", view.name()),
+                };
+                warnings.push(format!(
+                    "{prefix}: {} is bound under {table}, not here",
+                    action.name()
+                ));
+                continue;
+            }
+            bound.push(seq.clone());
+            if let Some(old) = self.bind(seq, action) {
+                if old != action && self.keys_for(old).is_empty() {
+                    warnings.push(format!(
+                        "{prefix}: {key:?} was the only key for {}, which is now unbound",
+                        old.name()
+                    ));
+                }
+            }
+        }
+        bound
+    }
+
+    /// Bindings that can never fire because a shorter one of the same view is
+    /// a prefix of them.
+    fn conflicts(&self) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter_map(|(seq, action)| {
+                let (prefix, shadow) = (1..seq.len())
+                    .find_map(|n| Some((&seq[..n], self.get(action.scope(), &seq[..n])?)))?;
+                Some(format!(
+                    "keys: {:?} ({}) can never fire because {:?} is bound to {}",
+                    sequence_label(seq),
+                    action.name(),
+                    sequence_label(prefix),
+                    shadow.name()
+                ))
+            })
+            .collect()
+    }
+
+    /// Keys set in `[keys]` that a view reads as one of its own, so the main
+    /// binding doesn't fire there.
+    fn shadowed(&self, main_keys: &[Vec<KeyChord>]) -> Vec<String> {
+        main_keys
+            .iter()
+            .filter_map(|seq| {
+                let main = self.get(KeyScope::Main, seq)?;
+                let (scope, view) = KeyScope::VIEWS
+                    .into_iter()
+                    .find_map(|scope| Some((scope, self.get(scope, seq)?)))?;
+                Some(format!(
+                    "keys: {:?} is {} in {}, so {} doesn't fire there (rebind it under [keys.{}])",
+                    sequence_label(seq),
+                    view.name(),
+                    scope.label(),
+                    main.name(),
+                    scope.name()
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    fn plain(c: char) -> KeyChord {
+        chord(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn apply(toml: &str) -> (KeyMap, Vec<String>) {
+        let mut keys = KeyMap::default();
+        let warnings = keys.apply(toml.parse().unwrap());
+        (keys, warnings)
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        let ctrl_n = chord(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(KeyChord::parse("ctrl+n"), Ok(ctrl_n));
+        assert_eq!(KeyChord::parse("C+n"), Ok(ctrl_n));
+        assert_eq!(
+            KeyChord::parse("ctrl+alt+right"),
+            Ok(chord(
+                KeyCode::Right,
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ))
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl++"),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(KeyChord::parse("+"), Ok(plain('+')));
+        // Shift is folded into letters and into Tab.
+        assert_eq!(KeyChord::parse("shift+n"), Ok(plain('N')));
+        assert_eq!(KeyChord::parse("N"), Ok(plain('N')));
+        assert_eq!(
+            KeyChord::parse("shift+tab"),
+            Ok(chord(KeyCode::BackTab, KeyModifiers::NONE))
+        );
+    }
+
+    #[test]
+    fn parses_named_keys() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(KeyChord::parse("space"), Ok(plain(' ')));
+        assert_eq!(KeyChord::parse("Enter"), Ok(chord(KeyCode::Enter, none)));
+        assert_eq!(KeyChord::parse("pgdn"), Ok(chord(KeyCode::PageDown, none)));
+        assert_eq!(KeyChord::parse("f12"), Ok(chord(KeyCode::F(12), none)));
+        assert_eq!(KeyChord::parse("minus"), Ok(plain('-')));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_modifiers() {
+        assert_eq!(
+            KeyChord::parse("hyper+x").unwrap_err(),
+            "unknown modifier \"hyper\" in \"hyper+x\""
+        );
+        assert_eq!(KeyChord::parse("f25").unwrap_err(), "unknown key \"f25\"");
+        assert!(KeyChord::parse("ctrl+foo").is_err());
+        assert!(KeyChord::parse("").is_err());
+    }
+
+    #[test]
+    fn a_run_of_plain_characters_is_a_sequence() {
+        assert_eq!(parse_sequence("gg"), Ok(vec![plain('g'), plain('g')]));
+        assert_eq!(parse_sequence("g g"), Ok(vec![plain('g'), plain('g')]));
+        assert_eq!(
+            parse_sequence("g ctrl+x"),
+            Ok(vec![
+                plain('g'),
+                chord(KeyCode::Char('x'), KeyModifiers::CONTROL)
+            ])
+        );
+        // Key names stay one key.
+        assert_eq!(
+            parse_sequence("end"),
+            Ok(vec![chord(KeyCode::End, KeyModifiers::NONE)])
+        );
+        assert!(parse_sequence("ctrl+xy").is_err());
+        assert!(parse_sequence(" ").is_err());
+        assert_eq!(sequence_label(&parse_sequence("gg").unwrap()), "gg");
+    }
+
+    #[test]
+    fn resolves_prefixes_and_scopes() {
+        let keys = KeyMap::default();
+        let g = plain('g');
+        assert_eq!(keys.resolve(KeyScope::Main, &[g]), KeyMatch::Prefix);
+        assert_eq!(
+            keys.resolve(KeyScope::Main, &[g, g]),
+            KeyMatch::Action(Action::SelectFirst)
+        );
+        assert_eq!(
+            keys.resolve(KeyScope::Main, &[g, plain('x')]),
+            KeyMatch::None
+        );
+        assert_eq!(keys.resolve(KeyScope::Main, &[plain('Z')]), KeyMatch::None);
+        // The same key means different things in a view.
+        assert_eq!(
+            keys.resolve(KeyScope::Main, &[plain('n')]),
+            KeyMatch::Action(Action::SeekForwardLong)
+        );
+        assert_eq!(
+            keys.resolve(KeyScope::Search, &[plain('n')]),
+            KeyMatch::Action(Action::SearchNext)
+        );
+        assert_eq!(keys.resolve(KeyScope::Search, &[g]), KeyMatch::None);
+    }
+
+    #[test]
+    fn the_defaults_have_no_warnings() {
+        assert_eq!(apply("").1, Vec::<String>::new());
+    }
+
+    #[test]
+    fn warns_when_a_key_hides_a_longer_binding() {
+        let (keys, warnings) = apply("g = \"help\"");
+        assert_eq!(
+            warnings,
+            ["keys: \"gg\" (select_first) can never fire because \"g\" is bound to help"]
+        );
+        assert_eq!(
+            keys.resolve(KeyScope::Main, &[plain('g')]),
+            KeyMatch::Action(Action::Help)
+        );
+
+        // A view's keys don't hide main ones.
+        let (_, warnings) = apply("[search]\ng = \"search_next\"");
+        assert_eq!(warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn view_keys_go_in_their_own_table() {
+        let (keys, warnings) = apply("[search]\nn = \"none\"\nx = \"search_next\"");
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(
+            keys.resolve(KeyScope::Search, &[plain('n')]),
+            KeyMatch::None
+        );
+        assert_eq!(
+            keys.resolve(KeyScope::Search, &[plain('x')]),
+            KeyMatch::Action(Action::SearchNext)
+        );
+
+        let (_, warnings) = apply("x = \"search_next\"");
+        assert_eq!(
+            warnings,
+            ["keys: search_next is bound under [keys.search], not here"]
+        );
+    }
+
+    #[test]
+    fn warns_when_a_view_shadows_a_main_key() {
+        let (_, warnings) = apply("w = \"next_track\"");
+        assert_eq!(
+            warnings,
+            [
+                "keys: \"w\" is stats_week in the listening stats, so next_track doesn't fire \
+              there (rebind it under [keys.stats])"
+            ]
+        );
+    }
+}
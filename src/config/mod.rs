@@ -1,22 +1,96 @@
+mod keys;
+
 use std::{env, fs, path::PathBuf};
 
 use anyhow::{Context, Result};
 use ratatui::style::Color;
 use serde::Deserialize;
 
+pub use keys::{Action, KeyChord, KeyMap, KeyMatch, KeyScope, SECTIONS};
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub theme: Theme,
+    pub library: LibraryConfig,
+    pub ui: UiConfig,
+    pub keys: KeyMap,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             theme: Theme::default(),
+            library: LibraryConfig::default(),
+            ui: UiConfig::default(),
+            keys: KeyMap::default(),
+        }
+    }
+}
+
+/// `[library]` section: where to look for music and what to skip.
+#[derive(Debug, Clone)]
+pub struct LibraryConfig {
+    /// Directories (or files) to scan. Empty means "use the default music dir".
+    pub roots: Vec<PathBuf>,
+    /// Glob patterns for paths to leave out, e.g. `**/Samples/**`.
+    pub exclude: Vec<String>,
+    /// Maximum directory depth below each root (`None` = unlimited).
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    /// How list entries are named, e.g. `{artist} - {title}`. `None` shows file names.
+    pub display: Option<String>,
+    /// Where the organize action moves files, e.g. `{artist}/{album}/{title}.{ext}`.
+    pub organize: Option<String>,
+    /// Delete files outright instead of moving them to the trash.
+    pub hard_delete: bool,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: None,
+            follow_symlinks: true,
+            display: None,
+            organize: None,
+            hard_delete: false,
         }
     }
 }
 
+/// `[ui]` section.
+#[derive(Debug, Clone, Default)]
+pub struct UiConfig {
+    pub album_art: AlbumArtMode,
+}
+
+/// How the cover art panel draws images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlbumArtMode {
+    /// Kitty graphics or sixel if the terminal looks capable, else half blocks.
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    /// Unicode half blocks (`▀`) in 24-bit color; works everywhere.
+    Blocks,
+    Off,
+}
+
+impl AlbumArtMode {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => AlbumArtMode::Auto,
+            "kitty" => AlbumArtMode::Kitty,
+            "sixel" => AlbumArtMode::Sixel,
+            "blocks" | "halfblocks" | "half-blocks" => AlbumArtMode::Blocks,
+            "off" | "none" | "false" => AlbumArtMode::Off,
+            _ => return None,
+        })
+    }
+}
+
 impl Config {
     pub fn load() -> Self {
         let Some(path) = config_path() else {
@@ -99,6 +173,26 @@ impl Default for Theme {
 #[derive(Debug, Default, Deserialize)]
 struct RawConfig {
     theme: Option<RawTheme>,
+    library: Option<RawLibrary>,
+    ui: Option<RawUi>,
+    /// `"key" = "action"` entries.
+    keys: Option<toml::Table>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawUi {
+    album_art: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawLibrary {
+    roots: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    follow_symlinks: Option<bool>,
+    display: Option<String>,
+    organize: Option<String>,
+    hard_delete: Option<bool>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -138,10 +232,71 @@ fn load_from_path(path: &PathBuf) -> Result<Config> {
     if let Some(theme) = raw.theme {
         apply_theme(&mut cfg.theme, theme);
     }
+    if let Some(library) = raw.library {
+        apply_library(&mut cfg.library, library);
+    }
+    if let Some(ui) = raw.ui {
+        apply_ui(&mut cfg.ui, ui);
+    }
+    if let Some(keys) = raw.keys {
+        for warning in cfg.keys.apply(keys) {
+            eprintln!("trix: {warning}");
+        }
+    }
 
     Ok(cfg)
 }
 
+fn apply_library(out: &mut LibraryConfig, raw: RawLibrary) {
+    if let Some(roots) = raw.roots {
+        out.roots = roots.iter().map(|r| expand_home(r)).collect();
+    }
+    if let Some(exclude) = raw.exclude {
+        out.exclude = exclude;
+    }
+    if raw.max_depth.is_some() {
+        out.max_depth = raw.max_depth;
+    }
+    if let Some(follow) = raw.follow_symlinks {
+        out.follow_symlinks = follow;
+    }
+    if let Some(display) = raw.display.filter(|d| !d.trim().is_empty()) {
+        out.display = Some(display);
+    }
+    if let Some(organize) = raw.organize.filter(|o| !o.trim().is_empty()) {
+        out.organize = Some(organize);
+    }
+    if let Some(hard) = raw.hard_delete {
+        out.hard_delete = hard;
+    }
+}
+
+fn apply_ui(out: &mut UiConfig, raw: RawUi) {
+    if let Some(mode) = raw.album_art {
+        match AlbumArtMode::parse(&mode) {
+            Some(m) => out.album_art = m,
+            None => eprintln!("trix: ignoring invalid ui.album_art: {mode}"),
+        }
+    }
+}
+
+/// Expands a leading `~` or `$HOME` so config paths can be written portably.
+fn expand_home(path: &str) -> PathBuf {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    match home {
+        Some(home) => {
+            if path == "~" || path == "$HOME" {
+                home
+            } else if let Some(rest) = path.strip_prefix("~/").or_else(|| path.strip_prefix("$HOME/")) {
+                home.join(rest)
+            } else {
+                PathBuf::from(path)
+            }
+        }
+        None => PathBuf::from(path),
+    }
+}
+
 fn apply_theme(out: &mut Theme, raw: RawTheme) {
     apply_color(&mut out.background, raw.background, "theme.background");
 
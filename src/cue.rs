@@ -0,0 +1,370 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// One `TRACK` entry of a CUE sheet, resolved against the audio file it slices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CueTrack {
+    pub(crate) number: u32,
+    /// How many tracks the whole sheet has.
+    pub(crate) total: u32,
+    pub(crate) title: Option<String>,
+    pub(crate) performer: Option<String>,
+    /// Album title (the sheet-level `TITLE`).
+    pub(crate) album: Option<String>,
+    /// Offset of `INDEX 01` within the audio file.
+    pub(crate) start: Duration,
+    /// Start of the next track in the same file, or `None` to play to the end.
+    pub(crate) end: Option<Duration>,
+}
+
+impl CueTrack {
+    /// Length of the slice, if it has a known end.
+    pub(crate) fn length(&self) -> Option<Duration> {
+        self.end.map(|end| end.saturating_sub(self.start))
+    }
+}
+
+/// A parsed CUE sheet: the virtual tracks grouped by the audio file they live in.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CueSheet {
+    pub(crate) files: Vec<(PathBuf, Vec<CueTrack>)>,
+}
+
+/// Loads and parses a `.cue` file.
+///
+/// `FILE` entries are resolved relative to the sheet's directory. Many rips reference
+/// the original `.wav` even though the file was later encoded to FLAC, so if the
+/// named file is missing we also try the same stem with any other extension that
+/// `is_audio` accepts. Files that cannot be found are dropped.
+pub(crate) fn load_cue(cue_path: &Path, is_audio: impl Fn(&Path) -> bool) -> Option<CueSheet> {
+    let bytes = fs::read(cue_path).ok()?;
+    // CUE sheets are frequently Latin-1/CP1252; lossy decoding keeps ASCII intact.
+    let content = String::from_utf8_lossy(&bytes);
+    let dir = cue_path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut sheet = parse_cue(&content);
+    sheet.files.retain_mut(|(file, _)| {
+        match resolve_file(dir, file, &is_audio) {
+            Some(p) => {
+                *file = p;
+                true
+            }
+            None => false,
+        }
+    });
+
+    if sheet.files.iter().all(|(_, tracks)| tracks.is_empty()) {
+        return None;
+    }
+    Some(sheet)
+}
+
+fn resolve_file(dir: &Path, name: &Path, is_audio: &impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    let direct = dir.join(name);
+    if direct.is_file() {
+        return Some(direct);
+    }
+
+    let stem = direct.file_stem()?.to_os_string();
+    let parent = direct.parent()?;
+    let mut candidates: Vec<PathBuf> = fs::read_dir(parent)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.file_stem() == Some(stem.as_os_str()) && p.is_file() && is_audio(p))
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next()
+}
+
+/// Parses CUE sheet text.
+///
+/// Supports:
+/// - Sheet-level `PERFORMER`/`TITLE` (used as album artist / album)
+/// - Multiple `FILE` entries, each with their own `TRACK`s
+/// - `INDEX 01` as the track start (falls back to `INDEX 00` if 01 is missing)
+/// - Data tracks (`TRACK nn MODE1/2352` and the like) are left out, but still
+///   end the audio track before them
+/// - Quoted and unquoted values; commands are case-insensitive
+pub(crate) fn parse_cue(content: &str) -> CueSheet {
+    struct Pending {
+        number: u32,
+        /// Data tracks are kept until the end so their `INDEX` lines don't land
+        /// on the track before.
+        audio: bool,
+        title: Option<String>,
+        performer: Option<String>,
+        index0: Option<Duration>,
+        index1: Option<Duration>,
+    }
+
+    let mut album: Option<String> = None;
+    let mut album_performer: Option<String> = None;
+    let mut files: Vec<(PathBuf, Vec<Pending>)> = Vec::new();
+
+    for raw_line in content.trim_start_matches('\u{feff}').lines() {
+        let line = raw_line.trim();
+        let (cmd, rest) = match line.split_once(char::is_whitespace) {
+            Some((c, r)) => (c, r.trim()),
+            None => (line, ""),
+        };
+
+        match cmd.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                // FILE "name.flac" WAVE — the file type is the last word.
+                let name = match rest.rsplit_once(char::is_whitespace) {
+                    Some((name, _kind)) => unquote(name),
+                    None => unquote(rest),
+                };
+                files.push((PathBuf::from(name), Vec::new()));
+            }
+            "TRACK" => {
+                let Some((_, tracks)) = files.last_mut() else { continue };
+                let mut parts = rest.split_whitespace();
+                let number = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                let is_audio = parts
+                    .next()
+                    .map(|k| k.eq_ignore_ascii_case("AUDIO"))
+                    .unwrap_or(true);
+                tracks.push(Pending {
+                    number,
+                    audio: is_audio,
+                    title: None,
+                    performer: None,
+                    index0: None,
+                    index1: None,
+                });
+            }
+            "TITLE" | "PERFORMER" => {
+                let value = unquote(rest);
+                if value.is_empty() {
+                    continue;
+                }
+                let track = files.last_mut().and_then(|(_, t)| t.last_mut());
+                let is_title = cmd.eq_ignore_ascii_case("TITLE");
+                match (track, is_title) {
+                    (Some(t), true) => t.title = Some(value),
+                    (Some(t), false) => t.performer = Some(value),
+                    (None, true) => album = Some(value),
+                    (None, false) => album_performer = Some(value),
+                }
+            }
+            "INDEX" => {
+                let Some(track) = files.last_mut().and_then(|(_, t)| t.last_mut()) else {
+                    continue;
+                };
+                let mut parts = rest.split_whitespace();
+                let idx: Option<u32> = parts.next().and_then(|n| n.parse().ok());
+                let time = parts.next().and_then(parse_cue_time);
+                match (idx, time) {
+                    (Some(0), Some(t)) => track.index0 = Some(t),
+                    (Some(1), Some(t)) => track.index1 = Some(t),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut files: Vec<(PathBuf, Vec<CueTrack>)> = files
+        .into_iter()
+        .map(|(path, pending)| {
+            let starts: Vec<Option<Duration>> =
+                pending.iter().map(|p| p.index1.or(p.index0)).collect();
+            let tracks = pending
+                .into_iter()
+                .enumerate()
+                .filter(|(_, p)| p.audio)
+                .filter_map(|(i, p)| {
+                    let start = starts[i]?;
+                    let end = starts[i + 1..].iter().flatten().next().copied();
+                    Some(CueTrack {
+                        number: p.number,
+                        total: 0,
+                        title: p.title,
+                        performer: p.performer.or_else(|| album_performer.clone()),
+                        album: album.clone(),
+                        start,
+                        end: end.filter(|&e| e > start),
+                    })
+                })
+                .collect();
+            (path, tracks)
+        })
+        .collect();
+
+    let total = files.iter().map(|(_, tracks)| tracks.len()).sum::<usize>() as u32;
+    for track in files.iter_mut().flat_map(|(_, tracks)| tracks) {
+        track.total = total;
+    }
+    CueSheet { files }
+}
+
+fn unquote(s: &str) -> String {
+    let s = s.trim();
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+        .to_string()
+}
+
+/// Parses a CUE `mm:ss:ff` timestamp (75 frames per second).
+fn parse_cue_time(s: &str) -> Option<Duration> {
+    let mut parts = s.split(':');
+    let mm: u64 = parts.next()?.parse().ok()?;
+    let ss: u64 = parts.next()?.parse().ok()?;
+    let ff: u64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || ss >= 60 || ff >= 75 {
+        return None;
+    }
+    Some(Duration::from_secs(mm * 60 + ss) + Duration::from_nanos(ff * 1_000_000_000 / 75))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::util::TempDir;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn converts_frames_to_time() {
+        assert_eq!(parse_cue_time("00:00:00"), Some(Duration::ZERO));
+        assert_eq!(parse_cue_time("01:02:00"), Some(ms(62_000)));
+        assert_eq!(
+            parse_cue_time("00:00:74"),
+            Some(Duration::from_nanos(986_666_666))
+        );
+        assert_eq!(parse_cue_time("99:59:74").map(|d| d.as_secs()), Some(5999));
+        for bad in ["00:60:00", "00:00:75", "00:00", "00:00:00:00", "a:00:00"] {
+            assert_eq!(parse_cue_time(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn starts_at_index_01_and_leaves_the_pregap_to_the_track_before() {
+        let sheet = parse_cue(
+            "FILE \"album.flac\" WAVE\n\
+             TRACK 01 AUDIO\n  INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n  INDEX 00 02:58:00\n  INDEX 01 03:00:00\n\
+             TRACK 03 AUDIO\n  INDEX 00 05:00:00\n",
+        );
+        let tracks = &sheet.files[0].1;
+        let spans: Vec<_> = tracks.iter().map(|t| (t.number, t.start, t.end)).collect();
+        assert_eq!(
+            spans,
+            [
+                (1, Duration::ZERO, Some(ms(180_000))),
+                (2, ms(180_000), Some(ms(300_000))),
+                // Without INDEX 01, INDEX 00 is the start.
+                (3, ms(300_000), None),
+            ]
+        );
+        assert_eq!(tracks[1].length(), Some(ms(120_000)));
+    }
+
+    #[test]
+    fn drops_tracks_without_an_index() {
+        let sheet = parse_cue(
+            "FILE a.wav WAVE\n\
+             TRACK 01 AUDIO\n  INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n  TITLE \"No index\"\n\
+             TRACK 03 AUDIO\n  INDEX 01 04:00:00\n\
+             TRACK 04 MODE1/2352\n  INDEX 01 05:00:00\n",
+        );
+        let tracks = &sheet.files[0].1;
+        let numbers: Vec<u32> = tracks.iter().map(|t| t.number).collect();
+        assert_eq!(numbers, [1, 3]);
+        assert_eq!(tracks[0].end, Some(ms(240_000)));
+        // The data track's INDEX is its own, and it ends the audio before it.
+        assert_eq!(
+            (tracks[1].start, tracks[1].end),
+            (ms(240_000), Some(ms(300_000)))
+        );
+    }
+
+    #[test]
+    fn keeps_file_blocks_apart() {
+        let sheet = parse_cue(
+            "file \"one.flac\" wave\n\
+             track 01 audio\n  index 01 00:00:00\n\
+             track 02 audio\n  index 01 02:00:00\n\
+             FILE two.flac WAVE\n\
+             TRACK 03 AUDIO\n  INDEX 01 00:00:00\n",
+        );
+        let files: Vec<(&Path, usize)> = sheet
+            .files
+            .iter()
+            .map(|(p, t)| (p.as_path(), t.len()))
+            .collect();
+        assert_eq!(
+            files,
+            [(Path::new("one.flac"), 2), (Path::new("two.flac"), 1)]
+        );
+        // The last track of a file plays to its end, not to the next file's start.
+        assert_eq!(sheet.files[0].1[1].end, None);
+        assert_eq!(sheet.files[1].1[0].start, Duration::ZERO);
+        // Track totals count the whole sheet.
+        let totals: Vec<u32> = sheet
+            .files
+            .iter()
+            .flat_map(|(_, t)| t)
+            .map(|t| t.total)
+            .collect();
+        assert_eq!(totals, [3, 3, 3]);
+    }
+
+    #[test]
+    fn falls_back_to_the_sheet_performer() {
+        let sheet = parse_cue(
+            "\u{feff}PERFORMER \"Band\"\nTITLE \"Album\"\n\
+             FILE \"album.flac\" WAVE\n\
+             TRACK 01 AUDIO\n  TITLE \"One\"\n  PERFORMER \"Guest\"\n  INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n  TITLE Two\n  PERFORMER \"\"\n  INDEX 01 03:00:00\n",
+        );
+        let tracks = &sheet.files[0].1;
+        assert_eq!(tracks[0].performer.as_deref(), Some("Guest"));
+        assert_eq!(tracks[1].performer.as_deref(), Some("Band"));
+        assert_eq!(tracks[1].title.as_deref(), Some("Two"));
+        assert!(tracks.iter().all(|t| t.album.as_deref() == Some("Album")));
+    }
+
+    #[test]
+    fn leaves_out_an_end_that_is_not_after_the_start() {
+        let sheet = parse_cue(
+            "FILE a.flac WAVE\n\
+             TRACK 01 AUDIO\n  INDEX 01 03:00:00\n\
+             TRACK 02 AUDIO\n  INDEX 01 03:00:00\n\
+             TRACK 03 AUDIO\n  INDEX 01 01:00:00\n",
+        );
+        let ends: Vec<_> = sheet.files[0].1.iter().map(|t| t.end).collect();
+        assert_eq!(ends, [None, None, None]);
+    }
+
+    #[test]
+    fn resolves_files_next_to_the_sheet() {
+        let dir = TempDir::new("cue");
+        fs::write(dir.join("album.flac"), b"").unwrap();
+        fs::write(
+            dir.join("album.cue"),
+            "FILE \"album.wav\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n\
+             FILE \"missing.wav\" WAVE\nTRACK 02 AUDIO\nINDEX 01 00:00:00\n",
+        )
+        .unwrap();
+        let is_audio = |p: &Path| p.extension().is_some_and(|e| e == "flac");
+
+        let sheet = load_cue(&dir.join("album.cue"), is_audio).unwrap();
+        // The encoded file stands in for the `.wav` the sheet names.
+        assert_eq!(sheet.files.len(), 1);
+        assert_eq!(sheet.files[0].0, dir.join("album.flac"));
+
+        fs::write(dir.join("empty.cue"), "FILE \"album.flac\" WAVE\n").unwrap();
+        assert!(load_cue(&dir.join("empty.cue"), is_audio).is_none());
+        assert!(load_cue(&dir.join("none.cue"), is_audio).is_none());
+    }
+}
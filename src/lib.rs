@@ -4,11 +4,13 @@ compile_error!("trix-player is Linux-only. Build on Linux (target_os=\"linux\").
 mod app;
 mod audio;
 mod config;
+mod cue;
 mod library;
 mod lrc;
 mod meta;
 mod mpris;
 mod player;
+mod subtitles;
 mod term;
 mod ui;
 mod util;
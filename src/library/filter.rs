@@ -0,0 +1,536 @@
+//! Filter queries that narrow the library list and play order, such as
+//! `artist:foo album:"bar baz" dur:>5m fav:yes`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
+
+use super::{PlayStats, TagTemplate, Track, TrackKey};
+
+/// What a filter checks besides the track itself.
+pub(crate) struct FilterContext<'a> {
+    pub(crate) favorites: &'a HashSet<TrackKey>,
+    pub(crate) play_stats: &'a HashMap<TrackKey, PlayStats>,
+    pub(crate) template: Option<&'a TagTemplate>,
+}
+
+/// A parsed filter query. A track is listed when it matches every term.
+///
+/// Terms are separated by spaces; double quotes keep spaces in a value and a
+/// leading `-` negates a term. Plain words match the list name, title, artist
+/// or album. `field:value` terms match one field:
+///
+/// * text: `title:`, `artist:`, `album:`, `albumartist:`, `genre:`, `name:`
+///   (file name), `path:`, `ext:` — case-insensitive substring match
+/// * numbers: `dur:` (`5m`, `3:30`, `90s`), `year:`, `plays:`, `skips:`,
+///   `played:` (days since last played; never played tracks have no value),
+///   `rating:` (0–5 stars or 0–100) — exact, compared with `<`, `<=`, `>`, `>=`,
+///   or a range like `year:1990..1999`
+/// * `fav:yes` / `fav:no`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TrackFilter {
+    query: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negate: bool,
+    condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    /// Lowercase text to find in the field.
+    Text(TextField, String),
+    Number(NumberField, Bounds),
+    Favorite(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextField {
+    /// List name, title, artist or album.
+    Any,
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    FileName,
+    Path,
+    Extension,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberField {
+    /// In milliseconds.
+    Duration,
+    Year,
+    Plays,
+    Skips,
+    /// Whole days since the track was last played.
+    DaysSincePlayed,
+    /// 0–100, like `TrackMeta::rating`.
+    Rating,
+}
+
+/// Inclusive limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    min: Option<u64>,
+    max: Option<u64>,
+}
+
+impl Bounds {
+    fn contains(self, value: u64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+impl TrackFilter {
+    /// Parses `query`. Returns `Ok(None)` for a blank query.
+    pub(crate) fn parse(query: &str) -> Result<Option<Self>, String> {
+        let terms = split_terms(query)
+            .into_iter()
+            .map(|token| parse_term(&token))
+            .collect::<Result<Vec<_>, _>>()?;
+        if terms.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            query: query.trim().to_string(),
+            terms,
+        }))
+    }
+
+    /// The query as typed.
+    pub(crate) fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Whether matching looks at tags, which are loaded in the background.
+    pub(crate) fn needs_tags(&self) -> bool {
+        self.terms.iter().any(|t| match t.condition {
+            Condition::Text(field, _) => !matches!(
+                field,
+                TextField::FileName | TextField::Path | TextField::Extension
+            ),
+            Condition::Number(field, _) => matches!(
+                field,
+                NumberField::Duration | NumberField::Year | NumberField::Rating
+            ),
+            Condition::Favorite(_) => false,
+        })
+    }
+
+    pub(crate) fn matches(&self, track: &Track, ctx: &FilterContext) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.condition.matches(track, ctx) != term.negate)
+    }
+}
+
+impl Condition {
+    fn matches(&self, track: &Track, ctx: &FilterContext) -> bool {
+        let tags = track.tags.as_ref();
+        let stats = || {
+            ctx.play_stats
+                .get(&track.key())
+                .copied()
+                .unwrap_or_default()
+        };
+        match self {
+            Condition::Text(TextField::Any, needle) => [
+                Some(track.list_name(ctx.template)),
+                Some(track.title().to_string()),
+                track.artist().map(str::to_string),
+                track.album().map(str::to_string),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|text| contains(&text, needle)),
+            Condition::Text(field, needle) => {
+                let text = match field {
+                    TextField::Any => unreachable!("handled above"),
+                    TextField::Title => Some(track.title().to_string()),
+                    TextField::Artist => track.artist().map(str::to_string),
+                    TextField::Album => track.album().map(str::to_string),
+                    TextField::AlbumArtist => tags.and_then(|m| m.album_artist.clone()),
+                    TextField::Genre => tags.and_then(|m| m.genre.clone()),
+                    TextField::FileName => Some(track.display_name.clone()),
+                    TextField::Path => Some(track.path.display().to_string()),
+                    TextField::Extension => track
+                        .path
+                        .extension()
+                        .map(|e| e.to_string_lossy().to_string()),
+                };
+                text.is_some_and(|text| contains(&text, needle))
+            }
+            Condition::Number(field, bounds) => {
+                let value = match field {
+                    NumberField::Duration => track.duration().map(|d| d.as_millis() as u64),
+                    NumberField::Year => tags.and_then(|m| m.year()).map(u64::from),
+                    NumberField::Plays => Some(stats().plays.into()),
+                    NumberField::Skips => Some(stats().skips.into()),
+                    NumberField::DaysSincePlayed => stats().last_played.map(|t| {
+                        let ago = SystemTime::now().duration_since(t).unwrap_or_default();
+                        ago.as_secs() / 86_400
+                    }),
+                    NumberField::Rating => tags.and_then(|m| m.rating).map(u64::from),
+                };
+                value.is_some_and(|v| bounds.contains(v))
+            }
+            Condition::Favorite(yes) => ctx.favorites.contains(&track.key()) == *yes,
+        }
+    }
+}
+
+fn contains(text: &str, needle: &str) -> bool {
+    text.to_lowercase().contains(needle)
+}
+
+/// Splits on whitespace outside double quotes, dropping the quotes.
+fn split_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    terms
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    let (negate, token) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+    let condition = match token.split_once(':') {
+        Some((name, value)) if is_field_name(name) => parse_condition(name, value)?,
+        _ => Condition::Text(TextField::Any, token.to_lowercase()),
+    };
+    Ok(Term { negate, condition })
+}
+
+/// Field names are plain words, so a time like `12:30` stays plain text.
+fn is_field_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn parse_condition(name: &str, value: &str) -> Result<Condition, String> {
+    let text = |field| Ok(Condition::Text(field, value.to_lowercase()));
+    match name.to_ascii_lowercase().as_str() {
+        "title" | "t" => text(TextField::Title),
+        "artist" | "a" | "ar" => text(TextField::Artist),
+        "album" | "al" => text(TextField::Album),
+        "albumartist" | "aa" => text(TextField::AlbumArtist),
+        "genre" | "g" => text(TextField::Genre),
+        "name" | "file" | "filename" => text(TextField::FileName),
+        "path" => text(TextField::Path),
+        "ext" | "format" => text(TextField::Extension),
+        "dur" | "duration" | "length" | "len" => Ok(Condition::Number(
+            NumberField::Duration,
+            parse_bounds(value, parse_duration_secs, 1000)?,
+        )),
+        "year" | "y" => Ok(Condition::Number(
+            NumberField::Year,
+            parse_bounds(value, parse_number, 1)?,
+        )),
+        "plays" | "count" => Ok(Condition::Number(
+            NumberField::Plays,
+            parse_bounds(value, parse_number, 1)?,
+        )),
+        "skips" => Ok(Condition::Number(
+            NumberField::Skips,
+            parse_bounds(value, parse_number, 1)?,
+        )),
+        "played" | "lastplayed" => Ok(Condition::Number(
+            NumberField::DaysSincePlayed,
+            parse_bounds(value, parse_number, 1)?,
+        )),
+        "rating" | "stars" => Ok(Condition::Number(
+            NumberField::Rating,
+            parse_bounds(value, parse_rating, 1)?,
+        )),
+        "fav" | "favorite" | "favourite" => match value.to_ascii_lowercase().as_str() {
+            "yes" | "y" | "true" | "1" => Ok(Condition::Favorite(true)),
+            "no" | "n" | "false" | "0" => Ok(Condition::Favorite(false)),
+            _ => Err(format!("{name}: expects yes or no")),
+        },
+        _ => Err(format!("unknown field {name}:")),
+    }
+}
+
+/// `N`, `<N`, `<=N`, `>N`, `>=N`, `=N` or `A..B` (either end may be left out).
+///
+/// The compared values are `unit` times finer than what `parse` returns, and a
+/// parsed `N` stands for the whole step `[N, N + 1)`: with seconds typed and
+/// milliseconds compared, `5m` matches 5:00.000 to 5:00.999 and `>5m` anything
+/// longer than 5:00.000.
+fn parse_bounds(
+    value: &str,
+    parse: fn(&str) -> Result<u64, String>,
+    unit: u64,
+) -> Result<Bounds, String> {
+    let value = value.trim();
+    let low = |n: u64| n.saturating_mul(unit);
+    let high = |n: u64| n.saturating_add(1).saturating_mul(unit).saturating_sub(1);
+    let bounds = if let Some(rest) = value.strip_prefix(">=") {
+        Bounds {
+            min: Some(low(parse(rest)?)),
+            max: None,
+        }
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        Bounds {
+            min: None,
+            max: Some(high(parse(rest)?)),
+        }
+    } else if let Some(rest) = value.strip_prefix('>') {
+        Bounds {
+            min: Some(low(parse(rest)?).saturating_add(1)),
+            max: None,
+        }
+    } else if let Some(rest) = value.strip_prefix('<') {
+        match parse(rest)? {
+            0 => return Err(format!("nothing is below {rest}")),
+            n => Bounds {
+                min: None,
+                max: Some(low(n) - 1),
+            },
+        }
+    } else if let Some((lo, hi)) = value.split_once("..") {
+        let end = |s: &str| (!s.is_empty()).then(|| parse(s)).transpose();
+        Bounds {
+            min: end(lo)?.map(low),
+            max: end(hi)?.map(high),
+        }
+    } else {
+        let n = parse(value.strip_prefix('=').unwrap_or(value))?;
+        Bounds {
+            min: Some(low(n)),
+            max: Some(high(n)),
+        }
+    };
+    Ok(bounds)
+}
+
+fn parse_number(s: &str) -> Result<u64, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("expected a number, got \"{s}\""))
+}
+
+/// Stars (0–5) or a 0–100 rating.
+fn parse_rating(s: &str) -> Result<u64, String> {
+    match parse_number(s)? {
+        n @ 0..=5 => Ok(n * 20),
+        n @ 6..=100 => Ok(n),
+        _ => Err(format!("rating {s} is not 0–5 stars or 0–100")),
+    }
+}
+
+/// `90`, `90s`, `5m`, `1h30m`, `1m30s` or clock style `3:30` / `1:02:03`.
+fn parse_duration_secs(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let invalid = || format!("invalid duration \"{s}\" (try 5m, 3:30 or 90s)");
+    if s.is_empty() {
+        return Err(invalid());
+    }
+    if s.contains(':') {
+        return s.split(':').try_fold(0u64, |acc, part| {
+            part.parse::<u64>()
+                .map(|n| acc * 60 + n)
+                .map_err(|_| invalid())
+        });
+    }
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(secs);
+    }
+
+    let mut total = 0;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let n: u64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        total += n * match c.to_ascii_lowercase() {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+    }
+    if !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{cue::CueTrack, meta::TrackMeta};
+
+    fn track(duration_ms: u64) -> Track {
+        let mut track = Track::from_path(PathBuf::from("/music/Artist - Song.flac"));
+        track.tags = Some(TrackMeta {
+            title: Some("Song".to_string()),
+            artist: Some("Artist".to_string()),
+            genre: Some("Rock".to_string()),
+            duration: Some(Duration::from_millis(duration_ms)),
+            ..TrackMeta::default()
+        });
+        track
+    }
+
+    fn matches(query: &str, track: &Track) -> bool {
+        let filter = TrackFilter::parse(query).unwrap().unwrap();
+        let ctx = FilterContext {
+            favorites: &HashSet::new(),
+            play_stats: &HashMap::new(),
+            template: None,
+        };
+        filter.matches(track, &ctx)
+    }
+
+    #[test]
+    fn splits_terms_on_spaces_outside_quotes() {
+        assert_eq!(
+            split_terms(r#"  artist:foo album:"bar baz"  -"two words" "#),
+            ["artist:foo", "album:bar baz", "-two words"]
+        );
+        assert!(split_terms("   ").is_empty());
+        assert!(TrackFilter::parse("  ").unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_number_bounds() {
+        let bounds = |s| parse_bounds(s, parse_number, 1).unwrap();
+        let b = |min, max| Bounds { min, max };
+        assert_eq!(bounds("7"), b(Some(7), Some(7)));
+        assert_eq!(bounds("=7"), b(Some(7), Some(7)));
+        assert_eq!(bounds(">7"), b(Some(8), None));
+        assert_eq!(bounds(">=7"), b(Some(7), None));
+        assert_eq!(bounds("<7"), b(None, Some(6)));
+        assert_eq!(bounds("<=7"), b(None, Some(7)));
+        assert_eq!(bounds("1990..1999"), b(Some(1990), Some(1999)));
+        assert_eq!(bounds("1990.."), b(Some(1990), None));
+        assert_eq!(bounds("..3"), b(None, Some(3)));
+        assert!(parse_bounds("<0", parse_number, 1).is_err());
+        assert!(parse_bounds(">x", parse_number, 1).is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration_secs("90"), Ok(90));
+        assert_eq!(parse_duration_secs("90s"), Ok(90));
+        assert_eq!(parse_duration_secs("5m"), Ok(300));
+        assert_eq!(parse_duration_secs("1h30m"), Ok(5400));
+        assert_eq!(parse_duration_secs("1m30s"), Ok(90));
+        assert_eq!(parse_duration_secs("3:30"), Ok(210));
+        assert_eq!(parse_duration_secs("1:02:03"), Ok(3723));
+        for bad in ["", "5x", "m", "5m3", "3:xx"] {
+            assert!(parse_duration_secs(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn parses_ratings_as_stars_or_percent() {
+        assert_eq!(parse_rating("0"), Ok(0));
+        assert_eq!(parse_rating("4"), Ok(80));
+        assert_eq!(parse_rating("5"), Ok(100));
+        assert_eq!(parse_rating("60"), Ok(60));
+        assert!(parse_rating("101").is_err());
+        assert!(matches!(
+            parse_term("rating:>=4").unwrap().condition,
+            Condition::Number(
+                NumberField::Rating,
+                Bounds {
+                    min: Some(80),
+                    max: None
+                }
+            )
+        ));
+    }
+
+    #[test]
+    fn compares_durations_to_the_millisecond() {
+        let just_over = track(300_600);
+        assert!(matches("dur:>5m", &just_over));
+        assert!(matches("dur:5m", &just_over));
+        assert!(matches("dur:<=5m", &just_over));
+        assert!(!matches("dur:<5m", &just_over));
+        assert!(!matches("dur:>=5:01", &just_over));
+
+        let exact = track(300_000);
+        assert!(!matches("dur:>5m", &exact));
+        assert!(matches("dur:>=5m", &exact));
+        assert!(matches("dur:4m..5m", &exact));
+        assert!(!matches("dur:5m", &track(299_999)));
+        assert!(matches("dur:<5m", &track(299_999)));
+    }
+
+    #[test]
+    fn negates_and_quotes_terms() {
+        let song = track(200_000);
+        assert!(matches("artist:art", &song));
+        assert!(!matches("-artist:art", &song));
+        assert!(matches("-genre:jazz", &song));
+        assert!(matches(r#"name:"artist - song""#, &song));
+        assert!(!matches(r#""artist song""#, &song));
+        // A lone dash and a clock time are plain words.
+        assert!(matches!(
+            parse_term("-").unwrap(),
+            Term {
+                negate: false,
+                condition: Condition::Text(TextField::Any, _)
+            }
+        ));
+        assert!(matches!(
+            parse_term("12:30").unwrap().condition,
+            Condition::Text(TextField::Any, _)
+        ));
+        assert!(TrackFilter::parse("bogus:1").is_err());
+    }
+
+    #[test]
+    fn title_field_matches_like_plain_words() {
+        let cue = CueTrack {
+            number: 3,
+            total: 3,
+            title: Some("Third".to_string()),
+            performer: None,
+            album: None,
+            start: Duration::from_secs(60),
+            end: None,
+        };
+        let track = Track::from_cue(
+            PathBuf::from("/music/album.flac"),
+            Path::new("/music/album.cue"),
+            cue,
+        );
+        assert!(track.tags.is_none());
+        assert!(matches("third", &track));
+        assert!(matches("title:third", &track));
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+use symphonia::core::{
+    codecs::{CodecType, CODEC_TYPE_NULL, CODEC_TYPE_OPUS, CODEC_TYPE_WAVPACK},
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+/// Whether a file found during discovery can be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Support {
+    Decodable,
+    /// Recognised as audio, but nothing in the decoding stack can play it.
+    Unsupported(String),
+}
+
+/// Extensions whose container always holds a codec we can decode.
+const DECODABLE: &[&str] = &[
+    "mp3", "mp2", "mp1", "flac", "wav", "wave", "aif", "aiff", "aifc", "m4a", "m4b", "alac",
+    "aac", "adts", "caf",
+];
+
+/// Container extensions that may hold either a decodable codec or one we lack
+/// (e.g. Ogg/WebM with Opus), so the codec is probed.
+const CONTAINERS: &[&str] = &["ogg", "oga", "mka", "mkv", "webm", "mp4"];
+
+/// Audio extensions with no decoder in Symphonia (and therefore rodio either).
+const UNSUPPORTED: &[(&str, &str)] = &[
+    ("opus", "Opus"),
+    ("wv", "WavPack"),
+    ("ape", "Monkey's Audio"),
+    ("wma", "WMA"),
+    ("mpc", "Musepack"),
+    ("tta", "TTA"),
+    ("dsf", "DSD"),
+    ("dff", "DSD"),
+    ("spx", "Speex"),
+    ("ac3", "AC-3"),
+    ("dts", "DTS"),
+];
+
+/// Extensions that are never audio, so we don't bother sniffing them.
+const NOT_AUDIO: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "txt", "lrc", "srt", "vtt", "cue", "nfo", "log",
+    "m3u", "m3u8", "pls", "pdf", "md", "json", "xml", "html", "ini", "toml", "sfv", "md5",
+    "accurip", "db", "part", "ytdl",
+];
+
+/// Returns true if `path` looks playable judging by its extension alone.
+///
+/// Used where probing is too expensive (e.g. resolving CUE `FILE` entries).
+pub(crate) fn has_decodable_extension(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| {
+        DECODABLE.contains(&ext.as_str()) || CONTAINERS.contains(&ext.as_str())
+    })
+}
+
+/// Classifies a file found while scanning the library.
+///
+/// Returns `None` for files that aren't audio at all. Files with a missing or
+/// unknown extension are sniffed by their leading bytes, so mislabelled downloads
+/// still show up.
+pub(crate) fn classify(path: &Path) -> Option<Support> {
+    let ext = extension(path);
+    match ext.as_deref() {
+        Some(e) if DECODABLE.contains(&e) => Some(Support::Decodable),
+        Some(e) if CONTAINERS.contains(&e) => Some(probe_codec(path)),
+        Some(e) if NOT_AUDIO.contains(&e) => None,
+        Some(e) => match UNSUPPORTED.iter().find(|(x, _)| *x == e) {
+            Some((_, name)) => Some(unsupported_codec(name)),
+            None => sniff(path),
+        },
+        None => sniff(path),
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+fn unsupported_codec(name: &str) -> Support {
+    Support::Unsupported(format!("{name} is not supported by the decoder"))
+}
+
+/// Identifies audio by magic bytes.
+fn sniff(path: &Path) -> Option<Support> {
+    let mut head = [0u8; 12];
+    let n = File::open(path).and_then(|mut f| f.read(&mut head)).ok()?;
+    let head = &head[..n];
+
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+
+    if at(0, b"wvpk") {
+        return Some(unsupported_codec("WavPack"));
+    }
+    if at(0, b"MAC ") {
+        return Some(unsupported_codec("Monkey's Audio"));
+    }
+
+    let is_audio = at(0, b"ID3")
+        || at(0, b"fLaC")
+        || at(0, b"OggS")
+        || at(0, b"caff")
+        || at(0, &[0x1a, 0x45, 0xdf, 0xa3]) // Matroska / WebM
+        || (at(0, b"RIFF") && at(8, b"WAVE"))
+        || (at(0, b"FORM") && (at(8, b"AIFF") || at(8, b"AIFC")))
+        || at(4, b"ftyp")
+        // MPEG audio / ADTS frame sync.
+        || (head.len() >= 2 && head[0] == 0xff && head[1] & 0xe0 == 0xe0);
+
+    is_audio.then(|| probe_codec(path))
+}
+
+/// Opens the container and checks that its audio track has a decoder.
+fn probe_codec(path: &Path) -> Support {
+    let Ok(file) = File::open(path) else {
+        return Support::Unsupported("cannot be opened".to_string());
+    };
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let probed = match symphonia::default::get_probe().format(
+        &Hint::new(),
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    ) {
+        Ok(p) => p,
+        Err(_) => return Support::Unsupported("unrecognised container".to_string()),
+    };
+
+    let codecs: Vec<CodecType> = probed
+        .format
+        .tracks()
+        .iter()
+        .map(|t| t.codec_params.codec)
+        .filter(|&c| c != CODEC_TYPE_NULL)
+        .collect();
+
+    let registry = symphonia::default::get_codecs();
+    if codecs.iter().any(|&c| registry.get_codec(c).is_some()) {
+        return Support::Decodable;
+    }
+
+    match codecs.first() {
+        Some(&c) if c == CODEC_TYPE_OPUS => unsupported_codec("Opus"),
+        Some(&c) if c == CODEC_TYPE_WAVPACK => unsupported_codec("WavPack"),
+        Some(_) => Support::Unsupported("unsupported codec".to_string()),
+        None => Support::Unsupported("no audio track".to_string()),
+    }
+}
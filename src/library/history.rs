@@ -0,0 +1,223 @@
+//! What was played: a log of every listen, and play statistics per track.
+//!
+//! Both live in the data directory. The log, `history.tsv`, gets one line per
+//! listen: `unix_secs<TAB>listened_ms<TAB>end<TAB>start_ms<TAB>path`.
+//! `play_stats.txt` holds `plays<TAB>skips<TAB>last_played_secs<TAB>start_ms<TAB>path`
+//! lines. In both, `start_ms` tells the CUE tracks of one file apart (0 for
+//! whole files).
+
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{Track, TrackKey};
+use crate::util::data_home;
+
+/// How a listen ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ListenEnd {
+    /// Played to the end.
+    Completed,
+    /// Another track was started before this one ended.
+    Skipped,
+    /// Playback was stopped, or the player closed.
+    Stopped,
+}
+
+impl ListenEnd {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ListenEnd::Completed => "completed",
+            ListenEnd::Skipped => "skipped",
+            ListenEnd::Stopped => "stopped",
+        }
+    }
+
+    fn parse(s: &str) -> Option<ListenEnd> {
+        [ListenEnd::Completed, ListenEnd::Skipped, ListenEnd::Stopped]
+            .into_iter()
+            .find(|e| e.label() == s)
+    }
+}
+
+/// One listen of a track.
+#[derive(Debug, Clone)]
+pub(crate) struct HistoryEntry {
+    /// When playback started.
+    pub(crate) at: SystemTime,
+    pub(crate) path: PathBuf,
+    /// Where the track starts in `path` (non-zero only for CUE tracks).
+    pub(crate) start: Duration,
+    /// Time actually spent playing, pauses left out.
+    pub(crate) listened: Duration,
+    pub(crate) end: ListenEnd,
+}
+
+impl HistoryEntry {
+    /// Whether this was a listen of `track`.
+    pub(crate) fn is_of(&self, track: &Track) -> bool {
+        self.path == track.path && self.start == track.start_offset()
+    }
+}
+
+/// How a track has been listened to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct PlayStats {
+    /// Times it was started from the beginning.
+    pub(crate) plays: u32,
+    pub(crate) skips: u32,
+    pub(crate) last_played: Option<SystemTime>,
+}
+
+impl PlayStats {
+    /// Adds `other` in, keeping the later of the two last plays.
+    pub(crate) fn merge(&mut self, other: PlayStats) {
+        self.plays += other.plays;
+        self.skips += other.skips;
+        self.last_played = self.last_played.max(other.last_played);
+    }
+}
+
+/// `$XDG_DATA_HOME/trix` (or `~/.local/share/trix`).
+fn data_dir() -> Option<PathBuf> {
+    Some(data_home()?.join("trix"))
+}
+
+fn unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// The whole log, oldest first. Lines that don't parse are left out.
+pub(crate) fn load_history() -> Vec<HistoryEntry> {
+    let Some(content) = data_dir().and_then(|d| fs::read_to_string(d.join("history.tsv")).ok())
+    else {
+        return Vec::new();
+    };
+    content.lines().filter_map(parse_history_line).collect()
+}
+
+fn parse_history_line(line: &str) -> Option<HistoryEntry> {
+    let mut fields = line.splitn(5, '\t');
+    let at = fields.next()?.parse().ok()?;
+    let listened = fields.next()?.parse().ok()?;
+    let end = ListenEnd::parse(fields.next()?)?;
+    let start = fields.next()?.parse().ok()?;
+    let path = fields.next()?;
+    Some(HistoryEntry {
+        at: UNIX_EPOCH + Duration::from_secs(at),
+        listened: Duration::from_millis(listened),
+        end,
+        path: PathBuf::from(path),
+        start: Duration::from_millis(start),
+    })
+}
+
+fn history_line(entry: &HistoryEntry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        unix_secs(entry.at),
+        entry.listened.as_millis(),
+        entry.end.label(),
+        entry.start.as_millis(),
+        entry.path.display()
+    )
+}
+
+/// Adds `entry` to the end of the log.
+pub(crate) fn append_history(entry: &HistoryEntry) {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    let _ = fs::create_dir_all(&dir);
+    let _ = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("history.tsv"))
+        .and_then(|mut f| f.write_all(history_line(entry).as_bytes()));
+}
+
+/// Writes the whole log again, e.g. after files in it were renamed.
+pub(crate) fn save_history(entries: &[HistoryEntry]) {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    let _ = fs::create_dir_all(&dir);
+    let content: String = entries.iter().map(history_line).collect();
+    let _ = fs::write(dir.join("history.tsv"), content);
+}
+
+/// Reads the stats; lines that don't parse are left out.
+pub(crate) fn load_play_stats() -> HashMap<TrackKey, PlayStats> {
+    let Some(content) = data_dir().and_then(|d| fs::read_to_string(d.join("play_stats.txt")).ok())
+    else {
+        return HashMap::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(5, '\t');
+            let plays = fields.next()?.parse().ok()?;
+            let skips = fields.next()?.parse().ok()?;
+            let last: u64 = fields.next()?.parse().ok()?;
+            let start = Duration::from_millis(fields.next()?.parse().ok()?);
+            let stats = PlayStats {
+                plays,
+                skips,
+                last_played: (last > 0).then(|| UNIX_EPOCH + Duration::from_secs(last)),
+            };
+            Some(((PathBuf::from(fields.next()?), start), stats))
+        })
+        .collect()
+}
+
+pub(crate) fn save_play_stats(stats: &HashMap<TrackKey, PlayStats>) {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    let _ = fs::create_dir_all(&dir);
+    let content: Vec<String> = stats
+        .iter()
+        .map(|((path, start), s)| {
+            let last = s.last_played.map_or(0, unix_secs);
+            let start = start.as_millis();
+            format!(
+                "{}\t{}\t{last}\t{start}\t{}",
+                s.plays,
+                s.skips,
+                path.display()
+            )
+        })
+        .collect();
+    let _ = fs::write(dir.join("play_stats.txt"), content.join("\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_lines_keep_the_cue_offset() {
+        let entry = HistoryEntry {
+            at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            path: PathBuf::from("/music/album.flac"),
+            start: Duration::from_millis(245_320),
+            listened: Duration::from_millis(61_500),
+            end: ListenEnd::Skipped,
+        };
+        let line = history_line(&entry);
+        assert_eq!(
+            line,
+            "1700000000\t61500\tskipped\t245320\t/music/album.flac\n"
+        );
+
+        let read = parse_history_line(line.trim_end()).unwrap();
+        assert_eq!(read.at, entry.at);
+        assert_eq!(read.listened, entry.listened);
+        assert_eq!(read.end, entry.end);
+        assert_eq!((read.path, read.start), (entry.path, entry.start));
+    }
+}
@@ -1,63 +1,366 @@
-use std::{env, fs, path::{Path, PathBuf}};
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use walkdir::WalkDir;
 
+use crate::{
+    cue::{self, CueTrack},
+    lrc::{self, Lyrics},
+    meta::{self, TrackMeta},
+};
+
+mod filter;
+mod formats;
+mod history;
+mod organize;
+mod playlist;
+mod search;
+mod sort;
+mod stats;
+mod tags;
+mod template;
+mod trash;
+mod watch;
+
+pub(crate) use filter::{FilterContext, TrackFilter};
+use formats::Support;
+pub(crate) use history::{
+    append_history, load_history, load_play_stats, save_history, save_play_stats, HistoryEntry,
+    ListenEnd, PlayStats,
+};
+pub(crate) use organize::{ConflictPolicy, MoveStatus, OrganizePlan};
+pub(crate) use playlist::{add_to_playlist, playlist_names, rename_in_playlists, write_m3u};
+pub(crate) use search::{search, SearchField, SearchHit};
+pub(crate) use sort::{sort_permutation, SortKey, SortOrder};
+pub(crate) use stats::{ListeningStats, Period, PlayedFile, Ranked};
+pub(crate) use tags::{BackgroundLoader, TagLoader, TrackKey};
+pub(crate) use template::TagTemplate;
+pub(crate) use trash::{move_to_trash, Trashed};
+pub(crate) use watch::{LibraryChanges, LibraryWatcher};
+
 #[derive(Debug, Clone)]
 pub(crate) struct Track {
     pub(crate) path: PathBuf,
     pub(crate) display_name: String,
+    /// Set for virtual tracks that come from a CUE sheet: the slice of `path` to play.
+    pub(crate) cue: Option<CueTrack>,
+    /// The `.cue` file a CUE track was read from.
+    pub(crate) cue_sheet: Option<PathBuf>,
+    /// When the file appeared in the library (creation time, else modification time).
+    pub(crate) added: Option<SystemTime>,
+    /// Tags, loaded in the background (see `TagLoader`).
+    pub(crate) tags: Option<TrackMeta>,
+}
+
+impl Track {
+    fn from_path(path: PathBuf) -> Self {
+        Self {
+            display_name: path
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string()),
+            added: added_time(&path),
+            path,
+            cue: None,
+            cue_sheet: None,
+            tags: None,
+        }
+    }
+
+    fn from_cue(path: PathBuf, sheet: &Path, cue: CueTrack) -> Self {
+        let title = cue
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("Track {:02}", cue.number));
+        let display_name = match &cue.performer {
+            Some(performer) => format!("{:02}. {performer} - {title}", cue.number),
+            None => format!("{:02}. {title}", cue.number),
+        };
+        Self {
+            added: added_time(&path),
+            path,
+            display_name,
+            cue: Some(cue),
+            cue_sheet: Some(sheet.to_path_buf()),
+            tags: None,
+        }
+    }
+
+    /// Offset into `path` at which this track starts (non-zero only for CUE tracks).
+    pub(crate) fn start_offset(&self) -> Duration {
+        self.cue.as_ref().map(|c| c.start).unwrap_or_default()
+    }
+
+    /// Probes the file's tags, with CUE sheet data applied on top.
+    pub(crate) fn read_meta(&self) -> TrackMeta {
+        let mut meta = meta::probe_track_meta(&self.path).unwrap_or_default();
+        if let Some(cue) = &self.cue {
+            meta::apply_cue(&mut meta, cue);
+        }
+        meta
+    }
+
+    /// Loads the file's lyrics (see `lrc::load_lyrics`). A CUE track gets only
+    /// its own lines, timed from where it starts.
+    pub(crate) fn load_lyrics(&self) -> Option<Lyrics> {
+        let lyrics = lrc::load_lyrics(&self.path)?;
+        match &self.cue {
+            Some(cue) => lyrics.slice(cue.start, cue.end),
+            None => Some(lyrics),
+        }
+    }
+
+    /// The tagged title, or the list entry name.
+    pub(crate) fn title(&self) -> &str {
+        self.tags
+            .as_ref()
+            .and_then(|m| m.title.as_deref())
+            .unwrap_or(&self.display_name)
+    }
+
+    pub(crate) fn artist(&self) -> Option<&str> {
+        self.tags.as_ref()?.artist.as_deref()
+    }
+
+    pub(crate) fn album(&self) -> Option<&str> {
+        self.tags.as_ref()?.album.as_deref()
+    }
+
+    pub(crate) fn duration(&self) -> Option<Duration> {
+        self.tags.as_ref()?.duration
+    }
+
+    pub(crate) fn key(&self) -> TrackKey {
+        (self.path.clone(), self.start_offset())
+    }
+
+    /// The name shown in the list: `template` filled from the tags, falling back
+    /// to the file name while tags are loading or when a field is missing.
+    pub(crate) fn list_name(&self, template: Option<&TagTemplate>) -> String {
+        template
+            .and_then(|t| t.render(self))
+            .unwrap_or_else(|| self.display_name.clone())
+    }
+
+    /// Returns true if both entries refer to the same playable item.
+    ///
+    /// CUE tracks share their audio file, so the path alone is not enough.
+    pub(crate) fn same_item(&self, other: &Track) -> bool {
+        self.path == other.path && self.start_offset() == other.start_offset()
+    }
 }
 
 pub(crate) fn default_library_path() -> PathBuf {
     default_music_dir().unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
 }
 
-pub(crate) fn discover_tracks(path: &Path) -> Result<Vec<Track>> {
-    let mut files: Vec<PathBuf> = Vec::new();
+/// An audio file that was found but cannot be played.
+#[derive(Debug, Clone)]
+pub(crate) struct SkippedFile {
+    pub(crate) path: PathBuf,
+    pub(crate) reason: String,
+}
+
+/// The result of scanning the library.
+#[derive(Debug, Default)]
+pub(crate) struct Discovery {
+    pub(crate) tracks: Vec<Track>,
+    /// Audio files that were recognised but can't be decoded, so the user can see
+    /// why they are missing from the list.
+    pub(crate) skipped: Vec<SkippedFile>,
+}
+
+/// What to scan: the library roots plus the filters applied while walking them.
+#[derive(Debug, Clone)]
+pub(crate) struct LibraryScan {
+    pub(crate) roots: Vec<PathBuf>,
+    exclude: GlobSet,
+    max_depth: Option<usize>,
+    follow_links: bool,
+}
+
+impl LibraryScan {
+    /// Builds the scan settings. Invalid exclude patterns are reported and ignored.
+    pub(crate) fn new(
+        roots: Vec<PathBuf>,
+        exclude: &[String],
+        max_depth: Option<usize>,
+        follow_links: bool,
+    ) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in exclude {
+            match Glob::new(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => eprintln!("trix: ignoring invalid exclude pattern {pattern:?}: {e}"),
+            }
+        }
+        let exclude = builder.build().unwrap_or_else(|_| GlobSet::empty());
+
+        // Overlapping roots would list the same files twice; keep the outermost.
+        let mut roots = roots;
+        roots.sort();
+        roots.dedup();
+        let outer: Vec<PathBuf> = roots
+            .iter()
+            .filter(|r| !roots.iter().any(|o| o != *r && r.starts_with(o)))
+            .cloned()
+            .collect();
+
+        Self {
+            roots: outer,
+            exclude,
+            max_depth,
+            follow_links,
+        }
+    }
+
+    /// Returns true if `path` matches an exclude pattern, either as an absolute path
+    /// or relative to the root it was found under.
+    pub(crate) fn is_excluded(&self, path: &Path) -> bool {
+        if self.exclude.is_empty() {
+            return false;
+        }
+        if self.exclude.is_match(path) {
+            return true;
+        }
+        self.roots
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .any(|rel| self.exclude.is_match(rel))
+    }
 
-    if path.is_file() {
-        files.push(path.to_path_buf());
-    } else {
-        for entry in WalkDir::new(path)
-            .follow_links(true)
+    /// A directory walker for `root` with depth, symlink and exclude rules applied.
+    pub(crate) fn walk(&self, root: &Path) -> impl Iterator<Item = walkdir::DirEntry> + '_ {
+        let mut walker = WalkDir::new(root).follow_links(self.follow_links);
+        if let Some(depth) = self.max_depth {
+            walker = walker.max_depth(depth.saturating_add(1));
+        }
+        walker
             .into_iter()
+            // Cyclic symlinks surface as errors here, which ends that branch of the walk.
+            .filter_entry(|e| e.depth() == 0 || !self.is_excluded(e.path()))
             .filter_map(|e| e.ok())
-        {
+    }
+}
+
+pub(crate) fn discover_tracks(scan: &LibraryScan) -> Result<Discovery> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut cue_sheets: Vec<PathBuf> = Vec::new();
+    let mut skipped: Vec<SkippedFile> = Vec::new();
+
+    // `explicit` is set for files named directly as a root: those are always
+    // attempted, even if they don't look like audio.
+    let mut consider = |p: &Path, explicit: bool| {
+        if is_cue_file(p) {
+            cue_sheets.push(p.to_path_buf());
+            return;
+        }
+        match formats::classify(p) {
+            Some(Support::Decodable) => files.push(p.to_path_buf()),
+            Some(Support::Unsupported(reason)) => skipped.push(SkippedFile {
+                path: p.to_path_buf(),
+                reason,
+            }),
+            None if explicit => files.push(p.to_path_buf()),
+            None => {}
+        }
+    };
+
+    for root in &scan.roots {
+        if root.is_file() {
+            consider(root, true);
+            continue;
+        }
+
+        for entry in scan.walk(root) {
             if !entry.file_type().is_file() {
                 continue;
             }
-            let p = entry.path();
-            if is_audio_file(p) {
-                files.push(p.to_path_buf());
+            consider(entry.path(), false);
+        }
+    }
+    files.sort();
+    files.dedup();
+
+    // Expand CUE sheets into virtual tracks. An audio file described by a sheet is
+    // listed only through its CUE tracks, not as one long track.
+    let mut tracks: Vec<Track> = Vec::new();
+    let mut covered: HashSet<PathBuf> = HashSet::new();
+    cue_sheets.sort();
+    for sheet_path in cue_sheets {
+        let Some(sheet) = cue::load_cue(&sheet_path, formats::has_decodable_extension) else {
+            continue;
+        };
+        for (file, cue_tracks) in sheet.files {
+            if cue_tracks.is_empty() || !covered.insert(file.clone()) {
+                continue;
             }
+            tracks.extend(
+                cue_tracks
+                    .into_iter()
+                    .map(|c| Track::from_cue(file.clone(), &sheet_path, c)),
+            );
         }
     }
 
-    files.sort();
+    tracks.extend(
+        files
+            .into_iter()
+            .filter(|p| !covered.contains(p))
+            .map(Track::from_path),
+    );
 
-    let tracks = files
-        .into_iter()
-        .map(|p| Track {
-            display_name: p
-                .file_name()
-                .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| p.display().to_string()),
-            path: p,
-        })
+    sort_tracks(&mut tracks);
+    skipped.retain(|s| !covered.contains(&s.path));
+    skipped.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Discovery { tracks, skipped })
+}
+
+/// The CUE track of `path` that starts at `start`, from a sheet next to the
+/// file. For describing tracks without scanning the library.
+pub(crate) fn find_cue_track(path: &Path, start: Duration) -> Option<CueTrack> {
+    let mut sheets: Vec<PathBuf> = fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| is_cue_file(p))
         .collect();
+    sheets.sort();
+    sheets.into_iter().find_map(|sheet| {
+        cue::load_cue(&sheet, formats::has_decodable_extension)?
+            .files
+            .into_iter()
+            .filter(|(file, _)| file == path)
+            .flat_map(|(_, tracks)| tracks)
+            .find(|t| t.start == start)
+    })
+}
 
-    Ok(tracks)
+fn added_time(path: &Path) -> Option<SystemTime> {
+    let md = fs::metadata(path).ok()?;
+    md.created().or_else(|_| md.modified()).ok()
 }
 
-fn is_audio_file(path: &Path) -> bool {
-    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
-        return false;
-    };
-    matches!(
-        ext.to_ascii_lowercase().as_str(),
-        "mp3" | "flac" | "wav" | "ogg" | "m4a" | "aac"
-    )
+/// Sorts by path, keeping CUE tracks of the same file in playback order.
+pub(crate) fn sort_tracks(tracks: &mut [Track]) {
+    tracks.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then_with(|| a.start_offset().cmp(&b.start_offset()))
+    });
+}
+
+fn is_cue_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("cue"))
 }
 
 fn home_dir() -> Option<PathBuf> {
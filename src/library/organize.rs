@@ -0,0 +1,294 @@
+//! Moving files into a directory layout built from their tags.
+
+use std::{
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    fs, io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use super::{TagTemplate, Track};
+use crate::{lrc, subtitles};
+
+/// What to do when the template puts a file where another one already is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConflictPolicy {
+    /// Leave the file where it is.
+    Skip,
+    /// Add a number: `Song (2).mp3`.
+    Number,
+}
+
+impl ConflictPolicy {
+    pub(crate) fn toggle(self) -> Self {
+        match self {
+            ConflictPolicy::Skip => ConflictPolicy::Number,
+            ConflictPolicy::Number => ConflictPolicy::Skip,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Number => "add number",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MoveStatus {
+    Move,
+    /// Already where the template puts it.
+    InPlace,
+    /// The target exists, or another file in the batch goes there.
+    Conflict,
+    /// The template uses a tag the file doesn't have.
+    MissingTags,
+    /// Split by a CUE sheet, which refers to the file by name.
+    Cue,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PlannedMove {
+    pub(crate) from: PathBuf,
+    /// Where the template puts the file, if it could be filled in.
+    pub(crate) to: Option<PathBuf>,
+    pub(crate) status: MoveStatus,
+}
+
+/// The moves an organize run would make; shown as a preview before anything
+/// is touched.
+#[derive(Debug, Clone)]
+pub(crate) struct OrganizePlan {
+    pub(crate) root: PathBuf,
+    /// Where each file goes below `root`; kept to plan again with another policy.
+    pub(crate) template: TagTemplate,
+    pub(crate) moves: Vec<PlannedMove>,
+    pub(crate) policy: ConflictPolicy,
+}
+
+impl OrganizePlan {
+    /// Plans moves for `tracks` (with tags loaded) to `root`/`template`.
+    pub(crate) fn new(
+        tracks: &[Track],
+        template: &TagTemplate,
+        root: &Path,
+        policy: ConflictPolicy,
+    ) -> Self {
+        let mut seen: HashSet<&Path> = HashSet::new();
+        let mut taken: HashSet<PathBuf> = HashSet::new();
+        let mut moves = Vec::new();
+
+        for track in tracks {
+            if !seen.insert(&track.path) {
+                continue;
+            }
+            let from = track.path.clone();
+            if track.cue.is_some() {
+                moves.push(PlannedMove {
+                    from,
+                    to: None,
+                    status: MoveStatus::Cue,
+                });
+                continue;
+            }
+            let Some(to) = template.render_path(track).map(|rel| root.join(rel)) else {
+                moves.push(PlannedMove {
+                    from,
+                    to: None,
+                    status: MoveStatus::MissingTags,
+                });
+                continue;
+            };
+            if to == from {
+                taken.insert(to.clone());
+                moves.push(PlannedMove {
+                    from,
+                    to: Some(to),
+                    status: MoveStatus::InPlace,
+                });
+                continue;
+            }
+
+            let free = |p: &Path| !taken.contains(p) && fs::symlink_metadata(p).is_err();
+            let (to, status) = if free(&to) {
+                (to, MoveStatus::Move)
+            } else if policy == ConflictPolicy::Number {
+                let numbered = (2..)
+                    .map(|n| numbered_path(&to, n))
+                    .find(|p| *p == from || free(p))
+                    .unwrap_or(to);
+                if numbered == from {
+                    (numbered, MoveStatus::InPlace)
+                } else {
+                    (numbered, MoveStatus::Move)
+                }
+            } else {
+                (to, MoveStatus::Conflict)
+            };
+            if status != MoveStatus::Conflict {
+                taken.insert(to.clone());
+            }
+            moves.push(PlannedMove {
+                from,
+                to: Some(to),
+                status,
+            });
+        }
+
+        Self {
+            root: root.to_path_buf(),
+            template: template.clone(),
+            moves,
+            policy,
+        }
+    }
+
+    pub(crate) fn count(&self, status: MoveStatus) -> usize {
+        self.moves.iter().filter(|m| m.status == status).count()
+    }
+
+    /// Performs the planned moves. Returns the `(from, to)` pairs that were
+    /// moved (including lyrics and subtitle sidecars) and a message per failure.
+    pub(crate) fn apply(&self) -> (Vec<(PathBuf, PathBuf)>, Vec<String>) {
+        let mut moved = Vec::new();
+        let mut failures = Vec::new();
+        for m in self.moves.iter().filter(|m| m.status == MoveStatus::Move) {
+            let Some(to) = &m.to else { continue };
+            match move_file(&m.from, to) {
+                Ok(()) => {
+                    for (side_from, side_to) in sidecars(&m.from, to) {
+                        if move_file(&side_from, &side_to).is_ok() {
+                            moved.push((side_from, side_to));
+                        }
+                    }
+                    moved.push((m.from.clone(), to.clone()));
+                    self.remove_empty_dirs(&m.from);
+                }
+                Err(e) => failures.push(format!(
+                    "{}: {e}",
+                    m.from.file_name().unwrap_or_default().to_string_lossy()
+                )),
+            }
+        }
+        (moved, failures)
+    }
+
+    /// Removes directories left empty by a move, up to (not including) the root.
+    fn remove_empty_dirs(&self, moved_from: &Path) {
+        for dir in moved_from.ancestors().skip(1) {
+            if !dir.starts_with(&self.root) || dir == self.root || fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// `dir/Song.mp3` -> `dir/Song (n).mp3`.
+fn numbered_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = OsString::from(path.file_stem().unwrap_or_default());
+    name.push(format!(" ({n})"));
+    if let Some(ext) = path.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    path.with_file_name(name)
+}
+
+/// Lyrics and subtitle files next to `from` (`Song.lrc`, `Song.mp3.lrc`,
+/// `Song.txt`, `Song.srt`, `Song.en.vtt`) and where they go.
+fn sidecars(from: &Path, to: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut out: Vec<(PathBuf, PathBuf)> = ["lrc", "txt"]
+        .into_iter()
+        .flat_map(|ext| {
+            lrc::sidecar_paths(from, ext)
+                .into_iter()
+                .zip(lrc::sidecar_paths(to, ext))
+        })
+        .collect();
+    if let (Some(from_stem), Some(to_stem)) = (from.file_stem(), to.file_stem()) {
+        for side in subtitles::candidates(from) {
+            // Keep whatever follows the stem: `.srt`, `.en.vtt`.
+            let Some(rest) = side
+                .file_name()
+                .and_then(|name| name.as_bytes().strip_prefix(from_stem.as_bytes()))
+            else {
+                continue;
+            };
+            let mut name = to_stem.to_os_string();
+            name.push(OsStr::from_bytes(rest));
+            let side_to = to.with_file_name(name);
+            out.push((side, side_to));
+        }
+    }
+    out.dedup();
+    out.retain(|(f, t)| f.is_file() && !t.exists());
+    out
+}
+
+/// Renames `from` to `to`, creating directories as needed. Falls back to copy and
+/// delete when they are on different filesystems.
+pub(super) fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::rename(from, to) {
+        Err(e) if e.raw_os_error() == Some(nix::errno::Errno::EXDEV as i32) => {
+            fs::copy(from, to)?;
+            fs::remove_file(from).inspect_err(|_| {
+                let _ = fs::remove_file(to);
+            })
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    #[test]
+    fn finds_lyrics_and_subtitle_sidecars() {
+        let dir = TempDir::new("organize-sidecars");
+        for name in [
+            "Song.flac",
+            "Song.lrc",
+            "Song.flac.txt",
+            "Song.vtt",
+            "Song.en.vtt",
+            "Song.live.flac.lrc",
+            "Other.srt",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let to = dir.join("Artist/01 Song.flac");
+        let mut found = sidecars(&dir.join("Song.flac"), &to);
+        found.sort();
+        let names: Vec<(String, String)> = found
+            .iter()
+            .map(|(f, t)| {
+                let name = |p: &Path| p.file_name().unwrap().to_string_lossy().into_owned();
+                assert_eq!(t.parent(), to.parent());
+                (name(f), name(t))
+            })
+            .collect();
+        let pair = |f: &str, t: &str| (f.to_string(), t.to_string());
+        assert_eq!(
+            names,
+            [
+                pair("Song.en.vtt", "01 Song.en.vtt"),
+                pair("Song.flac.txt", "01 Song.flac.txt"),
+                pair("Song.lrc", "01 Song.lrc"),
+                pair("Song.vtt", "01 Song.vtt"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! M3U playlists: named ones kept in the data directory, and exports of the
+//! library list.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use super::Track;
+use crate::util::data_home;
+
+/// `$XDG_DATA_HOME/trix/playlists` (or `~/.local/share/trix/playlists`).
+pub(crate) fn playlists_dir() -> Option<PathBuf> {
+    Some(data_home()?.join("trix").join("playlists"))
+}
+
+/// Names of the saved playlists, sorted.
+pub(crate) fn playlist_names() -> Vec<String> {
+    let Some(entries) = playlists_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| {
+            let path = e.ok()?.path();
+            if path.extension()? != "m3u" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Points entries of the saved playlists at where `new_path` says their files
+/// went. Playlists with nothing renamed are left alone.
+pub(crate) fn rename_in_playlists(new_path: impl Fn(&Path) -> Option<PathBuf>) {
+    let Some(entries) = playlists_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return;
+    };
+    for path in entries.filter_map(|e| Some(e.ok()?.path())) {
+        if path.extension().is_none_or(|e| e != "m3u") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut changed = false;
+        let mut lines: Vec<String> = content
+            .lines()
+            .map(|line| {
+                if line.trim().is_empty() || line.starts_with('#') {
+                    return line.to_string();
+                }
+                match new_path(Path::new(line)) {
+                    Some(to) => {
+                        changed = true;
+                        to.display().to_string()
+                    }
+                    None => line.to_string(),
+                }
+            })
+            .collect();
+        if changed {
+            if content.ends_with('\n') {
+                lines.push(String::new());
+            }
+            let _ = fs::write(&path, lines.join("\n"));
+        }
+    }
+}
+
+/// Adds `tracks` to the end of playlist `name`, creating it if needed. Tracks
+/// already in it are left out. Returns the playlist file and how many were added.
+pub(crate) fn add_to_playlist(name: &str, tracks: &[&Track]) -> Result<(PathBuf, usize)> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        bail!("invalid playlist name \"{name}\"");
+    }
+    let dir = playlists_dir().context("no data directory ($HOME is not set)")?;
+    let path = dir.join(format!("{name}.m3u"));
+    let added = append_entries(&path, tracks)?;
+    Ok((path, added))
+}
+
+/// Appends the `tracks` that `path` doesn't list yet; CUE tracks of one file
+/// count as different entries. Returns how many were added.
+fn append_entries(path: &Path, tracks: &[&Track]) -> Result<usize> {
+    let mut content = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => "#EXTM3U\n".to_string(),
+        Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+    };
+    let mut present: Vec<PathBuf> = content
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(PathBuf::from)
+        .collect();
+
+    let mut added = 0;
+    for track in tracks {
+        let location = location(track);
+        if present.contains(&location) {
+            continue;
+        }
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&entry(track));
+        present.push(location);
+        added += 1;
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("write {}", path.display()))?;
+    Ok(added)
+}
+
+/// Writes `tracks` to `path` as an extended M3U playlist, replacing the file.
+pub(crate) fn write_m3u(path: &Path, tracks: &[&Track]) -> Result<()> {
+    let mut content = String::from("#EXTM3U\n");
+    for track in tracks {
+        content.push_str(&entry(track));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("write {}", path.display()))
+}
+
+/// Where a playlist entry points: the file, or for a CUE track the sheet plus
+/// the track number (`album.cue/track0003`), as MPD writes them.
+fn location(track: &Track) -> PathBuf {
+    match (&track.cue_sheet, &track.cue) {
+        (Some(sheet), Some(cue)) => sheet.join(format!("track{:04}", cue.number)),
+        _ => track.path.clone(),
+    }
+}
+
+/// `#EXTINF:<seconds>,<artist - title>` followed by the location.
+fn entry(track: &Track) -> String {
+    let secs = track.duration().map_or(-1, |d| d.as_secs() as i64);
+    let title = match track.artist() {
+        Some(artist) => format!("{artist} - {}", track.title()),
+        None => track.title().to_string(),
+    };
+    format!("#EXTINF:{secs},{title}\n{}\n", location(track).display())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{cue::CueTrack, meta::TrackMeta, util::TempDir};
+
+    fn cue_track(number: u32, start_secs: u64) -> Track {
+        let cue = CueTrack {
+            number,
+            total: 3,
+            title: Some(format!("Song {number}")),
+            performer: None,
+            album: None,
+            start: Duration::from_secs(start_secs),
+            end: None,
+        };
+        Track::from_cue(
+            PathBuf::from("/music/album.flac"),
+            Path::new("/music/album.cue"),
+            cue,
+        )
+    }
+
+    #[test]
+    fn writes_cue_tracks_as_sheet_and_number() {
+        let mut file = Track::from_path(PathBuf::from("/music/song.mp3"));
+        file.tags = Some(TrackMeta {
+            title: Some("Song".to_string()),
+            artist: Some("Artist".to_string()),
+            duration: Some(Duration::from_millis(200_900)),
+            ..TrackMeta::default()
+        });
+        assert_eq!(entry(&file), "#EXTINF:200,Artist - Song\n/music/song.mp3\n");
+        assert_eq!(
+            entry(&cue_track(3, 400)),
+            "#EXTINF:-1,03. Song 3\n/music/album.cue/track0003\n"
+        );
+    }
+
+    #[test]
+    fn adds_each_cue_track_once() {
+        let dir = TempDir::new("playlist");
+        let path = dir.join("playlists/mix.m3u");
+        let (first, second) = (cue_track(1, 0), cue_track(2, 300));
+
+        assert_eq!(append_entries(&path, &[&first, &first]).unwrap(), 1);
+        assert_eq!(append_entries(&path, &[&first, &second]).unwrap(), 1);
+        let content = fs::read_to_string(&path).unwrap();
+
+        let locations: Vec<&str> = content.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            locations,
+            ["/music/album.cue/track0001", "/music/album.cue/track0002"]
+        );
+        assert!(content.starts_with("#EXTM3U\n"));
+    }
+}
@@ -0,0 +1,223 @@
+//! Fuzzy search over the library: list names, tags and lyrics.
+
+use std::collections::HashMap;
+
+use super::{Track, TrackKey};
+
+/// Only the first occurrences of a term's first letter are tried as match starts.
+const MAX_STARTS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum SearchField {
+    Name,
+    Title,
+    Artist,
+    Album,
+    Lyrics,
+}
+
+impl SearchField {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            SearchField::Name => "name",
+            SearchField::Title => "title",
+            SearchField::Artist => "artist",
+            SearchField::Album => "album",
+            SearchField::Lyrics => "lyrics",
+        }
+    }
+
+    /// Relative weight of a match, in tenths.
+    fn weight(self) -> i64 {
+        match self {
+            SearchField::Name | SearchField::Title => 10,
+            SearchField::Artist => 9,
+            SearchField::Album => 8,
+            SearchField::Lyrics => 6,
+        }
+    }
+}
+
+/// A track matching every term of the query.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchHit {
+    /// Index into the track list.
+    pub(crate) index: usize,
+    pub(crate) score: i64,
+    /// Matched character positions in the list name.
+    pub(crate) name_matches: Vec<usize>,
+    /// The best other field a term matched (for lyrics, the matching line),
+    /// with its matched character positions.
+    pub(crate) detail: Option<(SearchField, String, Vec<usize>)>,
+}
+
+/// Ranks `tracks` against `query`. Every whitespace-separated term has to match
+/// the list name (`name_of`), title, artist, album or lyrics text, fuzzily
+/// except for lyrics, which need the exact term.
+pub(crate) fn search(
+    tracks: &[Track],
+    query: &str,
+    name_of: impl Fn(&Track) -> String,
+    lyrics: &HashMap<TrackKey, Option<String>>,
+) -> Vec<SearchHit> {
+    let terms: Vec<Vec<char>> = query.split_whitespace().map(lower_chars).collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits: Vec<SearchHit> = tracks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, track)| {
+            let name = name_of(track);
+            let fields = [
+                (SearchField::Name, Some(name.as_str())),
+                (
+                    SearchField::Title,
+                    track.tags.as_ref().and_then(|m| m.title.as_deref()),
+                ),
+                (SearchField::Artist, track.artist()),
+                (SearchField::Album, track.album()),
+            ];
+            let lyrics = lyrics.get(&track.key()).and_then(Option::as_deref);
+            match_track(&terms, &fields, lyrics).map(|(score, name_matches, detail)| SearchHit {
+                index,
+                score,
+                name_matches,
+                detail,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+    hits
+}
+
+type TrackMatch = (i64, Vec<usize>, Option<(SearchField, String, Vec<usize>)>);
+
+fn match_track(
+    terms: &[Vec<char>],
+    fields: &[(SearchField, Option<&str>)],
+    lyrics: Option<&str>,
+) -> Option<TrackMatch> {
+    let mut total = 0;
+    let mut matched: HashMap<SearchField, (String, Vec<usize>, i64)> = HashMap::new();
+
+    for term in terms {
+        let best = fields
+            .iter()
+            .filter_map(|&(field, text)| {
+                let text = text?;
+                let (score, positions) = fuzzy_match(term, text)?;
+                Some((
+                    field,
+                    text.to_string(),
+                    score * field.weight() / 10,
+                    positions,
+                ))
+            })
+            .max_by_key(|(field, _, score, _)| (*score, std::cmp::Reverse(*field as u8)))
+            .or_else(|| {
+                let (line, positions) = lyrics_match(term, lyrics?)?;
+                let score = term.len() as i64 * 16 * SearchField::Lyrics.weight() / 10;
+                Some((SearchField::Lyrics, line, score, positions))
+            })?;
+
+        let (field, text, score, positions) = best;
+        total += score;
+        let entry = matched
+            .entry(field)
+            .or_insert_with(|| (text, Vec::new(), 0));
+        entry.1.extend(positions);
+        entry.2 += score;
+    }
+
+    let name_matches = matched
+        .remove(&SearchField::Name)
+        .map(|(_, positions, _)| sorted(positions))
+        .unwrap_or_default();
+    let detail = matched
+        .into_iter()
+        .max_by_key(|(field, (_, _, score))| (*score, std::cmp::Reverse(*field as u8)))
+        .map(|(field, (text, positions, _))| (field, text, sorted(positions)));
+    Some((total, name_matches, detail))
+}
+
+fn sorted(mut positions: Vec<usize>) -> Vec<usize> {
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+fn lower_chars(s: &str) -> Vec<char> {
+    s.chars().map(lower).collect()
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Matches the letters of `term` (lowercase) in order within `text`,
+/// ignoring case. Returns a score and the matched character positions, or
+/// `None` if the letters are missing or too scattered to be a real match.
+///
+/// Consecutive letters and letters starting a word score higher; gaps cost.
+fn fuzzy_match(term: &[char], text: &str) -> Option<(i64, Vec<usize>)> {
+    let hay = lower_chars(text);
+    let first = *term.first()?;
+    if term.len() > hay.len() {
+        return None;
+    }
+    let word_start = |i: usize| i == 0 || !hay[i - 1].is_alphanumeric();
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    let starts = hay
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == first)
+        .take(MAX_STARTS);
+    for (start, _) in starts {
+        let mut positions = Vec::with_capacity(term.len());
+        let mut next = start;
+        for &tc in term {
+            let Some(offset) = hay[next..].iter().position(|&c| c == tc) else {
+                break;
+            };
+            positions.push(next + offset);
+            next += offset + 1;
+        }
+        if positions.len() < term.len() {
+            // Later starts only have less text left to match in.
+            break;
+        }
+
+        let mut score = 0;
+        for (n, &i) in positions.iter().enumerate() {
+            score += 16;
+            if word_start(i) {
+                score += 10;
+            }
+            if n > 0 {
+                let gap = (i - positions[n - 1] - 1) as i64;
+                if gap == 0 {
+                    score += 12;
+                } else {
+                    score -= (3 + gap).min(15);
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|(s, _)| score > *s) {
+            best = Some((score, positions));
+        }
+    }
+    best.filter(|(score, _)| *score >= term.len() as i64 * 12)
+}
+
+/// Finds `term` (lowercase) in the lyrics; returns the line it is on and the
+/// matched character positions within that line.
+fn lyrics_match(term: &[char], lyrics: &str) -> Option<(String, Vec<usize>)> {
+    lyrics.lines().find_map(|line| {
+        let hay = lower_chars(line);
+        let at = hay.windows(term.len()).position(|w| w == term)?;
+        Some((line.trim_end().to_string(), (at..at + term.len()).collect()))
+    })
+}
@@ -0,0 +1,202 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::{PlayStats, Track, TrackKey};
+
+/// What the library list (and sequential play order) is sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SortKey {
+    Path,
+    FileName,
+    Title,
+    Artist,
+    /// Album, then disc and track number.
+    Album,
+    Duration,
+    DateAdded,
+    PlayCount,
+    Skips,
+    LastPlayed,
+    Rating,
+}
+
+impl SortKey {
+    pub(crate) const ALL: [SortKey; 11] = [
+        SortKey::Path,
+        SortKey::FileName,
+        SortKey::Title,
+        SortKey::Artist,
+        SortKey::Album,
+        SortKey::Duration,
+        SortKey::DateAdded,
+        SortKey::PlayCount,
+        SortKey::Skips,
+        SortKey::LastPlayed,
+        SortKey::Rating,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            SortKey::Path => "path",
+            SortKey::FileName => "name",
+            SortKey::Title => "title",
+            SortKey::Artist => "artist",
+            SortKey::Album => "album",
+            SortKey::Duration => "duration",
+            SortKey::DateAdded => "added",
+            SortKey::PlayCount => "plays",
+            SortKey::Skips => "skips",
+            SortKey::LastPlayed => "played",
+            SortKey::Rating => "rating",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<SortKey> {
+        let s = s.trim().to_ascii_lowercase();
+        SortKey::ALL.into_iter().find(|k| k.label() == s).or(match s.as_str() {
+            "file" | "filename" => Some(SortKey::FileName),
+            "date" | "date_added" => Some(SortKey::DateAdded),
+            "playcount" | "play_count" | "count" => Some(SortKey::PlayCount),
+            "last_played" | "lastplayed" | "recent" => Some(SortKey::LastPlayed),
+            "length" | "time" => Some(SortKey::Duration),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn next(self) -> SortKey {
+        let i = SortKey::ALL.iter().position(|&k| k == self).unwrap_or(0);
+        SortKey::ALL[(i + 1) % SortKey::ALL.len()]
+    }
+
+    /// Whether sorting by this key needs the tags of every track.
+    pub(crate) fn needs_tags(self) -> bool {
+        matches!(
+            self,
+            SortKey::Title | SortKey::Artist | SortKey::Album | SortKey::Duration | SortKey::Rating
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SortOrder {
+    pub(crate) key: SortKey,
+    pub(crate) descending: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Path,
+            descending: false,
+        }
+    }
+}
+
+impl SortOrder {
+    /// Parses the persisted form, e.g. `artist` or `added desc`.
+    pub(crate) fn parse(s: &str) -> Option<SortOrder> {
+        let mut parts = s.split_whitespace();
+        let key = SortKey::parse(parts.next()?)?;
+        let descending = matches!(parts.next(), Some("desc" | "descending"));
+        Some(SortOrder { key, descending })
+    }
+
+    pub(crate) fn to_config_string(self) -> String {
+        let dir = if self.descending { "desc" } else { "asc" };
+        format!("{} {dir}", self.key.label())
+    }
+}
+
+/// Returns the indices of `tracks` in `order`.
+///
+/// Tracks with no value for the key (e.g. untagged files when sorting by artist)
+/// go last in either direction. Ties fall back to path order, which also keeps CUE
+/// tracks of one file in sequence.
+pub(crate) fn sort_permutation(
+    tracks: &[Track],
+    order: SortOrder,
+    stats: &HashMap<TrackKey, PlayStats>,
+) -> Vec<usize> {
+    let by_path = |a: &Track, b: &Track| {
+        a.path
+            .cmp(&b.path)
+            .then_with(|| a.start_offset().cmp(&b.start_offset()))
+    };
+
+    let lower = |s: Option<&str>| s.map(|s| s.to_lowercase());
+    let stats_of = |t: &Track| stats.get(&t.key()).copied().unwrap_or_default();
+
+    let mut indices: Vec<usize> = (0..tracks.len()).collect();
+    indices.sort_by(|&a, &b| {
+        let (a, b) = (&tracks[a], &tracks[b]);
+        let primary = match order.key {
+            SortKey::Path => Some(by_path(a, b)),
+            SortKey::FileName => Some(
+                a.display_name
+                    .to_lowercase()
+                    .cmp(&b.display_name.to_lowercase()),
+            ),
+            SortKey::Title => cmp_present(lower(Some(a.title())), lower(Some(b.title()))),
+            SortKey::Artist => cmp_present(lower(a.artist()), lower(b.artist())),
+            SortKey::Album => cmp_present(lower(a.album()), lower(b.album())).map(|o| {
+                let disc_track = |t: &Track| {
+                    let tags = t.tags.as_ref();
+                    (
+                        tags.and_then(|m| m.disc_number).unwrap_or(0),
+                        tags.and_then(|m| m.track_number).unwrap_or(0),
+                    )
+                };
+                // Within an album, keep disc/track order ascending even when the
+                // albums themselves are listed in reverse.
+                let within = disc_track(a).cmp(&disc_track(b));
+                o.then(if order.descending { within.reverse() } else { within })
+            }),
+            SortKey::Duration => cmp_present(a.duration(), b.duration()),
+            SortKey::DateAdded => cmp_present(a.added, b.added),
+            SortKey::PlayCount => Some(stats_of(a).plays.cmp(&stats_of(b).plays)),
+            SortKey::Skips => Some(stats_of(a).skips.cmp(&stats_of(b).skips)),
+            SortKey::LastPlayed => cmp_present(stats_of(a).last_played, stats_of(b).last_played),
+            SortKey::Rating => cmp_present(
+                a.tags.as_ref().and_then(|m| m.rating),
+                b.tags.as_ref().and_then(|m| m.rating),
+            ),
+        };
+
+        match primary {
+            Some(o) => {
+                let o = if order.descending { o.reverse() } else { o };
+                o.then_with(|| by_path(a, b))
+            }
+            // Exactly one side is missing a value.
+            None => match (
+                has_value(a, order.key, stats),
+                has_value(b, order.key, stats),
+            ) {
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                _ => by_path(a, b),
+            },
+        }
+    });
+    indices
+}
+
+/// Compares two optional values; `None` if either is missing.
+fn cmp_present<T: Ord>(a: Option<T>, b: Option<T>) -> Option<Ordering> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => None,
+    }
+}
+
+fn has_value(t: &Track, key: SortKey, stats: &HashMap<TrackKey, PlayStats>) -> bool {
+    match key {
+        SortKey::Path | SortKey::FileName | SortKey::Title => true,
+        SortKey::Artist => t.artist().is_some(),
+        SortKey::Album => t.album().is_some(),
+        SortKey::Duration => t.duration().is_some(),
+        SortKey::DateAdded => t.added.is_some(),
+        SortKey::PlayCount | SortKey::Skips => stats.contains_key(&t.key()),
+        SortKey::LastPlayed => stats.get(&t.key()).is_some_and(|s| s.last_played.is_some()),
+        SortKey::Rating => t.tags.as_ref().and_then(|m| m.rating).is_some(),
+    }
+}
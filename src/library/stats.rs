@@ -0,0 +1,336 @@
+//! Listening statistics worked out from the play history: top tracks, artists
+//! and albums, listening time, streaks and the hours music is played at.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+
+use crate::{meta::TrackMeta, util::local_day_hour};
+
+use super::{HistoryEntry, ListenEnd};
+
+/// Skips this early don't count as a play.
+const QUICK_SKIP: Duration = Duration::from_secs(30);
+/// Entries in each top list.
+const TOP: usize = 10;
+
+/// The stretch of history a set of stats covers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Period {
+    /// The last 7 days.
+    Week,
+    /// The last 30 days.
+    Month,
+    AllTime,
+}
+
+impl Period {
+    pub(crate) const ALL: [Period; 3] = [Period::Week, Period::Month, Period::AllTime];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Period::Week => "Last 7 days",
+            Period::Month => "Last 30 days",
+            Period::AllTime => "All time",
+        }
+    }
+
+    fn span(self) -> Option<Duration> {
+        match self {
+            Period::Week => Some(Duration::from_secs(7 * 86_400)),
+            Period::Month => Some(Duration::from_secs(30 * 86_400)),
+            Period::AllTime => None,
+        }
+    }
+}
+
+/// A played track: its file, and where it starts in it (non-zero only for
+/// CUE tracks).
+type Played<'a> = (&'a Path, Duration);
+
+/// What the stats know about a played track, from its tags.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PlayedFile {
+    pub(crate) title: String,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) album_artist: Option<String>,
+}
+
+impl PlayedFile {
+    /// Falls back to the file name without tags.
+    pub(crate) fn new(path: &Path, tags: Option<&TrackMeta>) -> Self {
+        let file_name = || {
+            path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string())
+        };
+        let tag = |field: fn(&TrackMeta) -> &Option<String>| {
+            tags.and_then(|m| field(m).clone())
+                .filter(|s| !s.trim().is_empty())
+        };
+        Self {
+            title: tag(|m| &m.title).unwrap_or_else(file_name),
+            artist: tag(|m| &m.artist),
+            album: tag(|m| &m.album),
+            album_artist: tag(|m| &m.album_artist),
+        }
+    }
+
+    fn name(&self) -> String {
+        match &self.artist {
+            Some(artist) => format!("{artist} - {}", self.title),
+            None => self.title.clone(),
+        }
+    }
+
+    fn album_name(&self) -> Option<String> {
+        let album = self.album.as_ref()?;
+        Some(match self.album_artist.as_ref().or(self.artist.as_ref()) {
+            Some(artist) => format!("{album} ({artist})"),
+            None => album.clone(),
+        })
+    }
+}
+
+/// A line of a top list.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Ranked {
+    pub(crate) name: String,
+    /// Set for tracks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) path: Option<String>,
+    /// Plays, or skips in the most skipped list.
+    pub(crate) count: u32,
+    pub(crate) listened_secs: u64,
+}
+
+/// Stats over one period.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct PeriodStats {
+    pub(crate) period: Period,
+    pub(crate) listens: u32,
+    pub(crate) listened_secs: u64,
+    pub(crate) top_tracks: Vec<Ranked>,
+    pub(crate) top_artists: Vec<Ranked>,
+    pub(crate) top_albums: Vec<Ranked>,
+    pub(crate) most_skipped: Vec<Ranked>,
+    /// Seconds listened in each hour of the day, by local time of the start.
+    pub(crate) by_hour: [u64; 24],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ListeningStats {
+    /// Days in a row with something played, up to today (or yesterday).
+    pub(crate) current_streak: u32,
+    pub(crate) longest_streak: u32,
+    pub(crate) periods: Vec<PeriodStats>,
+}
+
+impl ListeningStats {
+    /// Works the stats out as of `now`. `describe` is asked once per track,
+    /// with its file and start offset, so CUE tracks of one file count apart.
+    pub(crate) fn compute(
+        history: &[HistoryEntry],
+        now: SystemTime,
+        mut describe: impl FnMut(&Path, Duration) -> PlayedFile,
+    ) -> Self {
+        let mut files: HashMap<Played, PlayedFile> = HashMap::new();
+        for entry in history {
+            files
+                .entry((&entry.path, entry.start))
+                .or_insert_with(|| describe(&entry.path, entry.start));
+        }
+
+        let periods = Period::ALL
+            .into_iter()
+            .map(|period| {
+                let since = period.span().and_then(|span| now.checked_sub(span));
+                let entries = history
+                    .iter()
+                    .filter(|e| since.is_none_or(|since| e.at >= since));
+                PeriodStats::compute(period, entries, &files)
+            })
+            .collect();
+
+        let mut days: Vec<i64> = history.iter().map(|e| local_day_hour(e.at).0).collect();
+        days.sort_unstable();
+        days.dedup();
+        let (mut run, mut longest) = (0, 0);
+        for (i, &day) in days.iter().enumerate() {
+            run = if i > 0 && days[i - 1] == day - 1 {
+                run + 1
+            } else {
+                1
+            };
+            longest = longest.max(run);
+        }
+        let today = local_day_hour(now).0;
+        let current = match days.last() {
+            Some(&last) if last >= today - 1 => run,
+            _ => 0,
+        };
+
+        Self {
+            current_streak: current,
+            longest_streak: longest,
+            periods,
+        }
+    }
+
+    pub(crate) fn period(&self, period: Period) -> &PeriodStats {
+        self.periods
+            .iter()
+            .find(|p| p.period == period)
+            .expect("stats cover every period")
+    }
+}
+
+/// Plays and time listened, to rank by.
+#[derive(Default)]
+struct Tally {
+    count: u32,
+    listened: Duration,
+}
+
+impl PeriodStats {
+    fn compute<'a>(
+        period: Period,
+        entries: impl Iterator<Item = &'a HistoryEntry>,
+        files: &HashMap<Played, PlayedFile>,
+    ) -> Self {
+        let mut stats = PeriodStats {
+            period,
+            listens: 0,
+            listened_secs: 0,
+            top_tracks: Vec::new(),
+            top_artists: Vec::new(),
+            top_albums: Vec::new(),
+            most_skipped: Vec::new(),
+            by_hour: [0; 24],
+        };
+        let mut tracks: HashMap<Played, Tally> = HashMap::new();
+        let mut artists: HashMap<String, Tally> = HashMap::new();
+        let mut albums: HashMap<String, Tally> = HashMap::new();
+        let mut skipped: HashMap<Played, Tally> = HashMap::new();
+
+        for entry in entries {
+            let track = (entry.path.as_path(), entry.start);
+            let Some(file) = files.get(&track) else {
+                continue;
+            };
+            stats.listens += 1;
+            stats.listened_secs += entry.listened.as_secs();
+            stats.by_hour[local_day_hour(entry.at).1 as usize % 24] += entry.listened.as_secs();
+
+            let skip = entry.end == ListenEnd::Skipped;
+            let played = u32::from(!skip || entry.listened >= QUICK_SKIP);
+            let add = |tally: &mut Tally, count: u32| {
+                tally.count += count;
+                tally.listened += entry.listened;
+            };
+            add(tracks.entry(track).or_default(), played);
+            if let Some(artist) = &file.artist {
+                add(artists.entry(artist.clone()).or_default(), played);
+            }
+            if let Some(album) = file.album_name() {
+                add(albums.entry(album).or_default(), played);
+            }
+            if skip {
+                add(skipped.entry(track).or_default(), 1);
+            }
+        }
+
+        let by_path = |tally: HashMap<Played, Tally>| {
+            tally
+                .into_iter()
+                .map(|(track, t)| {
+                    let name = files.get(&track).map(PlayedFile::name).unwrap_or_default();
+                    let path = track.0;
+                    (name, Some(path.display().to_string()), t)
+                })
+                .collect::<Vec<_>>()
+        };
+        let by_name = |tally: HashMap<String, Tally>| {
+            tally
+                .into_iter()
+                .map(|(name, t)| (name, None, t))
+                .collect::<Vec<_>>()
+        };
+        stats.top_tracks = top(by_path(tracks));
+        stats.top_artists = top(by_name(artists));
+        stats.top_albums = top(by_name(albums));
+        stats.most_skipped = top(by_path(skipped));
+        stats
+    }
+}
+
+/// The `TOP` highest counts, then most time listened; zero counts are left out.
+fn top(entries: Vec<(String, Option<String>, Tally)>) -> Vec<Ranked> {
+    let mut ranked: Vec<Ranked> = entries
+        .into_iter()
+        .filter(|(_, _, t)| t.count > 0)
+        .map(|(name, path, t)| Ranked {
+            name,
+            path,
+            count: t.count,
+            listened_secs: t.listened.as_secs(),
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.listened_secs.cmp(&a.listened_secs))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    ranked.truncate(TOP);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn counts_cue_tracks_of_one_file_apart() {
+        let now = SystemTime::now();
+        let listen = |start_secs, end| HistoryEntry {
+            at: now - Duration::from_secs(3600),
+            path: PathBuf::from("/music/album.flac"),
+            start: Duration::from_secs(start_secs),
+            listened: Duration::from_secs(200),
+            end,
+        };
+        let history = [
+            listen(0, ListenEnd::Completed),
+            listen(0, ListenEnd::Completed),
+            listen(300, ListenEnd::Skipped),
+        ];
+        let mut asked = Vec::new();
+        let stats = ListeningStats::compute(&history, now, |_, start| {
+            asked.push(start);
+            PlayedFile {
+                title: format!("Track at {}", start.as_secs()),
+                ..PlayedFile::default()
+            }
+        });
+        assert_eq!(asked.len(), 2);
+
+        let week = stats.period(Period::Week);
+        let top: Vec<(&str, u32)> = week
+            .top_tracks
+            .iter()
+            .map(|r| (r.name.as_str(), r.count))
+            .collect();
+        assert_eq!(top, [("Track at 0", 2), ("Track at 300", 1)]);
+        assert_eq!(week.most_skipped.len(), 1);
+        assert_eq!(week.most_skipped[0].name, "Track at 300");
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::Track;
+use crate::meta::TrackMeta;
+
+/// Identifies a track across rescans and re-sorts: its file and start offset.
+pub(crate) type TrackKey = (std::path::PathBuf, Duration);
+
+/// How often the loader hands finished tags to the UI thread.
+const BATCH_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Reads per-track data on a background thread so large libraries stay
+/// responsive: tags for the list, lyrics for search, the cover of the playing
+/// track.
+///
+/// Dropping the loader stops the thread at its next batch.
+#[derive(Debug)]
+pub(crate) struct BackgroundLoader<T> {
+    rx: Receiver<Vec<(TrackKey, T)>>,
+}
+
+pub(crate) type TagLoader = BackgroundLoader<TrackMeta>;
+
+impl TagLoader {
+    /// Starts loading tags for every track that doesn't have them yet.
+    /// Returns `None` if there is nothing to load.
+    pub(crate) fn spawn_tags(tracks: &[Track]) -> Option<Self> {
+        let todo: Vec<Track> = tracks.iter().filter(|t| t.tags.is_none()).cloned().collect();
+        Self::spawn(todo, Track::read_meta)
+    }
+}
+
+impl<T: Send + 'static> BackgroundLoader<T> {
+    /// Starts running `load` on each of `todo`. Returns `None` if it is empty.
+    pub(crate) fn spawn(todo: Vec<Track>, load: fn(&Track) -> T) -> Option<Self> {
+        if todo.is_empty() {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut last_send = Instant::now();
+            for track in todo {
+                let value = load(&track);
+                batch.push((track.key(), value));
+                if last_send.elapsed() >= BATCH_INTERVAL {
+                    if tx.send(std::mem::take(&mut batch)).is_err() {
+                        return;
+                    }
+                    last_send = Instant::now();
+                }
+            }
+            let _ = tx.send(batch);
+        });
+        Some(Self { rx })
+    }
+
+    /// Returns the values loaded since the last call, and whether loading has finished.
+    pub(crate) fn poll(&self) -> (Vec<(TrackKey, T)>, bool) {
+        let mut out = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(batch) => out.extend(batch),
+                Err(TryRecvError::Empty) => return (out, false),
+                Err(TryRecvError::Disconnected) => return (out, true),
+            }
+        }
+    }
+}
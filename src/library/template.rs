@@ -0,0 +1,278 @@
+use std::path::PathBuf;
+
+use super::Track;
+
+/// A string with `{field}` placeholders filled from a track's tags,
+/// e.g. `{artist} - {title}`. Numbers can be zero-padded: `{track:02}`.
+#[derive(Debug, Clone)]
+pub(crate) struct TagTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    /// A field, with the zero-padded width given after a colon.
+    Field(Field, Option<usize>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Composer,
+    Genre,
+    Year,
+    Track,
+    Disc,
+    FileName,
+    Ext,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        Some(match name.trim().to_ascii_lowercase().as_str() {
+            "title" => Field::Title,
+            "artist" => Field::Artist,
+            "album" => Field::Album,
+            "albumartist" | "album_artist" => Field::AlbumArtist,
+            "composer" => Field::Composer,
+            "genre" => Field::Genre,
+            "year" => Field::Year,
+            "track" | "tracknumber" => Field::Track,
+            "disc" | "discnumber" => Field::Disc,
+            "filename" | "file" => Field::FileName,
+            "ext" | "extension" => Field::Ext,
+            _ => return None,
+        })
+    }
+
+    fn is_number(self) -> bool {
+        matches!(self, Field::Year | Field::Track | Field::Disc)
+    }
+
+    fn value(self, track: &Track, width: Option<usize>) -> Option<String> {
+        match self {
+            Field::FileName => return Some(track.display_name.clone()),
+            Field::Ext => {
+                let ext = track.path.extension()?.to_string_lossy().to_ascii_lowercase();
+                return Some(ext);
+            }
+            _ => {}
+        }
+        let meta = track.tags.as_ref()?;
+        let text = |s: &Option<String>| {
+            s.as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+        };
+        match self {
+            Field::Title => text(&meta.title),
+            Field::Artist => text(&meta.artist),
+            Field::Album => text(&meta.album),
+            Field::AlbumArtist => text(&meta.album_artist).or_else(|| text(&meta.artist)),
+            Field::Composer => text(&meta.composer),
+            Field::Genre => text(&meta.genre),
+            Field::Year => meta.year().map(|y| format!("{y:0w$}", w = width.unwrap_or(0))),
+            Field::Track => meta
+                .track_number
+                .map(|n| format!("{n:0w$}", w = width.unwrap_or(0))),
+            Field::Disc => meta
+                .disc_number
+                .map(|n| format!("{n:0w$}", w = width.unwrap_or(0))),
+            Field::FileName | Field::Ext => unreachable!(),
+        }
+    }
+}
+
+impl TagTemplate {
+    /// Parses a template. `{{` and `}}` stand for literal braces; a `{` that is
+    /// never closed is an error.
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return Err(format!("unclosed {{{spec}")),
+                        }
+                    }
+                    let (name, width) = match spec.split_once(':') {
+                        Some((name, width)) => (name, Some(width)),
+                        None => (spec.as_str(), None),
+                    };
+                    let field =
+                        Field::parse(name).ok_or_else(|| format!("unknown field {{{name}}}"))?;
+                    let width = match width {
+                        None => None,
+                        Some(w) if field.is_number() => Some(
+                            w.trim()
+                                .parse()
+                                .map_err(|_| format!("invalid width in {{{spec}}}"))?,
+                        ),
+                        Some(_) => return Err(format!("{{{name}}} is not a number field")),
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field, width));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Fills in the template, or `None` if the track lacks any field it uses
+    /// (including when its tags haven't been loaded yet).
+    pub(crate) fn render(&self, track: &Track) -> Option<String> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(t) => out.push_str(t),
+                Part::Field(f, width) => out.push_str(&f.value(track, *width)?),
+            }
+        }
+        Some(out)
+    }
+
+    /// Fills in the template as a relative file path. `/` in the template separates
+    /// directories; in tag values it (and other characters that can't appear in file
+    /// names) is replaced, so `AC/DC` stays one directory.
+    pub(crate) fn render_path(&self, track: &Track) -> Option<PathBuf> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(t) => out.push_str(t),
+                Part::Field(f, width) => out.push_str(&sanitize(&f.value(track, *width)?)),
+            }
+        }
+        let mut path = PathBuf::new();
+        for component in out.split('/').map(str::trim) {
+            if component == ".." {
+                return None;
+            }
+            // Trailing dots and spaces are dropped (Windows and SMB shares reject them).
+            let component = component.trim_end_matches(['.', ' ']);
+            if !component.is_empty() {
+                path.push(component);
+            }
+        }
+        (!path.as_os_str().is_empty()).then_some(path)
+    }
+}
+
+/// Makes a tag value safe to use as (part of) a file name.
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '\0' => '_',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    // A leading dot would hide the file.
+    cleaned.trim().trim_start_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::meta::TrackMeta;
+
+    fn track() -> Track {
+        let mut track = Track::from_path(PathBuf::from("/music/in/01.FLAC"));
+        track.tags = Some(TrackMeta {
+            title: Some("Song".to_string()),
+            artist: Some("AC/DC".to_string()),
+            album: Some("Album".to_string()),
+            date: Some("1980-07-25".to_string()),
+            track_number: Some(7),
+            disc_number: Some(1),
+            ..TrackMeta::default()
+        });
+        track
+    }
+
+    fn render(template: &str) -> Option<String> {
+        TagTemplate::parse(template).unwrap().render(&track())
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(render("{{title}}").as_deref(), Some("{title}"));
+        assert_eq!(render("{{{title}}}").as_deref(), Some("{Song}"));
+        assert_eq!(render("a }} b").as_deref(), Some("a } b"));
+    }
+
+    #[test]
+    fn pads_numbers_to_the_given_width() {
+        assert_eq!(render("{track}").as_deref(), Some("7"));
+        assert_eq!(render("{track:02}").as_deref(), Some("07"));
+        assert_eq!(render("{disc:3}-{year}").as_deref(), Some("001-1980"));
+        assert_eq!(
+            TagTemplate::parse("{track:x}").unwrap_err(),
+            "invalid width in {track:x}"
+        );
+        assert_eq!(
+            TagTemplate::parse("{title:02}").unwrap_err(),
+            "{title} is not a number field"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_unclosed_braces() {
+        assert_eq!(
+            TagTemplate::parse("{artist} - {name}").unwrap_err(),
+            "unknown field {name}"
+        );
+        assert_eq!(
+            TagTemplate::parse("{artist").unwrap_err(),
+            "unclosed {artist"
+        );
+        assert_eq!(TagTemplate::parse("{").unwrap_err(), "unclosed {");
+    }
+
+    #[test]
+    fn field_names_ignore_case_and_spaces() {
+        assert_eq!(render("{ Title } ({EXT})").as_deref(), Some("Song (flac)"));
+        assert_eq!(render("{album_artist}").as_deref(), Some("AC/DC"));
+    }
+
+    #[test]
+    fn a_missing_tag_leaves_nothing_to_render() {
+        assert_eq!(render("{title} {genre}"), None);
+    }
+
+    #[test]
+    fn keeps_slashes_in_tags_out_of_the_path() {
+        let template = TagTemplate::parse("{artist}/{album}/{track:02}. {title}.{ext}").unwrap();
+        assert_eq!(
+            template.render_path(&track()).as_deref(),
+            Some(Path::new("AC_DC/Album/07. Song.flac"))
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Moving files to the freedesktop.org trash, and restoring them.
+//!
+//! Files on the home filesystem go to `$XDG_DATA_HOME/Trash`; files on other
+//! mounts to `$topdir/.Trash-$uid`, as the trash spec asks. Each trashed file
+//! gets a `.trashinfo` with its original path, so file managers can restore it.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{DirBuilderExt, MetadataExt},
+    },
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::util::{data_home, local_datetime};
+
+use super::organize::move_file;
+
+/// A file moved to the trash by [`move_to_trash`].
+#[derive(Debug, Clone)]
+pub(crate) struct Trashed {
+    pub(crate) original: PathBuf,
+    /// The file inside the trash's `files` directory.
+    file: PathBuf,
+    info: PathBuf,
+}
+
+impl Trashed {
+    /// Moves the file back to where it was.
+    pub(crate) fn restore(&self) -> Result<()> {
+        if fs::symlink_metadata(&self.original).is_ok() {
+            bail!("{} exists again", self.original.display());
+        }
+        move_file(&self.file, &self.original)
+            .with_context(|| format!("restore {}", self.original.display()))?;
+        let _ = fs::remove_file(&self.info);
+        Ok(())
+    }
+}
+
+/// Moves `path` to the trash.
+pub(crate) fn move_to_trash(path: &Path) -> Result<Trashed> {
+    // The directory is resolved, not the file: a symlink is trashed, not its target.
+    let name = path.file_name().context("no file name")?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let path = dir
+        .canonicalize()
+        .with_context(|| format!("find {}", dir.display()))?
+        .join(name);
+    let home_trash = home_trash().context("no trash directory ($HOME is not set)")?;
+
+    let file_dev = fs::symlink_metadata(&path)?.dev();
+    let home_dev = home_trash
+        .ancestors()
+        .find_map(|dir| fs::metadata(dir).ok())
+        .map(|m| m.dev());
+    if home_dev != Some(file_dev) {
+        if let Some(top) = mount_point(&path) {
+            let trash = top.join(format!(".Trash-{}", uid()));
+            // Outside the home filesystem the recorded path is relative to the mount.
+            let relative = path.strip_prefix(&top).unwrap_or(&path).to_path_buf();
+            if let Ok(trashed) = trash_into(&trash, &path, &relative) {
+                return Ok(trashed);
+            }
+        }
+    }
+    // Also when the mount has no usable trash; the file is then copied home.
+    trash_into(&home_trash, &path, &path)
+}
+
+/// `$XDG_DATA_HOME/Trash` (or `~/.local/share/Trash`).
+fn home_trash() -> Option<PathBuf> {
+    Some(data_home()?.join("Trash"))
+}
+
+/// The top directory of the filesystem `path` is on.
+fn mount_point(path: &Path) -> Option<PathBuf> {
+    let dev = fs::symlink_metadata(path).ok()?.dev();
+    let mut top = path;
+    for dir in path.ancestors().skip(1) {
+        if fs::metadata(dir).ok()?.dev() != dev {
+            break;
+        }
+        top = dir;
+    }
+    Some(top.to_path_buf())
+}
+
+fn uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    unsafe { libc::getuid() }
+}
+
+/// Moves `path` into `trash`, recording `recorded` as where it came from.
+fn trash_into(trash: &Path, path: &Path, recorded: &Path) -> Result<Trashed> {
+    let files = trash.join("files");
+    let info_dir = trash.join("info");
+    for dir in [&files, &info_dir] {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("create {}", dir.display()))?;
+    }
+
+    let content = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(recorded),
+        local_datetime(SystemTime::now())
+    );
+    let name = path.file_name().context("no file name")?;
+
+    // Creating the info file claims the name; another program trashing a file
+    // with the same name at the same time gets the next one.
+    for n in 1u32.. {
+        let name = numbered_name(name, n);
+        let mut info_name = name.clone();
+        info_name.push(".trashinfo");
+        let info = info_dir.join(info_name);
+        let file = files.join(&name);
+        let mut out = match OpenOptions::new().write(true).create_new(true).open(&info) {
+            Ok(out) => out,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("create {}", info.display())),
+        };
+        if fs::symlink_metadata(&file).is_ok() {
+            drop(out);
+            let _ = fs::remove_file(&info);
+            continue;
+        }
+        let written = out.write_all(content.as_bytes());
+        let moved = written.and_then(|()| move_file(path, &file));
+        if let Err(e) = moved {
+            let _ = fs::remove_file(&info);
+            return Err(e).with_context(|| format!("move {} to the trash", path.display()));
+        }
+        return Ok(Trashed {
+            original: path.to_path_buf(),
+            file,
+            info,
+        });
+    }
+    unreachable!("ran out of trash names")
+}
+
+/// `Song.mp3`, then `Song.2.mp3`, `Song.3.mp3`, …
+fn numbered_name(name: &std::ffi::OsStr, n: u32) -> OsString {
+    if n == 1 {
+        return name.to_os_string();
+    }
+    let path = Path::new(name);
+    let mut out = OsString::from(path.file_stem().unwrap_or(name));
+    out.push(format!(".{n}"));
+    if let Some(ext) = path.extension() {
+        out.push(".");
+        out.push(ext);
+    }
+    out
+}
+
+/// Escapes a path the way `.trashinfo` files store it (RFC 2396, `/` kept).
+fn percent_encode(path: &Path) -> String {
+    let mut out = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.!~*'()/".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
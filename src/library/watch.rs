@@ -0,0 +1,245 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+
+use super::{discover_tracks, Discovery, LibraryScan};
+
+/// How long the library must be quiet before accumulated changes are applied.
+/// Downloads and tag editors write in bursts; this coalesces them into one rescan.
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Rescan interval for roots where inotify can't see remote changes (NFS, SMB, FUSE).
+const NETWORK_RESCAN: Duration = Duration::from_secs(30);
+
+/// How often the rescan thread checks whether changes have settled.
+const RESCAN_CHECK: Duration = Duration::from_millis(100);
+
+/// How long a `MOVED_FROM` waits for its `MOVED_TO`. The two arrive together, but
+/// may be split across reads when the event buffer fills up.
+const MOVE_PAIRING: Duration = Duration::from_secs(1);
+
+/// Changes observed under the library roots since the last call to `take_changes`.
+#[derive(Debug, Default)]
+pub(crate) struct LibraryChanges {
+    /// Files whose contents were rewritten in place (e.g. tag edits).
+    pub(crate) modified: HashSet<PathBuf>,
+    /// `(from, to)` pairs for renames and moves within the library (files or directories).
+    pub(crate) renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+impl LibraryChanges {
+    /// Where `path` is now, if it (or a directory above it) was renamed.
+    pub(crate) fn new_path(&self, path: &Path) -> Option<PathBuf> {
+        self.renamed.iter().rev().find_map(|(from, to)| {
+            path.strip_prefix(from).ok().map(|rest| {
+                if rest.as_os_str().is_empty() {
+                    to.clone()
+                } else {
+                    to.join(rest)
+                }
+            })
+        })
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    changes: LibraryChanges,
+    dirty: bool,
+    last_event: Option<Instant>,
+}
+
+/// Watches the library with inotify and reports debounced change sets, each
+/// with a fresh scan of the library.
+///
+/// Directories are watched recursively; new subdirectories are picked up as they
+/// appear. If inotify is unavailable (or the root is on a network filesystem, where
+/// it only sees local writes) the watcher falls back to a periodic rescan. Scans
+/// run on a thread of their own, so a large or remote library doesn't hold up the UI.
+pub(crate) struct LibraryWatcher {
+    updates: Receiver<(LibraryChanges, Discovery)>,
+}
+
+impl LibraryWatcher {
+    pub(crate) fn spawn(scan: &LibraryScan) -> Self {
+        let pending = Arc::new(Mutex::new(Pending::default()));
+        let (tx, updates) = mpsc::channel();
+
+        let mut poll_interval = scan
+            .roots
+            .iter()
+            .any(|root| is_network_fs(root))
+            .then_some(NETWORK_RESCAN);
+        let mut watching = false;
+        if scan.roots.iter().any(|root| root.is_dir()) {
+            match Inotify::init() {
+                Ok(inotify) => {
+                    let pending = Arc::clone(&pending);
+                    let scan = scan.clone();
+                    thread::spawn(move || watch_loop(inotify, scan, pending));
+                    watching = true;
+                }
+                Err(e) => {
+                    eprintln!("trix: inotify unavailable ({e}); rescanning periodically");
+                    poll_interval = Some(NETWORK_RESCAN);
+                }
+            }
+        }
+        if watching || poll_interval.is_some() {
+            let scan = scan.clone();
+            thread::spawn(move || rescan_loop(scan, pending, poll_interval, tx));
+        }
+
+        Self { updates }
+    }
+
+    /// Returns the next batch of changes together with the rescan they were
+    /// followed by, once one has finished; `None` otherwise.
+    pub(crate) fn take_changes(&mut self) -> Option<(LibraryChanges, Discovery)> {
+        self.updates.try_recv().ok()
+    }
+}
+
+/// Rescans the library once changes have been quiet for the debounce period (or
+/// the periodic rescan is due), and sends the changes with the result.
+fn rescan_loop(
+    scan: LibraryScan,
+    pending: Arc<Mutex<Pending>>,
+    poll_interval: Option<Duration>,
+    updates: Sender<(LibraryChanges, Discovery)>,
+) {
+    let mut last_rescan = Instant::now();
+    loop {
+        thread::sleep(RESCAN_CHECK);
+        let poll_due = poll_interval.is_some_and(|every| last_rescan.elapsed() >= every);
+
+        let Ok(mut p) = pending.lock() else { return };
+        let settled = p.dirty && p.last_event.is_some_and(|at| at.elapsed() >= DEBOUNCE);
+        if !settled && !poll_due {
+            continue;
+        }
+        p.dirty = false;
+        p.last_event = None;
+        let changes = std::mem::take(&mut p.changes);
+        drop(p);
+
+        last_rescan = Instant::now();
+        let Ok(discovery) = discover_tracks(&scan) else {
+            continue;
+        };
+        if updates.send((changes, discovery)).is_err() {
+            return;
+        }
+    }
+}
+
+const MASK: WatchMask = WatchMask::CREATE
+    .union(WatchMask::DELETE)
+    .union(WatchMask::MOVED_FROM)
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::CLOSE_WRITE)
+    .union(WatchMask::DELETE_SELF);
+
+fn watch_loop(mut inotify: Inotify, scan: LibraryScan, pending: Arc<Mutex<Pending>>) {
+    let mut dirs: HashMap<WatchDescriptor, PathBuf> = HashMap::new();
+    for root in scan.roots.iter().filter(|r| r.is_dir()) {
+        add_watches(&mut inotify, &scan, root, &mut dirs);
+    }
+
+    // MOVED_FROM/MOVED_TO pairs share a cookie.
+    let mut moved_from: HashMap<u32, (PathBuf, Instant)> = HashMap::new();
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        let events = match inotify.read_events_blocking(&mut buffer) {
+            Ok(events) => events,
+            Err(_) => return,
+        };
+
+        let mut new_dirs: Vec<PathBuf> = Vec::new();
+        let Ok(mut p) = pending.lock() else { return };
+
+        for event in events {
+            p.dirty = true;
+            p.last_event = Some(Instant::now());
+
+            if event.mask.contains(EventMask::IGNORED) {
+                dirs.remove(&event.wd);
+                continue;
+            }
+
+            let (Some(dir), Some(name)) = (dirs.get(&event.wd), event.name) else {
+                continue;
+            };
+            let path = dir.join(name);
+            let is_dir = event.mask.contains(EventMask::ISDIR);
+
+            if event.mask.contains(EventMask::CLOSE_WRITE) {
+                p.changes.modified.insert(path);
+            } else if event.mask.contains(EventMask::MOVED_FROM) {
+                moved_from.insert(event.cookie, (path, Instant::now()));
+            } else if event.mask.contains(EventMask::MOVED_TO) {
+                if let Some((from, _)) = moved_from.remove(&event.cookie) {
+                    p.changes.renamed.push((from, path.clone()));
+                }
+                if is_dir {
+                    new_dirs.push(path);
+                } else {
+                    // Editors and taggers save by renaming a temp file over the
+                    // original; treat the target as rewritten.
+                    p.changes.modified.insert(path);
+                }
+            } else if event.mask.contains(EventMask::CREATE) && is_dir {
+                new_dirs.push(path);
+            }
+        }
+        drop(p);
+
+        // MOVED_FROM events still unpaired after a while are moves out of the
+        // library; the rescan drops those files.
+        moved_from.retain(|_, (_, at)| at.elapsed() < MOVE_PAIRING);
+
+        for dir in new_dirs.into_iter().filter(|d| !scan.is_excluded(d)) {
+            add_watches(&mut inotify, &scan, &dir, &mut dirs);
+        }
+    }
+}
+
+fn add_watches(
+    inotify: &mut Inotify,
+    scan: &LibraryScan,
+    root: &Path,
+    dirs: &mut HashMap<WatchDescriptor, PathBuf>,
+) {
+    for entry in scan.walk(root).filter(|e| e.file_type().is_dir()) {
+        // Running out of watches (fs.inotify.max_user_watches) is not fatal: the
+        // directories we did watch still update live.
+        if let Ok(wd) = inotify.watches().add(entry.path(), MASK) {
+            dirs.insert(wd, entry.path().to_path_buf());
+        }
+    }
+}
+
+/// Returns true if `path` lives on a filesystem where inotify misses remote writes.
+fn is_network_fs(path: &Path) -> bool {
+    use nix::sys::statfs::{statfs, FsType, FUSE_SUPER_MAGIC, NFS_SUPER_MAGIC, SMB_SUPER_MAGIC};
+
+    // Magic numbers not exported by nix.
+    const CIFS_MAGIC: FsType = FsType(0xff53_4d42_u32 as _);
+    const SMB2_MAGIC: FsType = FsType(0xfe53_4d42_u32 as _);
+
+    let Ok(stat) = statfs(path) else {
+        return false;
+    };
+    [NFS_SUPER_MAGIC, SMB_SUPER_MAGIC, CIFS_MAGIC, SMB2_MAGIC, FUSE_SUPER_MAGIC]
+        .contains(&stat.filesystem_type())
+}
@@ -1,11 +1,147 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-#[derive(Clone, Debug)]
+use anyhow::{Context, Result};
+
+use crate::{meta, subtitles};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct LrcEntry {
     pub(crate) time: Duration,
     pub(crate) text: String,
+    /// Word timings from enhanced LRC (`<mm:ss.xx>word`); empty for plain lines.
+    /// Their texts concatenate to `text`.
+    pub(crate) words: Vec<LrcWord>,
+    /// When the line stops showing, for subtitle cues; LRC lines last until the next.
+    pub(crate) end: Option<Duration>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LrcWord {
+    pub(crate) time: Duration,
+    pub(crate) text: String,
+}
+
+impl LrcEntry {
+    /// Whether the line is still showing at `pos` (it has started).
+    pub(crate) fn showing(&self, pos: Duration) -> bool {
+        self.end.is_none_or(|end| pos < end)
+    }
+
+    /// How many characters of `text` have been sung at `pos`, interpolating
+    /// through the current word. `line_end` is when the next line starts.
+    pub(crate) fn sung_chars(&self, pos: Duration, line_end: Option<Duration>) -> usize {
+        let mut sung = 0;
+        for (i, word) in self.words.iter().enumerate() {
+            if word.time > pos {
+                break;
+            }
+            let len = word.text.chars().count();
+            let end = self
+                .words
+                .get(i + 1)
+                .map(|w| w.time)
+                .or(self.end)
+                .or(line_end);
+            match end {
+                Some(end) if pos < end && end > word.time => {
+                    let done = (pos - word.time).as_secs_f64() / (end - word.time).as_secs_f64();
+                    return sung + (len as f64 * done) as usize;
+                }
+                _ => sung += len,
+            }
+        }
+        sung
+    }
+
+    /// The entry as an LRC line, with its word timestamps.
+    pub(crate) fn lrc_line(&self) -> String {
+        let mut line = format!("[{}]", format_lrc_time(self.time));
+        if self.words.is_empty() {
+            line.push_str(&self.text);
+        }
+        for word in &self.words {
+            line.push_str(&format!("<{}>{}", format_lrc_time(word.time), word.text));
+        }
+        line
+    }
+}
+
+/// Lyrics for a track, from a sidecar file or the track's own tags.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Lyrics {
+    /// Timed lines, sorted by time.
+    Synced(Vec<LrcEntry>),
+    /// Plain text with no timing, shown as a scrollable page.
+    Unsynced(String),
+}
+
+impl Lyrics {
+    /// The text without timing, one line per row.
+    pub(crate) fn plain_text(&self) -> String {
+        match self {
+            Lyrics::Synced(entries) => entries
+                .iter()
+                .map(|e| e.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Lyrics::Unsynced(text) => text.clone(),
+        }
+    }
+
+    /// Interprets lyrics text from a tag: embedded LRC is common, so text with
+    /// timestamps is treated as synced. Returns `None` for blank text.
+    pub(crate) fn from_text(text: &str) -> Option<Self> {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let entries = parse_lrc(&text);
+        if !entries.is_empty() {
+            return Some(Lyrics::Synced(entries));
+        }
+        let text = text.trim_matches('\n');
+        (!text.trim().is_empty()).then(|| Lyrics::Unsynced(text.to_string()))
+    }
+
+    /// The lines of a whole file's lyrics that fall in `[start, end)`, timed
+    /// from `start`: what a CUE track of the file shows. Plain text can't be
+    /// split up, so it is dropped.
+    pub(crate) fn slice(self, start: Duration, end: Option<Duration>) -> Option<Self> {
+        let Lyrics::Synced(entries) = self else {
+            return None;
+        };
+        let within = |t: Duration| t >= start && end.is_none_or(|end| t < end);
+        let from_start = |t: Duration| {
+            let t = end.map_or(t, |end| t.min(end));
+            t.saturating_sub(start)
+        };
+        let entries: Vec<LrcEntry> = entries
+            .into_iter()
+            .filter(|e| within(e.time))
+            .map(|e| LrcEntry {
+                time: from_start(e.time),
+                text: e.text,
+                words: e
+                    .words
+                    .into_iter()
+                    .map(|w| LrcWord {
+                        time: from_start(w.time),
+                        text: w.text,
+                    })
+                    .collect(),
+                end: e.end.map(from_start),
+            })
+            .collect();
+        (!entries.is_empty()).then_some(Lyrics::Synced(entries))
+    }
+}
+
+/// Loads lyrics for `audio_path`: a sidecar `.lrc` file (see [`load_lrc`]),
+/// `.srt`/`.vtt` subtitles, else lyrics embedded in its tags.
+pub(crate) fn load_lyrics(audio_path: &Path) -> Option<Lyrics> {
+    load_lrc(audio_path)
+        .or_else(|| subtitles::load_subtitles(audio_path))
+        .map(Lyrics::Synced)
+        .or_else(|| meta::embedded_lyrics(audio_path))
 }
 
 /// Loads and parses the `.lrc` file corresponding to `audio_path` if it exists.
@@ -13,33 +149,95 @@ pub(crate) struct LrcEntry {
 /// Tries both `track.lrc` (standard) and `track.<ext>.lrc` (some apps).
 /// Returns `None` if no file is found or parsing yields no entries.
 pub(crate) fn load_lrc(audio_path: &Path) -> Option<Vec<LrcEntry>> {
-    let mut p = audio_path.to_path_buf();
-    p.set_extension("lrc");
-    if let Ok(content) = fs::read_to_string(&p) {
-        let entries = parse_lrc(&content);
-        if !entries.is_empty() {
-            return Some(entries);
+    find_lrc(audio_path).map(|(_, entries)| entries)
+}
+
+/// Like [`load_lrc`], but also returns which file was used.
+fn find_lrc(audio_path: &Path) -> Option<(PathBuf, Vec<LrcEntry>)> {
+    sidecar_paths(audio_path, "lrc").into_iter().find_map(|p| {
+        let entries = parse_lrc(&fs::read_to_string(&p).ok()?);
+        (!entries.is_empty()).then_some((p, entries))
+    })
+}
+
+/// `track.<ext>` and `track.<audio ext>.<ext>` next to `audio_path`.
+pub(crate) fn sidecar_paths(audio_path: &Path, ext: &str) -> [PathBuf; 2] {
+    let long = match audio_path.extension() {
+        Some(audio_ext) => format!("{}.{ext}", audio_ext.to_string_lossy()),
+        None => ext.to_string(),
+    };
+    [
+        audio_path.with_extension(ext),
+        audio_path.with_extension(long),
+    ]
+}
+
+/// Reads plain lyrics from `track.txt` (or `track.<ext>.txt`) next to `audio_path`.
+pub(crate) fn load_plain_text(audio_path: &Path) -> Option<String> {
+    sidecar_paths(audio_path, "txt")
+        .into_iter()
+        .find_map(|p| fs::read_to_string(p).ok())
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Writes `entries` as an `.lrc` file, after `[key:value]` header tags. Lines
+/// that end before the next one starts are followed by an empty line.
+pub(crate) fn write_lrc(path: &Path, tags: &[(&str, String)], entries: &[LrcEntry]) -> Result<()> {
+    let mut out = String::new();
+    for (key, value) in tags {
+        out.push_str(&format!("[{key}:{value}]\n"));
+    }
+    for (i, e) in entries.iter().enumerate() {
+        out.push_str(&e.lrc_line());
+        out.push('\n');
+        let next = entries.get(i + 1).map(|n| n.time);
+        if let Some(end) = e.end.filter(|&end| next.is_none_or(|next| end < next)) {
+            out.push_str(&format!("[{}]\n", format_lrc_time(end)));
         }
     }
+    fs::write(path, out).with_context(|| format!("write {}", path.display()))
+}
 
-    // Fallback: try `track.<ext>.lrc`.
-    let p2 = {
-        let mut q = audio_path.to_path_buf();
-        if let Some(ext) = q.extension() {
-            let new_ext = format!("{}.lrc", ext.to_string_lossy());
-            q.set_extension(new_ext);
-        } else {
-            q.set_extension("lrc");
-        }
-        q
+/// Adds `adjust_ms` to the `[offset:]` tag of the `.lrc` file for `audio_path`.
+///
+/// Lyrics that came from the track's tags (`entries`) are written out as a new
+/// `track.lrc` carrying the offset. Returns the file written.
+pub(crate) fn save_offset(
+    audio_path: &Path,
+    entries: &[LrcEntry],
+    adjust_ms: i64,
+) -> Result<PathBuf> {
+    let Some((path, _)) = find_lrc(audio_path) else {
+        let path = audio_path.with_extension("lrc");
+        write_lrc(&path, &[("offset", format!("{adjust_ms:+}"))], entries)?;
+        return Ok(path);
     };
-    if let Ok(content) = fs::read_to_string(&p2) {
-        let entries = parse_lrc(&content);
-        if !entries.is_empty() {
-            return Some(entries);
+
+    let content = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+    let offset = content.lines().find_map(offset_tag).unwrap_or(0) + adjust_ms;
+
+    // Replace the existing tag (or add one at the top), keeping everything else.
+    let mut lines: Vec<String> = Vec::new();
+    if content.lines().all(|l| offset_tag(l).is_none()) && offset != 0 {
+        lines.push(format!("[offset:{offset:+}]"));
+    }
+    for line in content.lines() {
+        match offset_tag(line) {
+            Some(_) if offset != 0 => lines.push(format!("[offset:{offset:+}]")),
+            Some(_) => {}
+            None => lines.push(line.to_string()),
         }
     }
-    None
+    let mut out = lines.join("\n");
+    out.push('\n');
+    fs::write(&path, out).with_context(|| format!("write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Formats a timestamp as LRC `mm:ss.xx`.
+pub(crate) fn format_lrc_time(t: Duration) -> String {
+    let cs = t.as_millis() / 10;
+    format!("{:02}:{:02}.{:02}", cs / 6000, cs / 100 % 60, cs % 100)
 }
 
 /// Parses LRC-formatted text into a list of timed lyric entries.
@@ -48,14 +246,21 @@ pub(crate) fn load_lrc(audio_path: &Path) -> Option<Vec<LrcEntry>> {
 /// - Multiple timestamps per line: `[00:01.00][00:05.00]Lyrics text`
 /// - Fractional seconds with 1-3 digits: `[mm:ss.f]`, `[mm:ss.ff]`, `[mm:ss.fff]`
 /// - Skips metadata-only lines such as `[ar:Artist]`, `[ti:Title]`.
+/// - Enhanced LRC word timestamps: `[00:01.00]<00:01.00>Some <00:01.50>words`
+/// - Applies `[offset:+/-ms]`; a positive offset shows lyrics sooner.
 pub(crate) fn parse_lrc(content: &str) -> Vec<LrcEntry> {
     let mut entries: Vec<LrcEntry> = Vec::new();
+    let mut offset: i64 = 0;
 
-    for raw_line in content.lines() {
+    for raw_line in content.trim_start_matches('\u{feff}').lines() {
         let line = raw_line.trim_end();
         if line.is_empty() {
             continue;
         }
+        if let Some(ms) = offset_tag(line) {
+            offset = ms;
+            continue;
+        }
 
         let mut rest = line;
         let mut times: Vec<Duration> = Vec::new();
@@ -78,19 +283,107 @@ pub(crate) fn parse_lrc(content: &str) -> Vec<LrcEntry> {
             continue;
         }
 
-        let text = rest.trim().to_string();
+        let words = parse_words(rest, times[0]);
+        let text = if words.is_empty() {
+            rest.trim().to_string()
+        } else {
+            words.iter().map(|w| w.text.as_str()).collect()
+        };
         for t in times {
             entries.push(LrcEntry {
                 time: t,
                 text: text.clone(),
+                words: words.clone(),
+                end: None,
             });
         }
     }
 
+    if offset != 0 {
+        for e in &mut entries {
+            e.time = shift(e.time, -offset);
+            for w in &mut e.words {
+                w.time = shift(w.time, -offset);
+            }
+        }
+    }
     entries.sort_by_key(|e| e.time);
     entries
 }
 
+/// Splits text with inline `<mm:ss.xx>` timestamps into timed words; text before
+/// the first one starts at `line_time`. Returns nothing if there are none.
+fn parse_words(text: &str, line_time: Duration) -> Vec<LrcWord> {
+    let mut words: Vec<LrcWord> = Vec::new();
+    let mut current = LrcWord {
+        time: line_time,
+        text: String::new(),
+    };
+    let mut timed = false;
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let after = &rest[open + 1..];
+        let stamp = after
+            .find('>')
+            .and_then(|close| Some((close, parse_lrc_time(&after[..close])?)));
+        let Some((close, time)) = stamp else {
+            // A literal `<`.
+            current.text.push_str(&rest[..=open]);
+            rest = after;
+            continue;
+        };
+        current.text.push_str(&rest[..open]);
+        let done = std::mem::replace(
+            &mut current,
+            LrcWord {
+                time,
+                text: String::new(),
+            },
+        );
+        if timed || !done.text.trim().is_empty() {
+            words.push(done);
+        }
+        timed = true;
+        rest = &after[close + 1..];
+    }
+    if !timed {
+        return Vec::new();
+    }
+    current.text.push_str(rest);
+    words.push(current);
+
+    // Trim the line, keeping the spaces between words.
+    if let Some(w) = words.first_mut() {
+        w.text = w.text.trim_start().to_string();
+    }
+    for w in words.iter_mut().rev() {
+        w.text = w.text.trim_end().to_string();
+        if !w.text.is_empty() {
+            break;
+        }
+    }
+    words
+}
+
+/// The value of an `[offset:+/-ms]` line.
+fn offset_tag(line: &str) -> Option<i64> {
+    let tag = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = tag.split_once(':')?;
+    if !key.trim().eq_ignore_ascii_case("offset") {
+        return None;
+    }
+    value.trim().parse().ok()
+}
+
+/// `t + ms`, clamped at zero.
+pub(crate) fn shift(t: Duration, ms: i64) -> Duration {
+    if ms < 0 {
+        t.saturating_sub(Duration::from_millis(ms.unsigned_abs()))
+    } else {
+        t + Duration::from_millis(ms as u64)
+    }
+}
+
 fn parse_lrc_time(s: &str) -> Option<Duration> {
     // Accepts: mm:ss, mm:ss.f, mm:ss.ff, mm:ss.fff, m:ss
     let parts: Vec<&str> = s.splitn(2, ':').collect();
@@ -157,4 +450,187 @@ pub(crate) fn active_index(entries: &[LrcEntry], pos: Duration) -> Option<usize>
     } else {
         Some(lo - 1)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn times_and_texts(entries: &[LrcEntry]) -> Vec<(u64, &str)> {
+        entries
+            .iter()
+            .map(|e| (e.time.as_millis() as u64, e.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_timestamps_and_skips_metadata() {
+        let entries = parse_lrc("[ar:Someone]\n[ti:Song]\n[00:05.00]Second\n[00:01.00]First\n");
+        assert_eq!(
+            times_and_texts(&entries),
+            [(1000, "First"), (5000, "Second")]
+        );
+    }
+
+    #[test]
+    fn repeats_lines_with_several_timestamps() {
+        let entries = parse_lrc("[00:01.00][01:02.50]Chorus");
+        assert_eq!(
+            times_and_texts(&entries),
+            [(1000, "Chorus"), (62_500, "Chorus")]
+        );
+    }
+
+    #[test]
+    fn reads_one_to_three_digit_fractions() {
+        assert_eq!(parse_lrc_time("00:01.5"), Some(ms(1500)));
+        assert_eq!(parse_lrc_time("00:01.05"), Some(ms(1050)));
+        assert_eq!(parse_lrc_time("00:01.005"), Some(ms(1005)));
+        assert_eq!(parse_lrc_time("2:03"), Some(ms(123_000)));
+        assert_eq!(parse_lrc_time("00:01."), None);
+        assert_eq!(parse_lrc_time("ar:Someone"), None);
+    }
+
+    #[test]
+    fn handles_crlf_and_a_byte_order_mark() {
+        let entries = parse_lrc("\u{feff}[00:01.00]One\r\n[00:02.00]Two\r\n");
+        assert_eq!(times_and_texts(&entries), [(1000, "One"), (2000, "Two")]);
+    }
+
+    #[test]
+    fn applies_the_offset_tag() {
+        // A positive offset shows lyrics sooner, wherever the tag is.
+        let entries = parse_lrc("[00:01.00]One\n[offset:+500]\n[00:00.20]Zero");
+        assert_eq!(times_and_texts(&entries), [(0, "Zero"), (500, "One")]);
+        let entries = parse_lrc("[offset: -250]\n[00:01.00]One");
+        assert_eq!(times_and_texts(&entries), [(1250, "One")]);
+    }
+
+    #[test]
+    fn reads_the_offset_tag_value() {
+        assert_eq!(offset_tag("[offset:+120]"), Some(120));
+        assert_eq!(offset_tag(" [OFFSET: -80] "), Some(-80));
+        assert_eq!(offset_tag("[offset:soon]"), None);
+        assert_eq!(offset_tag("[00:01.00]offset"), None);
+    }
+
+    #[test]
+    fn shift_clamps_at_zero() {
+        assert_eq!(shift(ms(300), -500), Duration::ZERO);
+        assert_eq!(shift(ms(300), 200), ms(500));
+    }
+
+    #[test]
+    fn saves_the_offset_into_the_sidecar() {
+        let dir = TempDir::new("lrc");
+        let audio = dir.join("song.flac");
+        let lrc = dir.join("song.lrc");
+        fs::write(&lrc, "[ti:Song]\n[offset:+100]\n[00:01.00]One\n").unwrap();
+
+        let entries = load_lrc(&audio).unwrap();
+        save_offset(&audio, &entries, 150).unwrap();
+        let content = fs::read_to_string(&lrc).unwrap();
+        save_offset(&audio, &entries, -250).unwrap();
+        let cleared = fs::read_to_string(&lrc).unwrap();
+
+        assert_eq!(content, "[ti:Song]\n[offset:+250]\n[00:01.00]One\n");
+        assert_eq!(cleared, "[ti:Song]\n[00:01.00]One\n");
+    }
+
+    #[test]
+    fn splits_enhanced_lines_into_words() {
+        let entries = parse_lrc("[00:01.00]<00:01.00>Some <00:01.50>words <00:02.5>here");
+        let words: Vec<(u64, &str)> = entries[0]
+            .words
+            .iter()
+            .map(|w| (w.time.as_millis() as u64, w.text.as_str()))
+            .collect();
+        assert_eq!(words, [(1000, "Some "), (1500, "words "), (2500, "here")]);
+        assert_eq!(entries[0].text, "Some words here");
+    }
+
+    #[test]
+    fn starts_untimed_leading_text_with_the_line() {
+        let words = parse_words("  Hey <00:03.00>you ", ms(2000));
+        let words: Vec<(u64, &str)> = words
+            .iter()
+            .map(|w| (w.time.as_millis() as u64, w.text.as_str()))
+            .collect();
+        assert_eq!(words, [(2000, "Hey "), (3000, "you")]);
+    }
+
+    #[test]
+    fn keeps_literal_angle_brackets() {
+        assert!(parse_words("a <3 b", ms(0)).is_empty());
+        let words = parse_words("<00:01.00>x < y", ms(0));
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].text, "x < y");
+    }
+
+    #[test]
+    fn offsets_word_times_too() {
+        let entries = parse_lrc("[offset:+500]\n[00:02.00]<00:02.00>a <00:03.00>b");
+        assert_eq!(entries[0].words[0].time, ms(1500));
+        assert_eq!(entries[0].words[1].time, ms(2500));
+    }
+
+    #[test]
+    fn interpolates_sung_characters() {
+        let entries = parse_lrc("[00:01.00]<00:01.00>abcd<00:02.00>ef");
+        let line = &entries[0];
+        assert_eq!(line.sung_chars(ms(500), None), 0);
+        assert_eq!(line.sung_chars(ms(1500), None), 2);
+        assert_eq!(line.sung_chars(ms(2000), Some(ms(3000))), 4);
+        assert_eq!(line.sung_chars(ms(2500), Some(ms(3000))), 5);
+        // The last word without a line end is sung at once.
+        assert_eq!(line.sung_chars(ms(2500), None), 6);
+    }
+
+    #[test]
+    fn writes_word_timestamps_back() {
+        let entries = parse_lrc("[00:01.00]<00:01.00>Some <00:01.50>words");
+        assert_eq!(
+            entries[0].lrc_line(),
+            "[00:01.00]<00:01.00>Some <00:01.50>words"
+        );
+    }
+
+    #[test]
+    fn finds_the_active_line() {
+        let entries = parse_lrc("[00:01.00]One\n[00:02.00]Two");
+        assert_eq!(active_index(&entries, ms(500)), None);
+        assert_eq!(active_index(&entries, ms(1000)), Some(0));
+        assert_eq!(active_index(&entries, ms(5000)), Some(1));
+    }
+
+    #[test]
+    fn slices_file_lyrics_for_a_cue_track() {
+        let lyrics = Lyrics::Synced(parse_lrc(
+            "[00:01.00]Intro\n[01:00.00]<01:00.00>Second <01:00.50>song\n[02:00.00]Third",
+        ));
+        let Some(Lyrics::Synced(entries)) = lyrics.slice(ms(60_000), Some(ms(120_000))) else {
+            panic!("expected synced lyrics");
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].time, ms(0));
+        assert_eq!(entries[0].words[1].time, ms(500));
+
+        let last = Lyrics::Synced(parse_lrc("[00:01.00]Intro\n[02:00.00]Third"));
+        let Some(Lyrics::Synced(entries)) = last.slice(ms(60_000), None) else {
+            panic!("expected synced lyrics");
+        };
+        assert_eq!(
+            (entries[0].time, entries[0].text.as_str()),
+            (ms(60_000), "Third")
+        );
+
+        assert!(Lyrics::Unsynced("words".to_string())
+            .slice(ms(0), None)
+            .is_none());
+    }
+}
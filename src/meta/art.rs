@@ -0,0 +1,59 @@
+//! Cover art: a picture embedded in the file, or an image next to it.
+
+use std::{fs, path::Path};
+
+use symphonia::core::meta::{MetadataRevision, StandardVisualKey, Visual};
+
+use super::probe;
+
+/// Names (without extension) of cover images in an album folder, best first.
+const FOLDER_NAMES: [&str; 5] = ["cover", "folder", "front", "album", "albumart"];
+const FOLDER_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// Returns the encoded cover image for the track at `path`: an embedded picture
+/// (ID3v2 APIC, FLAC PICTURE, MP4 `covr`), else e.g. `cover.jpg` in its folder.
+pub(crate) fn cover_art(path: &Path) -> Option<Vec<u8>> {
+    embedded_cover(path).or_else(|| folder_cover(path))
+}
+
+fn embedded_cover(path: &Path) -> Option<Vec<u8>> {
+    let mut probed = probe(path).ok()?;
+
+    let mut visuals: Vec<Visual> = Vec::new();
+    let mut take = |rev: Option<&MetadataRevision>| {
+        visuals.extend(rev.into_iter().flat_map(|r| r.visuals().iter().cloned()));
+    };
+    if let Some(container_meta) = probed.metadata.get() {
+        take(container_meta.current());
+    }
+    take(probed.format.metadata().current());
+
+    // Prefer the front cover; otherwise any picture will do.
+    let pos = visuals
+        .iter()
+        .position(|v| v.usage == Some(StandardVisualKey::FrontCover))
+        .unwrap_or(0);
+    (pos < visuals.len()).then(|| visuals.swap_remove(pos).data.into_vec())
+}
+
+fn folder_cover(path: &Path) -> Option<Vec<u8>> {
+    let dir = path.parent()?;
+    let mut best: Option<(usize, std::path::PathBuf)> = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let file = entry.path();
+        let (Some(stem), Some(ext)) = (file.file_stem(), file.extension()) else {
+            continue;
+        };
+        let stem = stem.to_string_lossy().to_ascii_lowercase();
+        let ext = ext.to_string_lossy().to_ascii_lowercase();
+        if !FOLDER_EXTENSIONS.contains(&ext.as_str()) {
+            continue;
+        }
+        if let Some(rank) = FOLDER_NAMES.iter().position(|n| *n == stem) {
+            if best.as_ref().is_none_or(|(r, _)| rank < *r) {
+                best = Some((rank, file));
+            }
+        }
+    }
+    fs::read(best?.1).ok()
+}
@@ -0,0 +1,81 @@
+//! Lyrics stored in a track's tags.
+
+use std::{fs::File, io::Read, path::Path, time::Duration};
+
+use symphonia::core::meta::{MetadataRevision, StandardTagKey};
+
+use super::probe;
+use crate::lrc::{LrcEntry, Lyrics};
+
+/// Samples per MPEG audio frame (layer II/III), for SYLT frame timestamps.
+const MPEG_FRAME_SAMPLES: f64 = 1152.0;
+
+/// Returns lyrics embedded in the file at `path`: ID3v2 SYLT (synced) or USLT
+/// frames, or a `LYRICS`/`UNSYNCEDLYRICS` comment (Vorbis, FLAC) or `©lyr` atom (MP4).
+pub(crate) fn embedded_lyrics(path: &Path) -> Option<Lyrics> {
+    if has_id3(path) {
+        if let Some(lyrics) = id3_lyrics(path) {
+            return Some(lyrics);
+        }
+    }
+    tag_lyrics(path)
+}
+
+fn has_id3(path: &Path) -> bool {
+    let mut head = [0u8; 3];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut head))
+        .is_ok_and(|()| &head == b"ID3")
+}
+
+fn id3_lyrics(path: &Path) -> Option<Lyrics> {
+    use id3::frame::TimestampFormat;
+
+    let tag = id3::Tag::read_from_path(path)
+        .or_else(|e| id3::partial_tag_ok(Err(e)))
+        .ok()?;
+
+    if let Some(sylt) = tag.synchronised_lyrics().find(|s| !s.content.is_empty()) {
+        // Timestamps count milliseconds or MPEG frames.
+        let unit = match sylt.timestamp_format {
+            TimestampFormat::Ms => Some(Duration::from_millis(1)),
+            TimestampFormat::Mpeg => super::probe_track_meta(path)
+                .ok()
+                .and_then(|m| m.sample_rate)
+                .filter(|&rate| rate > 0)
+                .map(|rate| Duration::from_secs_f64(MPEG_FRAME_SAMPLES / f64::from(rate))),
+        };
+        if let Some(unit) = unit {
+            let mut entries: Vec<LrcEntry> = sylt
+                .content
+                .iter()
+                .map(|(t, text)| LrcEntry {
+                    time: unit * *t,
+                    text: text.trim().to_string(),
+                    words: Vec::new(),
+                    end: None,
+                })
+                .collect();
+            entries.sort_by_key(|e| e.time);
+            return Some(Lyrics::Synced(entries));
+        }
+    }
+
+    let lyrics = tag.lyrics().find_map(|l| Lyrics::from_text(&l.text));
+    lyrics
+}
+
+fn tag_lyrics(path: &Path) -> Option<Lyrics> {
+    let mut probed = probe(path).ok()?;
+
+    let find = |rev: Option<&MetadataRevision>| {
+        rev.into_iter()
+            .flat_map(|r| r.tags())
+            .filter(|t| t.std_key == Some(StandardTagKey::Lyrics))
+            .find_map(|t| Lyrics::from_text(&t.value.to_string()))
+    };
+    if let Some(lyrics) = probed.metadata.get().and_then(|m| find(m.current())) {
+        return Some(lyrics);
+    }
+    find(probed.format.metadata().current())
+}
@@ -5,20 +5,71 @@ use std::{
 };
 
 use anyhow::{Context, Result};
+
+use crate::cue::CueTrack;
 use symphonia::core::{
+    codecs::CODEC_TYPE_NULL,
     formats::FormatOptions,
     io::MediaSourceStream,
     meta::{MetadataOptions, StandardTagKey},
-    probe::Hint,
+    probe::{Hint, ProbeResult},
     units::Time,
 };
 
+mod art;
+mod lyrics;
+mod write;
+
+pub(crate) use art::cover_art;
+pub(crate) use lyrics::embedded_lyrics;
+pub(crate) use write::{write_tags, TagEdit};
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct TrackMeta {
     pub(crate) title: Option<String>,
     pub(crate) artist: Option<String>,
     pub(crate) album: Option<String>,
     pub(crate) duration: Option<Duration>,
+    pub(crate) track_number: Option<u32>,
+    pub(crate) track_total: Option<u32>,
+    pub(crate) disc_number: Option<u32>,
+    pub(crate) disc_total: Option<u32>,
+    /// Release date as tagged: a year, or a full `YYYY-MM-DD` date.
+    pub(crate) date: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) album_artist: Option<String>,
+    pub(crate) composer: Option<String>,
+    pub(crate) comment: Option<String>,
+    /// Rating normalised to 0–100, whatever scale the tag format uses.
+    pub(crate) rating: Option<u8>,
+    pub(crate) musicbrainz: MusicBrainzIds,
+
+    // Technical details of the first decodable audio stream.
+    pub(crate) codec: Option<String>,
+    pub(crate) sample_rate: Option<u32>,
+    pub(crate) bit_depth: Option<u32>,
+    pub(crate) channels: Option<usize>,
+    /// Average bitrate in bits per second, from the file size and duration.
+    pub(crate) bitrate: Option<u64>,
+    pub(crate) file_size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MusicBrainzIds {
+    pub(crate) recording: Option<String>,
+    pub(crate) release: Option<String>,
+    pub(crate) release_group: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album_artist: Option<String>,
+}
+
+impl TrackMeta {
+    /// The four-digit year from `date`.
+    pub(crate) fn year(&self) -> Option<u32> {
+        let date = self.date.as_deref()?.trim();
+        let digits: String = date.chars().take(4).collect();
+        (digits.len() == 4).then(|| digits.parse().ok()).flatten()
+    }
 }
 
 pub(crate) fn probe_duration(path: &Path) -> Result<Duration> {
@@ -26,7 +77,7 @@ pub(crate) fn probe_duration(path: &Path) -> Result<Duration> {
     meta.duration.context("Duration unavailable")
 }
 
-pub(crate) fn probe_track_meta(path: &Path) -> Result<TrackMeta> {
+fn probe(path: &Path) -> Result<ProbeResult> {
     let file = File::open(path)?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
@@ -35,12 +86,16 @@ pub(crate) fn probe_track_meta(path: &Path) -> Result<TrackMeta> {
         hint.with_extension(ext);
     }
 
-    let mut probed = symphonia::default::get_probe().format(
+    Ok(symphonia::default::get_probe().format(
         &hint,
         mss,
         &FormatOptions::default(),
         &MetadataOptions::default(),
-    )?;
+    )?)
+}
+
+pub(crate) fn probe_track_meta(path: &Path) -> Result<TrackMeta> {
+    let mut probed = probe(path)?;
 
     let mut meta = TrackMeta::default();
 
@@ -54,15 +109,24 @@ pub(crate) fn probe_track_meta(path: &Path) -> Result<TrackMeta> {
         apply_tags(&mut meta, rev);
     }
 
-    // Duration (best-effort): use time_base*n_frames if present; else sample_rate*n_frames.
-    if meta.duration.is_none() {
-        if let Some(track) = probed
-            .format
-            .default_track()
-            .or_else(|| probed.format.tracks().first())
-        {
-            let params = &track.codec_params;
+    let track = probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .or_else(|| probed.format.default_track());
 
+    if let Some(track) = track {
+        let params = &track.codec_params;
+        meta.codec = symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|d| d.short_name.to_string());
+        meta.sample_rate = params.sample_rate;
+        meta.bit_depth = params.bits_per_sample.or(params.bits_per_coded_sample);
+        meta.channels = params.channels.map(|c| c.count());
+
+        // Duration (best-effort): use time_base*n_frames if present; else sample_rate*n_frames.
+        if meta.duration.is_none() {
             if let (Some(time_base), Some(n_frames)) = (params.time_base, params.n_frames) {
                 let Time { seconds, frac, .. } = time_base.calc_time(n_frames);
                 meta.duration = Some(Duration::from_secs(seconds) + Duration::from_secs_f64(frac));
@@ -76,9 +140,36 @@ pub(crate) fn probe_track_meta(path: &Path) -> Result<TrackMeta> {
         }
     }
 
+    meta.file_size = std::fs::metadata(path).ok().map(|m| m.len());
+    if let (Some(size), Some(duration)) = (meta.file_size, meta.duration) {
+        let secs = duration.as_secs_f64();
+        if secs > 0.0 {
+            meta.bitrate = Some((size as f64 * 8.0 / secs) as u64);
+        }
+    }
+
     Ok(meta)
 }
 
+/// Overlays CUE sheet metadata onto the tags of the shared audio file.
+///
+/// The file's own title usually names the whole album, so the CUE title wins even
+/// when it is missing (the list entry name is used as a fallback by the UI).
+pub(crate) fn apply_cue(meta: &mut TrackMeta, cue: &CueTrack) {
+    meta.title = cue.title.clone();
+    meta.track_number = Some(cue.number);
+    meta.track_total = Some(cue.total);
+    if cue.performer.is_some() {
+        meta.artist = cue.performer.clone();
+    }
+    if cue.album.is_some() {
+        meta.album = cue.album.clone();
+    }
+    meta.duration = cue
+        .length()
+        .or_else(|| meta.duration.map(|d| d.saturating_sub(cue.start)));
+}
+
 fn apply_tags(meta: &mut TrackMeta, rev: &symphonia::core::meta::MetadataRevision) {
     for tag in rev.tags() {
         let value = tag.value.to_string();
@@ -92,6 +183,62 @@ fn apply_tags(meta: &mut TrackMeta, rev: &symphonia::core::meta::MetadataRevisio
             Some(StandardTagKey::Album) => {
                 meta.album.get_or_insert(value);
             }
+            Some(StandardTagKey::TrackNumber) => {
+                let (n, total) = parse_number_pair(&value);
+                meta.track_number = meta.track_number.or(n);
+                meta.track_total = meta.track_total.or(total);
+            }
+            Some(StandardTagKey::TrackTotal) => {
+                meta.track_total = meta.track_total.or(parse_number_pair(&value).0);
+            }
+            Some(StandardTagKey::DiscNumber) => {
+                let (n, total) = parse_number_pair(&value);
+                meta.disc_number = meta.disc_number.or(n);
+                meta.disc_total = meta.disc_total.or(total);
+            }
+            Some(StandardTagKey::DiscTotal) => {
+                meta.disc_total = meta.disc_total.or(parse_number_pair(&value).0);
+            }
+            Some(
+                StandardTagKey::Date | StandardTagKey::ReleaseDate | StandardTagKey::OriginalDate,
+            ) => {
+                meta.date.get_or_insert(value);
+            }
+            Some(StandardTagKey::Genre) => {
+                meta.genre.get_or_insert(value);
+            }
+            Some(StandardTagKey::AlbumArtist) => {
+                meta.album_artist.get_or_insert(value);
+            }
+            Some(StandardTagKey::Composer) => {
+                meta.composer.get_or_insert(value);
+            }
+            Some(StandardTagKey::Comment) => {
+                meta.comment.get_or_insert(value);
+            }
+            Some(StandardTagKey::MusicBrainzRecordingId | StandardTagKey::MusicBrainzTrackId) => {
+                meta.musicbrainz.recording.get_or_insert(value);
+            }
+            Some(StandardTagKey::MusicBrainzAlbumId) => {
+                meta.musicbrainz.release.get_or_insert(value);
+            }
+            Some(StandardTagKey::MusicBrainzReleaseGroupId) => {
+                meta.musicbrainz.release_group.get_or_insert(value);
+            }
+            Some(StandardTagKey::MusicBrainzArtistId) => {
+                meta.musicbrainz.artist.get_or_insert(value);
+            }
+            Some(StandardTagKey::MusicBrainzAlbumArtistId) => {
+                meta.musicbrainz.album_artist.get_or_insert(value);
+            }
+            Some(StandardTagKey::Rating) if tag.key.starts_with("POPM") => {
+                // Popularimeter: a single byte, 0 = unrated.
+                let popm = value.parse::<u32>().ok().filter(|&v| v > 0);
+                meta.rating = meta.rating.or(popm.map(|v| (v.min(255) * 100 / 255) as u8));
+            }
+            Some(StandardTagKey::Rating) => {
+                meta.rating = meta.rating.or_else(|| parse_rating(&value));
+            }
             _ => {
                 // Fallbacks for common raw keys.
                 match tag.key.to_ascii_lowercase().as_str() {
@@ -104,6 +251,15 @@ fn apply_tags(meta: &mut TrackMeta, rev: &symphonia::core::meta::MetadataRevisio
                     "album" => {
                         meta.album.get_or_insert(value);
                     }
+                    "year" => {
+                        meta.date.get_or_insert(value);
+                    }
+                    "albumartist" | "album artist" => {
+                        meta.album_artist.get_or_insert(value);
+                    }
+                    "rating" | "fmps_rating" => {
+                        meta.rating = meta.rating.or_else(|| parse_rating(&value));
+                    }
                     _ => {
                         // ignore
                     }
@@ -112,3 +268,29 @@ fn apply_tags(meta: &mut TrackMeta, rev: &symphonia::core::meta::MetadataRevisio
         };
     }
 }
+
+/// Parses `3` or `3/12` style track and disc numbers into (number, total).
+fn parse_number_pair(value: &str) -> (Option<u32>, Option<u32>) {
+    let parse = |s: &str| s.trim().parse().ok().filter(|&n: &u32| n > 0);
+    match value.split_once('/') {
+        Some((n, total)) => (parse(n), parse(total)),
+        None => (parse(value), None),
+    }
+}
+
+/// Maps textual ratings onto 0–100: Vorbis comments use 0–100 or 1–5 stars, and
+/// FMPS ratings are fractions in 0.0–1.0.
+fn parse_rating(value: &str) -> Option<u8> {
+    let v: f64 = value.trim().parse().ok()?;
+    if !v.is_finite() || v < 0.0 {
+        return None;
+    }
+    let pct = if v <= 1.0 && value.contains('.') {
+        v * 100.0
+    } else if v <= 5.0 {
+        v * 20.0
+    } else {
+        v.min(100.0)
+    };
+    Some(pct.round() as u8)
+}
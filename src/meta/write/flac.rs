@@ -0,0 +1,207 @@
+//! FLAC: rewrites the VORBIS_COMMENT metadata block, using PADDING to avoid
+//! moving the audio frames where possible.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+
+use super::{replace_file, vorbis::VorbisComments, TagEdit};
+
+const STREAMINFO: u8 = 0;
+const PADDING: u8 = 1;
+const VORBIS_COMMENT: u8 = 4;
+
+/// Padding added when the metadata has to grow, so later edits fit in place.
+const NEW_PADDING: usize = 4096;
+const MAX_BLOCK_LEN: usize = (1 << 24) - 1;
+
+pub(super) fn write(path: &Path, edit: &TagEdit) -> Result<()> {
+    let mut file = File::open(path)?;
+
+    // Some taggers put an ID3v2 tag in front of the FLAC stream; keep it as is.
+    let mut head = [0u8; 10];
+    file.read_exact(&mut head).context("read FLAC header")?;
+    let flac_start = if head.starts_with(b"ID3") {
+        let size = head[6..10]
+            .iter()
+            .fold(0u64, |acc, &b| (acc << 7) | u64::from(b & 0x7f));
+        let footer = if head[5] & 0x10 != 0 { 10 } else { 0 };
+        10 + size + footer
+    } else {
+        0
+    };
+
+    file.seek(SeekFrom::Start(flac_start))?;
+    let mut marker = [0u8; 4];
+    file.read_exact(&mut marker)?;
+    if &marker != b"fLaC" {
+        bail!("not a FLAC file");
+    }
+
+    let mut blocks: Vec<(u8, Vec<u8>)> = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        file.read_exact(&mut header).context("read FLAC metadata")?;
+        let last = header[0] & 0x80 != 0;
+        let kind = header[0] & 0x7f;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut body = vec![0u8; len];
+        file.read_exact(&mut body).context("read FLAC metadata")?;
+        blocks.push((kind, body));
+        if last {
+            break;
+        }
+    }
+    let audio_start = file.stream_position()?;
+    let old_len = (audio_start - flac_start) as usize;
+
+    let mut comments = match blocks.iter().find(|(k, _)| *k == VORBIS_COMMENT) {
+        Some((_, body)) => VorbisComments::parse(body)?.0,
+        None => VorbisComments::new(),
+    };
+    comments.apply(edit)?;
+    let comment_block = (VORBIS_COMMENT, comments.to_bytes());
+
+    // Replace the comment block where it was (or right after STREAMINFO), and drop
+    // padding: it is re-added below to fill whatever space is left.
+    let mut new_blocks: Vec<(u8, Vec<u8>)> = Vec::new();
+    let mut comment_block = Some(comment_block);
+    for (kind, body) in blocks {
+        match kind {
+            PADDING => {}
+            VORBIS_COMMENT => new_blocks.extend(comment_block.take()),
+            _ => {
+                let is_streaminfo = kind == STREAMINFO;
+                new_blocks.push((kind, body));
+                if is_streaminfo {
+                    if let Some(c) = comment_block.take() {
+                        new_blocks.push(c);
+                    }
+                }
+            }
+        }
+    }
+    new_blocks.extend(comment_block);
+    if new_blocks.iter().any(|(_, b)| b.len() > MAX_BLOCK_LEN) {
+        bail!("metadata block too large");
+    }
+
+    let used: usize = 4 + new_blocks.iter().map(|(_, b)| 4 + b.len()).sum::<usize>();
+    let in_place = used == old_len || (used + 4 <= old_len && old_len - used - 4 <= MAX_BLOCK_LEN);
+
+    if in_place {
+        if used < old_len {
+            new_blocks.push((PADDING, vec![0; old_len - used - 4]));
+        }
+        let mut out = OpenOptions::new().write(true).open(path)?;
+        out.seek(SeekFrom::Start(flac_start))?;
+        out.write_all(&encode(&new_blocks))?;
+        out.sync_all()?;
+        return Ok(());
+    }
+
+    new_blocks.push((PADDING, vec![0; NEW_PADDING]));
+    drop(file);
+    replace_file(path, |out| {
+        let mut src = File::open(path)?;
+        io::copy(&mut (&mut src).take(flac_start), out)?;
+        out.write_all(&encode(&new_blocks))?;
+        src.seek(SeekFrom::Start(audio_start))?;
+        io::copy(&mut src, out)?;
+        Ok(())
+    })
+}
+
+fn encode(blocks: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut out = b"fLaC".to_vec();
+    for (i, (kind, body)) in blocks.iter().enumerate() {
+        let last = if i + 1 == blocks.len() { 0x80 } else { 0 };
+        let len = (body.len() as u32).to_be_bytes();
+        out.extend_from_slice(&[last | kind, len[1], len[2], len[3]]);
+        out.extend_from_slice(body);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::write::test_support::{assert_sample_tags, round_trip, sample_edit};
+
+    /// One 16-sample frame of stereo silence, which has to come through untouched.
+    fn frame() -> Vec<u8> {
+        // Fixed block size, 44.1 kHz, left/right, 16 bit, frame 0, 16 samples.
+        let mut frame = vec![0xff, 0xf8, 0x69, 0x18, 0x00, 0x0f];
+        let crc8 = frame.iter().fold(0u8, |mut crc, &b| {
+            crc ^= b;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    (crc << 1) ^ 0x07
+                } else {
+                    crc << 1
+                };
+            }
+            crc
+        });
+        frame.push(crc8);
+        // Two CONSTANT subframes of 0, then a CRC-16 left at 0.
+        frame.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        frame
+    }
+
+    /// A FLAC stream with 44.1 kHz stereo 16-bit STREAMINFO, the given extra
+    /// blocks and one frame.
+    fn flac(extra: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut info = vec![0x00, 0x10, 0x00, 0x10, 0, 0, 0, 0, 0, 0];
+        let packed: u64 = (44_100 << 44) | (1 << 41) | (15 << 36) | 16;
+        info.extend_from_slice(&packed.to_be_bytes());
+        info.extend_from_slice(&[0; 16]);
+        let mut blocks = vec![(STREAMINFO, info)];
+        blocks.extend_from_slice(extra);
+        let mut out = encode(&blocks);
+        out.extend_from_slice(&frame());
+        out
+    }
+
+    #[test]
+    fn writes_into_padding_in_place() {
+        let original = flac(&[(PADDING, vec![0; 1024])]);
+        let (meta, bytes) = round_trip("in-place.flac", &original, &sample_edit());
+        assert_sample_tags(&meta);
+        assert_eq!(bytes.len(), original.len());
+        assert!(bytes.ends_with(&frame()));
+    }
+
+    #[test]
+    fn grows_metadata_when_there_is_no_room() {
+        let (meta, bytes) = round_trip("grow.flac", &flac(&[]), &sample_edit());
+        assert_sample_tags(&meta);
+        assert!(bytes.ends_with(&frame()));
+    }
+
+    #[test]
+    fn keeps_other_comments() {
+        let mut comments = VorbisComments::new();
+        comments
+            .apply(&TagEdit {
+                album: Some("Old Album".into()),
+                genre: Some("Ambient".into()),
+                ..TagEdit::default()
+            })
+            .unwrap();
+        let original = flac(&[(VORBIS_COMMENT, comments.to_bytes())]);
+        let edit = TagEdit {
+            title: Some("New Title".into()),
+            genre: Some(String::new()),
+            ..TagEdit::default()
+        };
+        let (meta, _) = round_trip("keep.flac", &original, &edit);
+        assert_eq!(meta.title.as_deref(), Some("New Title"));
+        assert_eq!(meta.album.as_deref(), Some("Old Album"));
+        assert_eq!(meta.genre, None);
+    }
+}
@@ -0,0 +1,282 @@
+//! Writing tags back to audio files.
+//!
+//! ID3v2 (MP3) goes through the `id3` crate. FLAC metadata blocks, Ogg
+//! Vorbis comment packets and MP4 `ilst` atoms are rewritten here directly; other
+//! parts of those files are copied unchanged.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+
+mod flac;
+mod mp4;
+mod ogg;
+mod vorbis;
+
+/// Tag changes to apply. `None` leaves a field as it is; an empty string removes it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TagEdit {
+    pub(crate) title: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) track: Option<String>,
+    pub(crate) year: Option<String>,
+    pub(crate) genre: Option<String>,
+}
+
+impl TagEdit {
+    pub(crate) fn is_empty(&self) -> bool {
+        [
+            &self.title,
+            &self.artist,
+            &self.album,
+            &self.track,
+            &self.year,
+            &self.genre,
+        ]
+        .iter()
+        .all(|f| f.is_none())
+    }
+
+    /// The track number as `(number, total)`, accepting `3` or `3/12`.
+    fn track_pair(&self) -> Result<Option<(Option<u32>, Option<u32>)>> {
+        let Some(track) = self.track.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        if track.is_empty() {
+            return Ok(Some((None, None)));
+        }
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<u32>()
+                .with_context(|| format!("invalid track number {track:?}"))
+        };
+        Ok(Some(match track.split_once('/') {
+            Some((n, total)) => (Some(parse(n)?), Some(parse(total)?)),
+            None => (Some(parse(track)?), None),
+        }))
+    }
+
+    fn year_number(&self) -> Result<Option<Option<i32>>> {
+        match self.year.as_deref().map(str::trim) {
+            None => Ok(None),
+            Some("") => Ok(Some(None)),
+            Some(y) => y
+                .parse()
+                .map(|y| Some(Some(y)))
+                .with_context(|| format!("invalid year {y:?}")),
+        }
+    }
+}
+
+/// Writes `edit` into the tags of the file at `path`.
+pub(crate) fn write_tags(path: &Path, edit: &TagEdit) -> Result<()> {
+    // Validate before touching the file.
+    edit.track_pair()?;
+    edit.year_number()?;
+
+    let mut head = [0u8; 12];
+    let n = File::open(path)
+        .and_then(|mut f| f.read(&mut head))
+        .with_context(|| format!("open {}", path.display()))?;
+    let head = &head[..n];
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if head.starts_with(b"fLaC") || (ext == "flac" && head.starts_with(b"ID3")) {
+        flac::write(path, edit)
+    } else if head.starts_with(b"OggS") {
+        ogg::write(path, edit)
+    } else if head.get(4..8) == Some(b"ftyp") {
+        mp4::write(path, edit)
+    } else if head.starts_with(b"ID3") || matches!(ext.as_str(), "mp3" | "mp2" | "mp1") {
+        write_id3(path, edit)
+    } else {
+        bail!("writing tags to this file type is not supported")
+    }
+}
+
+fn write_id3(path: &Path, edit: &TagEdit) -> Result<()> {
+    use id3::{Tag, TagLike, Timestamp, Version};
+
+    let existing = id3::no_tag_ok(Tag::read_from_path(path))
+        .or_else(|e| id3::partial_tag_ok(Err(e)).map(Some))
+        .context("read ID3 tag")?;
+    // Keep the version the file already uses; v2.2 can't be written, so upgrade it.
+    let version = match existing.as_ref().map(|t| t.version()) {
+        Some(Version::Id3v23) => Version::Id3v23,
+        _ => Version::Id3v24,
+    };
+    let mut tag = existing.unwrap_or_default();
+
+    let set = |tag: &mut Tag, value: &Option<String>, id: &str| {
+        match value.as_deref().map(str::trim) {
+            None => {}
+            Some("") => {
+                tag.remove(id);
+            }
+            Some(v) => tag.set_text(id, v),
+        }
+    };
+    set(&mut tag, &edit.title, "TIT2");
+    set(&mut tag, &edit.artist, "TPE1");
+    set(&mut tag, &edit.album, "TALB");
+    set(&mut tag, &edit.genre, "TCON");
+
+    if let Some((number, total)) = edit.track_pair()? {
+        let total = total.or(tag.total_tracks()).filter(|_| number.is_some());
+        tag.remove_track();
+        tag.remove_total_tracks();
+        if let Some(n) = number {
+            tag.set_track(n);
+        }
+        if let Some(t) = total {
+            tag.set_total_tracks(t);
+        }
+    }
+
+    if let Some(year) = edit.year_number()? {
+        tag.remove_year();
+        tag.remove_date_recorded();
+        match (year, version) {
+            (None, _) => {}
+            (Some(y), Version::Id3v23) => tag.set_year(y),
+            (Some(y), _) => tag.set_date_recorded(Timestamp {
+                year: y,
+                month: None,
+                day: None,
+                hour: None,
+                minute: None,
+                second: None,
+            }),
+        }
+    }
+
+    tag.write_to_path(path, version).context("write ID3 tag")
+}
+
+/// Writes a new version of `path` through `write` and swaps it into place, so a
+/// failure halfway never leaves a truncated file behind.
+fn replace_file(path: &Path, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = dir.join(format!(".{name}.trix-tmp"));
+
+    let result = (|| {
+        let file = File::create(&tmp).with_context(|| format!("create {}", tmp.display()))?;
+        let mut out = io::BufWriter::new(file);
+        write(&mut out)?;
+        let file = out.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        if let Ok(md) = fs::metadata(path) {
+            let _ = fs::set_permissions(&tmp, md.permissions());
+        }
+        fs::rename(&tmp, path).with_context(|| format!("replace {}", path.display()))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test_support {
+    use std::fs;
+
+    use super::{write_tags, TagEdit};
+    use crate::{meta::TrackMeta, util::TempDir};
+
+    /// The edit the writer tests apply.
+    pub(super) fn sample_edit() -> TagEdit {
+        TagEdit {
+            title: Some("Round Trip".into()),
+            artist: Some("Tester".into()),
+            album: Some("Fixtures".into()),
+            track: Some("3/12".into()),
+            year: Some("1999".into()),
+            genre: Some("Jazz".into()),
+        }
+    }
+
+    /// Writes `bytes` to a scratch file named `name`, applies `edit` and returns
+    /// the rewritten file, with its tags as read back if `probe` is set.
+    pub(super) fn rewrite(
+        name: &str,
+        bytes: &[u8],
+        edit: &TagEdit,
+        probe: bool,
+    ) -> (Option<TrackMeta>, Vec<u8>) {
+        let dir = TempDir::new(name);
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        write_tags(&path, edit).unwrap();
+        let meta = probe.then(|| crate::meta::probe_track_meta(&path).unwrap());
+        (meta, fs::read(&path).unwrap())
+    }
+
+    /// Like `rewrite`, reading the tags back through the normal probe.
+    pub(super) fn round_trip(name: &str, bytes: &[u8], edit: &TagEdit) -> (TrackMeta, Vec<u8>) {
+        let (meta, contents) = rewrite(name, bytes, edit, true);
+        (meta.expect("probed"), contents)
+    }
+
+    /// Checks that `meta` holds `sample_edit`.
+    pub(super) fn assert_sample_tags(meta: &TrackMeta) {
+        assert_eq!(meta.title.as_deref(), Some("Round Trip"));
+        assert_eq!(meta.artist.as_deref(), Some("Tester"));
+        assert_eq!(meta.album.as_deref(), Some("Fixtures"));
+        assert_eq!(meta.track_number, Some(3));
+        assert_eq!(meta.track_total, Some(12));
+        assert_eq!(meta.year(), Some(1999));
+        assert_eq!(meta.genre.as_deref(), Some("Jazz"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{assert_sample_tags, round_trip, sample_edit};
+    use super::TagEdit;
+
+    /// `frames` MPEG-1 Layer III frames (128 kbit/s, 44.1 kHz, stereo) of silence.
+    fn mp3(frames: usize) -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        frame.repeat(frames)
+    }
+
+    #[test]
+    fn writes_a_new_id3_tag() {
+        let audio = mp3(8);
+        let (meta, contents) = round_trip("new.mp3", &audio, &sample_edit());
+        assert_sample_tags(&meta);
+        assert!(contents.starts_with(b"ID3"));
+        assert!(contents.ends_with(&audio));
+    }
+
+    #[test]
+    fn edits_an_existing_id3_tag() {
+        let (_, tagged) = round_trip("old.mp3", &mp3(8), &sample_edit());
+        let edit = TagEdit {
+            title: Some("Renamed".into()),
+            genre: Some(String::new()),
+            ..TagEdit::default()
+        };
+        let (meta, _) = round_trip("old.mp3", &tagged, &edit);
+        assert_eq!(meta.title.as_deref(), Some("Renamed"));
+        assert_eq!(meta.artist.as_deref(), Some("Tester"));
+        assert_eq!(meta.track_number, Some(3));
+        assert_eq!(meta.genre, None);
+    }
+}
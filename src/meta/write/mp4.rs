@@ -0,0 +1,416 @@
+//! MP4 / M4A: rewrites the iTunes-style `moov/udta/meta/ilst` item list.
+//!
+//! When `moov` changes size and sits in front of the media data, a neighbouring
+//! `free` atom absorbs the difference if possible; otherwise the chunk offset
+//! tables (`stco`/`co64`) are shifted to match.
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+
+use super::{replace_file, TagEdit};
+
+type Kind = [u8; 4];
+
+/// Atoms whose payload is a list of child atoms (on the path to `ilst` and to the
+/// chunk offset tables).
+const CONTAINERS: [&Kind; 8] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"meta", b"ilst",
+];
+
+enum Atom {
+    Leaf {
+        kind: Kind,
+        data: Vec<u8>,
+    },
+    Container {
+        kind: Kind,
+        /// Bytes between the header and the first child (`meta` version/flags).
+        prefix: Vec<u8>,
+        children: Vec<Atom>,
+    },
+}
+
+impl Atom {
+    fn kind(&self) -> &Kind {
+        match self {
+            Atom::Leaf { kind, .. } | Atom::Container { kind, .. } => kind,
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut Vec<Atom>> {
+        match self {
+            Atom::Container { children, .. } => Some(children),
+            Atom::Leaf { .. } => None,
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(self.kind());
+        match self {
+            Atom::Leaf { data, .. } => out.extend_from_slice(data),
+            Atom::Container {
+                prefix, children, ..
+            } => {
+                out.extend_from_slice(prefix);
+                for child in children {
+                    child.encode(out);
+                }
+            }
+        }
+        let size = (out.len() - start) as u32;
+        out[start..start + 4].copy_from_slice(&size.to_be_bytes());
+    }
+}
+
+fn parse_atoms(mut data: &[u8]) -> Result<Vec<Atom>> {
+    let mut atoms = Vec::new();
+    while data.len() >= 8 {
+        let (header_len, size) = match u32::from_be_bytes(data[0..4].try_into()?) {
+            0 => (8, data.len()),
+            1 => {
+                let large = data.get(8..16).context("truncated MP4 atom")?;
+                (16, u64::from_be_bytes(large.try_into()?) as usize)
+            }
+            n => (8, n as usize),
+        };
+        if size < header_len || size > data.len() {
+            bail!("corrupt MP4 atom");
+        }
+        let kind: Kind = data[4..8].try_into()?;
+        let payload = &data[header_len..size];
+
+        atoms.push(if CONTAINERS.contains(&&kind) {
+            // `meta` is a full box (4 bytes version/flags) in MP4, but not in
+            // some QuickTime files; tell them apart by where `hdlr` starts.
+            let prefix_len = if &kind == b"meta" && payload.get(4..8) != Some(b"hdlr") {
+                4
+            } else {
+                0
+            };
+            let prefix_len = prefix_len.min(payload.len());
+            Atom::Container {
+                kind,
+                prefix: payload[..prefix_len].to_vec(),
+                children: parse_atoms(&payload[prefix_len..])?,
+            }
+        } else {
+            Atom::Leaf {
+                kind,
+                data: payload.to_vec(),
+            }
+        });
+        data = &data[size..];
+    }
+    Ok(atoms)
+}
+
+/// Returns the child of `kind`, creating it with `make` if missing.
+fn child<'a>(children: &'a mut Vec<Atom>, kind: &Kind, make: impl FnOnce() -> Atom) -> &'a mut Atom {
+    let idx = match children.iter().position(|a| a.kind() == kind) {
+        Some(i) => i,
+        None => {
+            children.push(make());
+            children.len() - 1
+        }
+    };
+    &mut children[idx]
+}
+
+fn container(kind: &Kind, prefix: Vec<u8>, children: Vec<Atom>) -> Atom {
+    Atom::Container {
+        kind: *kind,
+        prefix,
+        children,
+    }
+}
+
+/// An `ilst` item holding a single `data` atom.
+fn item(kind: &Kind, type_code: u32, payload: &[u8]) -> Atom {
+    let mut data = Vec::new();
+    let mut body = type_code.to_be_bytes().to_vec();
+    body.extend_from_slice(&[0; 4]); // locale
+    body.extend_from_slice(payload);
+    Atom::Leaf {
+        kind: *b"data",
+        data: body,
+    }
+    .encode(&mut data);
+    Atom::Leaf { kind: *kind, data }
+}
+
+const UTF8: u32 = 1;
+const IMPLICIT: u32 = 0;
+const TRKN: Kind = *b"trkn";
+
+fn apply(ilst: &mut Vec<Atom>, edit: &TagEdit) -> Result<()> {
+    let set_text = |ilst: &mut Vec<Atom>, kind: &Kind, value: &Option<String>| {
+        let Some(value) = value.as_deref().map(str::trim) else {
+            return;
+        };
+        let at = ilst.iter().position(|a| a.kind() == kind);
+        ilst.retain(|a| a.kind() != kind);
+        if !value.is_empty() {
+            let new = item(kind, UTF8, value.as_bytes());
+            ilst.insert(at.unwrap_or(ilst.len()).min(ilst.len()), new);
+        }
+    };
+    set_text(ilst, b"\xa9nam", &edit.title);
+    set_text(ilst, b"\xa9ART", &edit.artist);
+    set_text(ilst, b"\xa9alb", &edit.album);
+    set_text(ilst, b"\xa9day", &edit.year);
+    if edit.genre.is_some() {
+        // Numeric ID3v1-style genre; superseded by the text one.
+        ilst.retain(|a| a.kind() != b"gnre");
+        set_text(ilst, b"\xa9gen", &edit.genre);
+    }
+
+    if let Some((number, total)) = edit.track_pair()? {
+        let old_total = ilst.iter().find_map(|a| match a {
+            Atom::Leaf { kind: TRKN, data } => {
+                // `data` header (8) + type/locale (8) + [0, 0, n, n, t, t]
+                data.get(20..22).map(|t| u16::from_be_bytes([t[0], t[1]]))
+            }
+            _ => None,
+        });
+        ilst.retain(|a| a.kind() != &TRKN);
+        if let Some(n) = number {
+            let total = total
+                .map(|t| t as u16)
+                .or(old_total.filter(|&t| t > 0))
+                .unwrap_or(0);
+            let mut payload = vec![0, 0];
+            payload.extend_from_slice(&(n as u16).to_be_bytes());
+            payload.extend_from_slice(&total.to_be_bytes());
+            payload.extend_from_slice(&[0, 0]);
+            ilst.push(item(&TRKN, IMPLICIT, &payload));
+        }
+    }
+    Ok(())
+}
+
+/// Adds `delta` to every chunk offset below `atoms`.
+fn shift_chunk_offsets(atoms: &mut [Atom], delta: i64) -> Result<()> {
+    for atom in atoms {
+        match atom {
+            Atom::Container { children, .. } => shift_chunk_offsets(children, delta)?,
+            Atom::Leaf { kind, data } if kind == b"stco" => {
+                for entry in data.get_mut(8..).unwrap_or_default().chunks_exact_mut(4) {
+                    let old = u32::from_be_bytes(entry.try_into()?);
+                    let new = u32::try_from(i64::from(old) + delta)
+                        .context("chunk offset out of range")?;
+                    entry.copy_from_slice(&new.to_be_bytes());
+                }
+            }
+            Atom::Leaf { kind, data } if kind == b"co64" => {
+                for entry in data.get_mut(8..).unwrap_or_default().chunks_exact_mut(8) {
+                    let old = u64::from_be_bytes(entry.try_into()?);
+                    let new = old.checked_add_signed(delta).context("chunk offset out of range")?;
+                    entry.copy_from_slice(&new.to_be_bytes());
+                }
+            }
+            Atom::Leaf { .. } => {}
+        }
+    }
+    Ok(())
+}
+
+/// A top-level atom: kind, file offset and total size.
+struct TopAtom {
+    kind: Kind,
+    offset: u64,
+    size: u64,
+}
+
+fn scan_top_level(file: &mut File) -> Result<Vec<TopAtom>> {
+    let len = file.metadata()?.len();
+    let mut atoms = Vec::new();
+    let mut offset = 0;
+    while offset + 8 <= len {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header[..8])?;
+        let size = match u32::from_be_bytes(header[0..4].try_into()?) {
+            0 => len - offset,
+            1 => {
+                file.read_exact(&mut header[8..16])?;
+                u64::from_be_bytes(header[8..16].try_into()?)
+            }
+            n => u64::from(n),
+        };
+        if size < 8 || offset + size > len {
+            bail!("corrupt MP4 file");
+        }
+        atoms.push(TopAtom {
+            kind: header[4..8].try_into()?,
+            offset,
+            size,
+        });
+        offset += size;
+    }
+    Ok(atoms)
+}
+
+pub(super) fn write(path: &Path, edit: &TagEdit) -> Result<()> {
+    let mut file = File::open(path)?;
+    let top = scan_top_level(&mut file)?;
+    let moov_idx = top
+        .iter()
+        .position(|a| &a.kind == b"moov")
+        .context("MP4 file has no moov atom")?;
+    let moov_at = &top[moov_idx];
+
+    let mut raw = vec![0u8; moov_at.size as usize];
+    file.seek(SeekFrom::Start(moov_at.offset))?;
+    file.read_exact(&mut raw)?;
+    let mut atoms = parse_atoms(&raw)?;
+    let moov = atoms.first_mut().context("corrupt moov atom")?;
+
+    let hdlr = || {
+        // Handler `mdir`/`appl`, as written by iTunes.
+        let mut data = vec![0; 8];
+        data.extend_from_slice(b"mdirappl");
+        data.extend_from_slice(&[0; 9]);
+        Atom::Leaf {
+            kind: *b"hdlr",
+            data,
+        }
+    };
+    let udta = child(moov.children_mut().context("corrupt moov atom")?, b"udta", || {
+        container(b"udta", Vec::new(), Vec::new())
+    });
+    let meta = child(udta.children_mut().context("corrupt udta atom")?, b"meta", || {
+        container(b"meta", vec![0; 4], vec![hdlr()])
+    });
+    let ilst = child(meta.children_mut().context("corrupt meta atom")?, b"ilst", || {
+        container(b"ilst", Vec::new(), Vec::new())
+    });
+    apply(ilst.children_mut().context("corrupt ilst atom")?, edit)?;
+
+    let mut new_moov = Vec::new();
+    atoms[0].encode(&mut new_moov);
+
+    // Make room: use a following `free` atom, or pad with a new one.
+    let old_size = moov_at.size;
+    let next_free = top
+        .get(moov_idx + 1)
+        .filter(|a| &a.kind == b"free" || &a.kind == b"skip");
+    let available = old_size + next_free.map_or(0, |a| a.size);
+    let new_size = new_moov.len() as u64;
+    let (padding, consumed_free) = if new_size == available {
+        (0, next_free.is_some())
+    } else if new_size + 8 <= available {
+        (available - new_size, next_free.is_some())
+    } else {
+        (0, false)
+    };
+    let fits = padding > 0 || new_size == available;
+
+    if !fits {
+        let delta = new_size as i64 - old_size as i64;
+        let media_after = top[moov_idx + 1..].iter().any(|a| &a.kind == b"mdat");
+        if media_after {
+            if top.iter().any(|a| &a.kind == b"moof") {
+                bail!("fragmented MP4 files are not supported");
+            }
+            shift_chunk_offsets(&mut atoms, delta)?;
+            new_moov.clear();
+            atoms[0].encode(&mut new_moov);
+        }
+    }
+    if padding > 0 {
+        new_moov.extend_from_slice(&(padding as u32).to_be_bytes());
+        new_moov.extend_from_slice(b"free");
+        new_moov.resize(new_moov.len() + padding as usize - 8, 0);
+    }
+
+    drop(file);
+    replace_file(path, |out| {
+        let mut src = File::open(path)?;
+        for (i, atom) in top.iter().enumerate() {
+            if i == moov_idx {
+                out.write_all(&new_moov)?;
+                continue;
+            }
+            if consumed_free && i == moov_idx + 1 {
+                continue;
+            }
+            src.seek(SeekFrom::Start(atom.offset))?;
+            io::copy(&mut (&mut src).take(atom.size), out)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::write::test_support::{assert_sample_tags, rewrite, round_trip, sample_edit};
+
+    const MEDIA: &[u8] = b"not really AAC frames";
+
+    fn atom(kind: &Kind, data: Vec<u8>) -> Atom {
+        Atom::Leaf { kind: *kind, data }
+    }
+
+    /// `ftyp`, then `moov` holding `mvhd` and `extra`, then `mdat`; the offset
+    /// of `MEDIA` in the file is passed to `extra`.
+    fn mp4(extra: impl Fn(u32) -> Vec<Atom>) -> Vec<u8> {
+        let ftyp = atom(b"ftyp", b"M4A \0\0\0\0M4A isom".to_vec());
+        // Version 0: times, timescale 1000, duration, rate 1.0, volume 1.0, matrix, next id.
+        let mut mvhd = vec![0; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[20..24].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        mvhd[24..26].copy_from_slice(&0x0100u16.to_be_bytes());
+        mvhd[96..100].copy_from_slice(&2u32.to_be_bytes());
+        let mdat = atom(b"mdat", MEDIA.to_vec());
+
+        let build = |offset: u32| {
+            let mut children = vec![atom(b"mvhd", mvhd.clone())];
+            children.extend(extra(offset));
+            let mut out = Vec::new();
+            ftyp.encode(&mut out);
+            container(b"moov", Vec::new(), children).encode(&mut out);
+            mdat.encode(&mut out);
+            out
+        };
+        // Lay the file out once to learn where the media lands.
+        let offset = (build(0).len() - MEDIA.len()) as u32;
+        build(offset)
+    }
+
+    #[test]
+    fn writes_ilst_items() {
+        let (meta, bytes) = round_trip("tags.m4a", &mp4(|_| Vec::new()), &sample_edit());
+        assert_sample_tags(&meta);
+        assert!(bytes.ends_with(MEDIA));
+    }
+
+    #[test]
+    fn shifts_chunk_offsets_when_moov_grows() {
+        let stco = |offset: u32| {
+            let mut data = vec![0, 0, 0, 0, 0, 0, 0, 1];
+            data.extend_from_slice(&offset.to_be_bytes());
+            data
+        };
+        let original = mp4(|offset| {
+            let stbl = container(b"stbl", Vec::new(), vec![atom(b"stco", stco(offset))]);
+            let minf = container(b"minf", Vec::new(), vec![stbl]);
+            let mdia = container(b"mdia", Vec::new(), vec![minf]);
+            vec![container(b"trak", Vec::new(), vec![mdia])]
+        });
+        let (_, bytes) = rewrite("offsets.m4a", &original, &sample_edit(), false);
+        assert!(bytes.len() > original.len());
+
+        let needle = b"stco";
+        let at = bytes.windows(4).position(|w| w == needle).unwrap() + 4 + 8;
+        let offset = u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        assert_eq!(&bytes[offset..offset + MEDIA.len()], MEDIA);
+    }
+}
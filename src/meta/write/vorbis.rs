@@ -0,0 +1,118 @@
+//! Vorbis comment blocks, shared by FLAC and Ogg.
+
+use anyhow::{bail, Result};
+
+use super::TagEdit;
+
+pub(super) struct VorbisComments {
+    vendor: Vec<u8>,
+    /// Raw `KEY=value` entries, in file order.
+    entries: Vec<Vec<u8>>,
+}
+
+impl VorbisComments {
+    pub(super) fn new() -> Self {
+        Self {
+            vendor: b"trix".to_vec(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Parses a comment block (without any packet header). Returns the comments
+    /// and the number of bytes consumed.
+    pub(super) fn parse(data: &[u8]) -> Result<(Self, usize)> {
+        let mut pos = 0;
+        let mut take = |len: usize| -> Result<&[u8]> {
+            let Some(bytes) = data.get(pos..pos + len) else {
+                bail!("truncated Vorbis comment block");
+            };
+            pos += len;
+            Ok(bytes)
+        };
+        let read_u32 = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize;
+
+        let vendor_len = read_u32(take(4)?);
+        let vendor = take(vendor_len)?.to_vec();
+        let count = read_u32(take(4)?);
+        let mut entries = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let len = read_u32(take(4)?);
+            entries.push(take(len)?.to_vec());
+        }
+        Ok((Self { vendor, entries }, pos))
+    }
+
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.vendor.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.vendor);
+        out.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            out.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            out.extend_from_slice(entry);
+        }
+        out
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.entries.iter().find_map(|e| {
+            let (k, v) = split_entry(e)?;
+            k.eq_ignore_ascii_case(key.as_bytes())
+                .then(|| String::from_utf8_lossy(v).to_string())
+        })
+    }
+
+    /// Replaces every `key` entry with `value`; an empty value removes them.
+    fn set(&mut self, key: &str, value: &str) {
+        let at = self.entries.iter().position(|e| is_key(e, key));
+        self.entries.retain(|e| !is_key(e, key));
+        if value.is_empty() {
+            return;
+        }
+        let entry = format!("{key}={value}").into_bytes();
+        match at {
+            Some(i) => self.entries.insert(i.min(self.entries.len()), entry),
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub(super) fn apply(&mut self, edit: &TagEdit) -> Result<()> {
+        for (key, value) in [
+            ("TITLE", &edit.title),
+            ("ARTIST", &edit.artist),
+            ("ALBUM", &edit.album),
+            ("GENRE", &edit.genre),
+            ("DATE", &edit.year),
+        ] {
+            if let Some(value) = value {
+                self.set(key, value.trim());
+            }
+        }
+
+        if let Some((number, total)) = edit.track_pair()? {
+            // A combined `3/12` in TRACKNUMBER is common too; keep its total.
+            let old_total = self
+                .get("TRACKTOTAL")
+                .or_else(|| self.get("TOTALTRACKS"))
+                .or_else(|| {
+                    let number = self.get("TRACKNUMBER")?;
+                    number.split_once('/').map(|(_, t)| t.trim().to_string())
+                });
+            let total = total.map(|t| t.to_string()).or(old_total);
+            self.set("TRACKNUMBER", &number.map(|n| n.to_string()).unwrap_or_default());
+            self.set("TOTALTRACKS", "");
+            let total = total.filter(|_| number.is_some()).unwrap_or_default();
+            self.set("TRACKTOTAL", &total);
+        }
+        Ok(())
+    }
+}
+
+fn split_entry(entry: &[u8]) -> Option<(&[u8], &[u8])> {
+    let eq = entry.iter().position(|&b| b == b'=')?;
+    Some((&entry[..eq], &entry[eq + 1..]))
+}
+
+fn is_key(entry: &[u8], key: &str) -> bool {
+    split_entry(entry).is_some_and(|(k, _)| k.eq_ignore_ascii_case(key.as_bytes()))
+}
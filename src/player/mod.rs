@@ -1,20 +1,25 @@
 use std::{
     cmp::min,
-    collections::HashSet,
-    env, fs, io,
+    collections::{HashMap, HashSet, VecDeque},
+    env, fs,
     path::{Path, PathBuf},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use rodio::Source;
 
 use crate::{
     audio,
     audio::{AudioControl, AudioOutput, VolumeControl},
-    library::Track,
-    lrc::{self, LrcEntry},
-    meta::{self, TrackMeta},
+    library::{
+        self, append_history, load_history, load_play_stats, save_history, save_play_stats,
+        BackgroundLoader, ConflictPolicy, Discovery, FilterContext, HistoryEntry, LibraryChanges,
+        LibraryScan, ListenEnd, MoveStatus, OrganizePlan, PlayStats, SkippedFile, SortOrder,
+        TagLoader, TagTemplate, Track, TrackFilter, TrackKey, Trashed,
+    },
+    lrc::{self, Lyrics},
+    meta::{self, TagEdit, TrackMeta},
     util::{make_shuffled_order, SaturatingDurationSince},
 };
 
@@ -25,11 +30,56 @@ pub(crate) enum PlayState {
     Paused = 2,
 }
 
+/// A track being listened to, for the history.
+struct Listen {
+    track: TrackKey,
+    started: SystemTime,
+    /// Time played before the last pause.
+    listened: Duration,
+    /// Set while playing.
+    resumed: Option<Instant>,
+}
+
+impl Listen {
+    fn listened(&self) -> Duration {
+        self.listened + self.resumed.map_or(Duration::ZERO, |at| at.elapsed())
+    }
+
+    fn pause(&mut self) {
+        self.listened = self.listened();
+        self.resumed = None;
+    }
+
+    fn resume(&mut self) {
+        self.resumed.get_or_insert_with(Instant::now);
+    }
+}
+
+/// A track interrupted this close to its end still counts as played through.
+const COMPLETED_MARGIN: Duration = Duration::from_secs(3);
+
+/// Files moved to the trash by one delete.
+pub(crate) struct DeletedFiles {
+    files: Vec<Trashed>,
+    /// Favorite tracks of them.
+    favorites: Vec<TrackKey>,
+}
+
+/// Whether `path` is one of the `restored` files. The trash records canonical
+/// paths, which may not be the ones the library knows the files by.
+fn is_restored(restored: &[PathBuf], path: &Path) -> bool {
+    restored
+        .iter()
+        .any(|r| r == path || path.canonicalize().is_ok_and(|c| c == *r))
+}
+
 pub(crate) struct Player {
     audio: AudioOutput,
     audio_ctl: AudioControl,
 
     pub(crate) tracks: Vec<Track>,
+    /// Audio files found in the library that can't be decoded.
+    pub(crate) skipped: Vec<SkippedFile>,
     pub(crate) current: usize,
     pub(crate) selected: usize,
 
@@ -48,13 +98,54 @@ pub(crate) struct Player {
     pub(crate) total_duration: Option<Duration>,
 
     pub(crate) now_meta: TrackMeta,
-    pub(crate) lrc: Option<Vec<LrcEntry>>,
-    pub(crate) favorites: HashSet<PathBuf>,
+    pub(crate) lyrics: Option<Lyrics>,
+    /// First line shown of unsynced lyrics.
+    pub(crate) lyrics_scroll: u16,
+    /// Timing adjustment for synced lyrics in ms, on top of the file's own
+    /// `[offset:]`; positive shows them sooner.
+    pub(crate) lyrics_offset: i64,
+    pub(crate) favorites: HashSet<TrackKey>,
     pub(crate) show_favorites: bool,
+    /// Narrows the list and play order, on top of the favorites view.
+    pub(crate) filter: Option<TrackFilter>,
+    /// Order of the library list, and of sequential playback.
+    pub(crate) sort: SortOrder,
+    /// Plays, skips and last play of each track.
+    pub(crate) play_stats: HashMap<TrackKey, PlayStats>,
+    /// Every listen so far, oldest first.
+    pub(crate) history: Vec<HistoryEntry>,
+    listen: Option<Listen>,
+    /// Tracks marked for batch actions. Keyed by identity so marks survive re-sorting.
+    pub(crate) marked: HashSet<TrackKey>,
+    /// Set while a visual selection is open: the track it started on and the
+    /// marks from before it. The range up to the selected track is marked on top.
+    pub(crate) visual: Option<(TrackKey, HashSet<TrackKey>)>,
+    /// How list entries are named; `None` shows file names.
+    pub(crate) display_template: Option<TagTemplate>,
+    /// Where "organize" moves files, relative to `library_path`.
+    pub(crate) organize_template: TagTemplate,
+    tag_loader: Option<TagLoader>,
+    /// Plain lyrics text of each track, for search. Filled in the background.
+    pub(crate) lyrics_index: HashMap<TrackKey, Option<String>>,
+    lyrics_loader: Option<BackgroundLoader<Option<String>>>,
+    /// Bumped whenever `tracks` changes (order, entries or loaded tags), so views
+    /// derived from it know to rebuild.
+    pub(crate) revision: u64,
+    /// Tracks to play next, ahead of the play order.
+    pub(crate) queue: VecDeque<TrackKey>,
 
     pub(crate) loop_current: bool,
 
-    /// The directory from which the library was loaded. Used for YouTube downloads.
+    /// Delete files outright instead of moving them to the trash.
+    pub(crate) hard_delete: bool,
+    /// What the last delete moved to the trash, for undo.
+    pub(crate) last_deleted: Option<DeletedFiles>,
+    /// Files an undo brought back, to select once the watcher's rescan lists them.
+    restored: Vec<PathBuf>,
+
+    /// Roots and filters the library is scanned with.
+    pub(crate) library: LibraryScan,
+    /// The first library directory. Used for YouTube downloads.
     pub(crate) library_path: PathBuf,
 }
 
@@ -63,16 +154,27 @@ impl Player {
         tracks: Vec<Track>,
         start_index: usize,
         audio: AudioOutput,
-        library_path: PathBuf,
+        library: LibraryScan,
     ) -> Result<Self> {
         let audio_ctl = audio.control();
 
+        // Downloads go into the first root; a file root means "next to that file".
+        let library_path = library
+            .roots
+            .first()
+            .map(|root| match root.parent() {
+                Some(parent) if root.is_file() => parent.to_path_buf(),
+                _ => root.clone(),
+            })
+            .unwrap_or_else(|| PathBuf::from("."));
+
         let start_index = min(start_index, tracks.len().saturating_sub(1));
         let play_order: Vec<usize> = (0..tracks.len()).collect();
-        Ok(Self {
+        let mut player = Self {
             audio,
             audio_ctl,
             tracks,
+            skipped: Vec::new(),
             current: start_index,
             selected: start_index,
 
@@ -88,13 +190,38 @@ impl Player {
             total_duration: None,
 
             now_meta: TrackMeta::default(),
-            lrc: None,
+            lyrics: None,
+            lyrics_scroll: 0,
+            lyrics_offset: 0,
             favorites: load_favorites(),
             show_favorites: false,
+            filter: None,
+            sort: load_sort_order(),
+            play_stats: load_play_stats(),
+            history: load_history(),
+            listen: None,
+            marked: HashSet::new(),
+            visual: None,
+            display_template: None,
+            organize_template: TagTemplate::parse(DEFAULT_ORGANIZE_TEMPLATE)
+                .expect("default organize template is valid"),
+            tag_loader: None,
+            lyrics_index: HashMap::new(),
+            lyrics_loader: None,
+            revision: 0,
+            queue: VecDeque::new(),
 
             loop_current: false,
+            hard_delete: false,
+            last_deleted: None,
+            restored: Vec::new(),
+            library,
             library_path,
-        })
+        };
+        if player.sort != SortOrder::default() {
+            player.apply_sort();
+        }
+        Ok(player)
     }
 
     fn sync_play_pos(&mut self) {
@@ -115,17 +242,70 @@ impl Player {
         self.rebuild_play_order();
     }
 
+    pub(crate) fn set_shuffle(&mut self, shuffle: bool) {
+        if shuffle != self.shuffle {
+            self.toggle_shuffle();
+        }
+    }
+
     pub(crate) fn set_favorites_view(&mut self, show_favorites: bool) {
         self.show_favorites = show_favorites;
+        self.renarrow();
+    }
+
+    /// Sets (or with `None`, clears) the filter on the list and play order.
+    pub(crate) fn set_filter(&mut self, filter: Option<TrackFilter>) {
+        if filter.as_ref().is_some_and(TrackFilter::needs_tags) && self.tag_loader.is_none() {
+            self.tag_loader = TagLoader::spawn_tags(&self.tracks);
+        }
+        self.filter = filter;
+        self.renarrow();
+    }
+
+    /// Rebuilds the play order after the listed set changed, moving the
+    /// selection onto a listed track.
+    fn renarrow(&mut self) {
         self.rebuild_play_order();
-        if self.show_favorites
-            && !self.play_order.is_empty()
-            && !self.play_order.contains(&self.selected)
+        if self.narrowed() && !self.play_order.is_empty() && !self.play_order.contains(&self.selected)
         {
-            self.selected = self.play_order[0];
+            self.selected = self.listed_indices()[0];
         }
     }
 
+    /// Whether the favorites view or a filter hides part of the library.
+    fn narrowed(&self) -> bool {
+        self.show_favorites || self.filter.is_some()
+    }
+
+    /// Whether `track` is shown in the list and played in order.
+    pub(crate) fn is_listed(&self, track: &Track) -> bool {
+        if self.show_favorites && !self.favorites.contains(&track.key()) {
+            return false;
+        }
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let ctx = FilterContext {
+            favorites: &self.favorites,
+            play_stats: &self.play_stats,
+            template: self.display_template.as_ref(),
+        };
+        filter.matches(track, &ctx)
+    }
+
+    /// Indices of the listed tracks, in list order.
+    pub(crate) fn listed_indices(&self) -> Vec<usize> {
+        if !self.narrowed() {
+            return (0..self.tracks.len()).collect();
+        }
+        self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| self.is_listed(t))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
     pub(crate) fn rebuild_play_order(&mut self) {
         if !self.has_tracks() {
             self.play_order.clear();
@@ -133,30 +313,25 @@ impl Player {
             return;
         }
 
-        if self.show_favorites {
-            let fav_indices: Vec<usize> = self
-                .tracks
-                .iter()
-                .enumerate()
-                .filter(|(_, t)| self.favorites.contains(&t.path))
-                .map(|(i, _)| i)
-                .collect();
+        if self.narrowed() {
+            let listed = self.listed_indices();
 
-            if fav_indices.is_empty() {
+            if listed.is_empty() {
                 self.play_order.clear();
                 self.play_pos = 0;
+                self.audio_ctl.clear_next_source();
                 return;
             }
 
             if self.shuffle {
-                let current_pos = fav_indices
+                let current_pos = listed
                     .iter()
                     .position(|&i| i == self.current)
                     .unwrap_or(0);
-                let shuffled_local = make_shuffled_order(fav_indices.len(), current_pos);
-                self.play_order = shuffled_local.iter().map(|&i| fav_indices[i]).collect();
+                let shuffled_local = make_shuffled_order(listed.len(), current_pos);
+                self.play_order = shuffled_local.iter().map(|&i| listed[i]).collect();
             } else {
-                self.play_order = fav_indices.clone();
+                self.play_order = listed;
             }
 
             self.play_pos = self
@@ -184,6 +359,351 @@ impl Player {
         self.tracks.get(self.current)
     }
 
+    fn index_of(&self, track: &Track) -> Option<usize> {
+        self.tracks.iter().position(|t| t.same_item(track))
+    }
+
+    pub(crate) fn list_name(&self, track: &Track) -> String {
+        track.list_name(self.display_template.as_ref())
+    }
+
+    pub(crate) fn set_display_template(&mut self, template: Option<TagTemplate>) {
+        self.display_template = template;
+        self.load_tags_in_background(false);
+    }
+
+    /// Starts reading tags if the list name or sort order needs them. `restart`
+    /// replaces a loader that is already running (after the track list changed).
+    fn load_tags_in_background(&mut self, restart: bool) {
+        if self.display_template.is_none() && !self.sort.key.needs_tags() {
+            return;
+        }
+        if self.tag_loader.is_some() && !restart {
+            return;
+        }
+        self.tag_loader = TagLoader::spawn_tags(&self.tracks);
+    }
+
+    /// Reads the tags of every track that has none yet, in the background.
+    pub(crate) fn load_all_tags(&mut self) {
+        if self.tag_loader.is_none() {
+            self.tag_loader = TagLoader::spawn_tags(&self.tracks);
+        }
+    }
+
+    /// Loads everything search looks at: tags for every track, and lyrics for
+    /// those not indexed yet.
+    pub(crate) fn prepare_search(&mut self) {
+        self.load_all_tags();
+        if self.lyrics_loader.is_none() {
+            let todo: Vec<Track> = self
+                .tracks
+                .iter()
+                .filter(|t| !self.lyrics_index.contains_key(&t.key()))
+                .cloned()
+                .collect();
+            self.lyrics_loader = BackgroundLoader::spawn(todo, |t| {
+                t.load_lyrics().map(|l| l.plain_text())
+            });
+        }
+    }
+
+    /// Whether search data is still loading.
+    pub(crate) fn search_loading(&self) -> bool {
+        self.tag_loader.is_some() || self.lyrics_loader.is_some()
+    }
+
+    /// Stores tags and lyrics read by the background loaders. Once all tags are
+    /// in, a tag-based sort is applied again so the list settles in its final order.
+    pub(crate) fn poll_loaders(&mut self) {
+        if let Some(loader) = &self.lyrics_loader {
+            let (loaded, done) = loader.poll();
+            if !loaded.is_empty() {
+                self.lyrics_index.extend(loaded);
+                self.revision += 1;
+            }
+            if done {
+                self.lyrics_loader = None;
+            }
+        }
+
+        let Some(loader) = &self.tag_loader else {
+            return;
+        };
+        let (loaded, done) = loader.poll();
+        if !loaded.is_empty() {
+            let index: HashMap<TrackKey, usize> = self
+                .tracks
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (t.key(), i))
+                .collect();
+            for (key, meta) in loaded {
+                if let Some(&i) = index.get(&key) {
+                    self.tracks[i].tags = Some(meta);
+                }
+            }
+            self.revision += 1;
+        }
+        if done {
+            self.tag_loader = None;
+            if self.sort.key.needs_tags() {
+                self.apply_sort();
+            }
+            if self.filter.as_ref().is_some_and(TrackFilter::needs_tags) {
+                self.renarrow();
+            }
+        }
+    }
+
+    pub(crate) fn toggle_mark_selected(&mut self) {
+        let Some(key) = self.tracks.get(self.selected).map(Track::key) else {
+            return;
+        };
+        if !self.marked.remove(&key) {
+            self.marked.insert(key);
+        }
+    }
+
+    /// Starts marking the range between the selected track and wherever the
+    /// selection moves.
+    pub(crate) fn start_visual(&mut self) {
+        let Some(anchor) = self.tracks.get(self.selected).map(Track::key) else {
+            return;
+        };
+        self.visual = Some((anchor, self.marked.clone()));
+        self.update_visual();
+    }
+
+    /// Ends the visual selection; the range stays marked.
+    pub(crate) fn end_visual(&mut self) {
+        self.visual = None;
+    }
+
+    /// Marks the visual range again after the selection moved.
+    pub(crate) fn update_visual(&mut self) {
+        let Some(anchor) = self.visual.as_ref().map(|(anchor, _)| anchor.clone()) else {
+            return;
+        };
+        let listed = self.listed_indices();
+        let pos = |i: usize| listed.iter().position(|&j| j == i);
+        let range = self
+            .index_of_key(&anchor)
+            .and_then(pos)
+            .zip(pos(self.selected));
+        // The start of the range was filtered out or removed.
+        let (Some((a, b)), Some((_, base))) = (range, &self.visual) else {
+            self.visual = None;
+            return;
+        };
+        let mut marked = base.clone();
+        marked.extend(listed[a.min(b)..=a.max(b)].iter().map(|&i| self.tracks[i].key()));
+        self.marked = marked;
+    }
+
+    /// The tracks a batch action applies to: the marked ones in list order, or
+    /// just the selected one when nothing is marked.
+    pub(crate) fn selection(&self) -> Vec<usize> {
+        let marked: Vec<usize> = self
+            .tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| self.marked.contains(&t.key()))
+            .map(|(i, _)| i)
+            .collect();
+        if marked.is_empty() && self.selected < self.tracks.len() {
+            vec![self.selected]
+        } else {
+            marked
+        }
+    }
+
+    /// Writes `edit` into the files behind `targets` and reloads their tags.
+    ///
+    /// Every file is attempted; the error lists the ones that failed.
+    pub(crate) fn edit_tags(&mut self, targets: &[Track], edit: &TagEdit) -> Result<()> {
+        if targets.iter().any(|t| t.cue.is_some()) {
+            bail!("CUE tracks take their tags from the .cue sheet");
+        }
+        let mut paths: Vec<&PathBuf> = targets.iter().map(|t| &t.path).collect();
+        paths.sort();
+        paths.dedup();
+
+        let mut written: HashSet<PathBuf> = HashSet::new();
+        let mut failures: Vec<String> = Vec::new();
+        for path in paths {
+            match meta::write_tags(path, edit) {
+                Ok(()) => {
+                    written.insert(path.clone());
+                }
+                Err(e) => failures.push(format!(
+                    "{}: {e:#}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                )),
+            }
+        }
+
+        for track in self.tracks.iter_mut().filter(|t| written.contains(&t.path)) {
+            track.tags = Some(track.read_meta());
+        }
+        self.revision += 1;
+        if let Some(track) = self.current_track().filter(|t| written.contains(&t.path)) {
+            self.now_meta = track.read_meta();
+        }
+
+        match failures.len() {
+            0 => Ok(()),
+            1 => bail!("{}", failures[0]),
+            n => bail!("{n} files failed; {}", failures.join("; ")),
+        }
+    }
+
+    /// Plans moving the selected (or marked) files to `organize_template`.
+    pub(crate) fn plan_organize(&self, policy: ConflictPolicy) -> OrganizePlan {
+        self.plan_moves(&self.organize_template, &self.library_path, policy)
+    }
+
+    /// Plans moving the selected (or marked) files into `dir` (relative to the
+    /// library, unless absolute), keeping their names.
+    pub(crate) fn plan_move_to(&self, dir: &Path, policy: ConflictPolicy) -> OrganizePlan {
+        let template = TagTemplate::parse("{filename}").expect("file name template is valid");
+        self.plan_moves(&template, &self.library_path.join(dir), policy)
+    }
+
+    /// Plans moving the selected (or marked) files to `root`/`template`.
+    pub(crate) fn plan_moves(
+        &self,
+        template: &TagTemplate,
+        root: &Path,
+        policy: ConflictPolicy,
+    ) -> OrganizePlan {
+        let targets: Vec<Track> = self
+            .selection()
+            .into_iter()
+            .filter_map(|i| self.tracks.get(i))
+            .map(|t| Track {
+                tags: Some(t.read_meta()),
+                ..t.clone()
+            })
+            .collect();
+        OrganizePlan::new(&targets, template, root, policy)
+    }
+
+    /// Carries out `plan`; favorites, play stats, history, saved playlists and the
+    /// playing track follow the moved files. Returns how many files were moved.
+    pub(crate) fn organize(&mut self, plan: &OrganizePlan) -> Result<usize> {
+        let (moved, failures) = plan.apply();
+        let count = plan.count(MoveStatus::Move) - failures.len();
+        if !moved.is_empty() {
+            self.rescan(LibraryChanges {
+                renamed: moved,
+                ..LibraryChanges::default()
+            });
+        }
+        match failures.len() {
+            0 => Ok(count),
+            1 => bail!("{}", failures[0]),
+            n => bail!("{n} files failed; {}", failures.join("; ")),
+        }
+    }
+
+    /// Switches to the next sort key, keeping the direction.
+    pub(crate) fn cycle_sort_key(&mut self) {
+        self.sort.key = self.sort.key.next();
+        self.apply_sort();
+        self.save_sort_order();
+    }
+
+    pub(crate) fn toggle_sort_direction(&mut self) {
+        self.sort.descending = !self.sort.descending;
+        self.apply_sort();
+        self.save_sort_order();
+    }
+
+    pub(crate) fn set_sort(&mut self, sort: SortOrder) {
+        self.sort = sort;
+        self.apply_sort();
+        self.save_sort_order();
+    }
+
+    /// Reorders `tracks` by `self.sort`, keeping the playing and selected tracks and
+    /// the current play order (including a shuffled one) intact.
+    fn apply_sort(&mut self) {
+        self.load_tags_in_background(false);
+        let perm = library::sort_permutation(&self.tracks, self.sort, &self.play_stats);
+
+        let mut new_index = vec![0; perm.len()];
+        for (new, &old) in perm.iter().enumerate() {
+            new_index[old] = new;
+        }
+        self.tracks = permute(std::mem::take(&mut self.tracks), &perm);
+        self.revision += 1;
+
+        let remap = |i: usize| new_index.get(i).copied().unwrap_or(i);
+        self.current = remap(self.current);
+        self.selected = remap(self.selected);
+        if self.shuffle || self.narrowed() {
+            for i in &mut self.play_order {
+                *i = remap(*i);
+            }
+            if self.narrowed() && !self.shuffle {
+                self.play_order.sort_unstable();
+            }
+            self.sync_play_pos();
+        } else {
+            self.play_order = (0..self.tracks.len()).collect();
+            self.play_pos = self.current;
+        }
+        self.prepare_next_track();
+    }
+
+    /// Starts a listen of the current track, counting it as a play.
+    fn begin_listen(&mut self) {
+        let Some(track) = self.current_track().map(Track::key) else {
+            return;
+        };
+        let now = SystemTime::now();
+        let stats = self.play_stats.entry(track.clone()).or_default();
+        stats.plays += 1;
+        stats.last_played = Some(now);
+        save_play_stats(&self.play_stats);
+        self.listen = Some(Listen {
+            track,
+            started: now,
+            listened: Duration::ZERO,
+            resumed: Some(Instant::now()),
+        });
+    }
+
+    /// Logs the listen in progress, if any.
+    fn end_listen(&mut self, end: ListenEnd) {
+        let Some(listen) = self.listen.take() else {
+            return;
+        };
+        if end == ListenEnd::Skipped {
+            self.play_stats.entry(listen.track.clone()).or_default().skips += 1;
+            save_play_stats(&self.play_stats);
+        }
+        let entry = HistoryEntry {
+            at: listen.started,
+            listened: listen.listened(),
+            path: listen.track.0,
+            start: listen.track.1,
+            end,
+        };
+        append_history(&entry);
+        self.history.push(entry);
+    }
+
+    /// How the listen in progress ends if another track starts now.
+    fn interrupted_listen_end(&self) -> ListenEnd {
+        let position = self.position();
+        match self.total_duration {
+            Some(total) if position + COMPLETED_MARGIN >= total => ListenEnd::Completed,
+            _ => ListenEnd::Skipped,
+        }
+    }
+
     pub(crate) fn refresh_volume(&mut self) {
         self.volume.refresh();
         self.audio_ctl.set_gain(self.volume.app_gain_scalar());
@@ -194,6 +714,11 @@ impl Player {
         self.audio_ctl.set_gain(self.volume.app_gain_scalar());
     }
 
+    /// Sets the volume to `level` (1.0 = 100%), within the backend's range.
+    pub(crate) fn set_volume(&mut self, level: f32) {
+        self.adjust_volume(level - self.volume.display());
+    }
+
     pub(crate) fn play_selected(&mut self) -> Result<()> {
         if !self.has_tracks() {
             return Ok(());
@@ -201,11 +726,11 @@ impl Player {
         self.audio_ctl.clear_next_source();
         self.current = self.selected;
         self.sync_play_pos();
-        self.start_track(Duration::ZERO)
+        self.start_track()
     }
 
     pub(crate) fn restart_current(&mut self) -> Result<()> {
-        self.start_track(Duration::ZERO)
+        self.seek_to(Duration::ZERO)
     }
 
     pub(crate) fn toggle_loop_selected(&mut self) -> Result<()> {
@@ -217,27 +742,41 @@ impl Player {
         if self.selected != self.current {
             self.current = self.selected;
             self.loop_current = true;
-            return self.start_track(Duration::ZERO);
+            return self.start_track();
         }
 
         // Toggle loop for the current track. Restart playback to apply the source mode.
         let pos = self.position();
         self.loop_current = !self.loop_current;
-        self.start_track(pos)
+        self.seek_to(pos)
+    }
+
+    /// Plays the current track from the start as a new listen, ending the one
+    /// in progress.
+    pub(crate) fn start_track(&mut self) -> Result<()> {
+        self.open_track(Duration::ZERO, true)
+    }
+
+    /// Plays the current track from `pos`, continuing the listen in progress.
+    pub(crate) fn seek_to(&mut self, pos: Duration) -> Result<()> {
+        self.open_track(pos, false)
     }
 
-    pub(crate) fn start_track(&mut self, start_pos: Duration) -> Result<()> {
-        let track = self
-            .current_track()
-            .context("No track selected")?
-            .path
-            .clone();
+    fn open_track(&mut self, start_pos: Duration, new_listen: bool) -> Result<()> {
+        let track = self.current_track().context("No track selected")?.clone();
 
         // Prepare everything first. If decoding/seeking fails, keep the current sink playing.
-        let meta = meta::probe_track_meta(&track).unwrap_or_default();
-        let lyrics = lrc::load_lrc(&track);
+        let meta = track.read_meta();
+        let lyrics = track.load_lyrics();
         let (source, total_duration) = open_source(&track, start_pos, self.loop_current)
-            .with_context(|| format!("Failed to open track: {}", track.display()))?;
+            .with_context(|| format!("Failed to open track: {}", track.path.display()))?;
+        // A seek while stopped, or after the list changed under the listen,
+        // starts a new one too.
+        let continues = !new_listen && self.listen.as_ref().is_some_and(|l| l.track == track.key());
+        if !continues {
+            let end = self.interrupted_listen_end();
+            self.end_listen(end);
+        }
 
         // Ensure app gain is applied in the callback.
         self.audio_ctl.set_gain(self.volume.app_gain_scalar());
@@ -246,13 +785,17 @@ impl Player {
             .set_source(source, self.audio.channels, self.audio.sample_rate);
 
         self.now_meta = meta.clone();
-        self.lrc = lyrics;
+        self.set_lyrics(lyrics);
         self.total_duration = total_duration.or(meta.duration);
         self.base_pos = start_pos;
         self.started_at = Some(Instant::now());
         self.paused_at = None;
         self.total_pause = Duration::ZERO;
         self.state = PlayState::Playing;
+        match &mut self.listen {
+            Some(listen) if continues => listen.resume(),
+            _ => self.begin_listen(),
+        }
 
         self.prepare_next_track();
         Ok(())
@@ -260,17 +803,9 @@ impl Player {
 
     pub(crate) fn toggle_pause(&mut self) {
         match self.state {
-            PlayState::Playing => {
-                self.audio_ctl.set_paused(true);
-                self.state = PlayState::Paused;
-                self.paused_at = Some(Instant::now());
-            }
+            PlayState::Playing => self.pause(),
             PlayState::Paused => {
-                self.audio_ctl.set_paused(false);
-                self.state = PlayState::Playing;
-                if let Some(paused_at) = self.paused_at.take() {
-                    self.total_pause += paused_at.elapsed();
-                }
+                let _ = self.play();
             }
             PlayState::Stopped => {
                 // no-op
@@ -288,11 +823,14 @@ impl Player {
                 if let Some(paused_at) = self.paused_at.take() {
                     self.total_pause += paused_at.elapsed();
                 }
+                if let Some(listen) = &mut self.listen {
+                    listen.resume();
+                }
                 Ok(())
             }
             PlayState::Stopped => {
                 if self.has_tracks() {
-                    self.start_track(Duration::ZERO)
+                    self.start_track()
                 } else {
                     Ok(())
                 }
@@ -306,10 +844,14 @@ impl Player {
             self.audio_ctl.set_paused(true);
             self.state = PlayState::Paused;
             self.paused_at = Some(Instant::now());
+            if let Some(listen) = &mut self.listen {
+                listen.pause();
+            }
         }
     }
 
     pub(crate) fn stop_playback(&mut self) {
+        self.end_listen(ListenEnd::Stopped);
         self.audio_ctl.stop_now();
         self.audio_ctl.set_paused(false);
 
@@ -328,21 +870,67 @@ impl Player {
             return Ok(());
         }
         self.audio_ctl.clear_next_source();
+        if !self.advance() {
+            return Ok(());
+        }
+        self.selected = self.current;
+        self.start_track()
+    }
+
+    /// Moves on to the next queued track, else the next one in play order.
+    /// Queued tracks don't move the play order position. Returns false if
+    /// there is nothing to move to (everything is filtered out).
+    fn advance(&mut self) -> bool {
+        while let Some(key) = self.queue.pop_front() {
+            if let Some(i) = self.index_of_key(&key) {
+                self.current = i;
+                return true;
+            }
+        }
+        if self.play_order.is_empty() {
+            return false;
+        }
         self.play_pos = (self.play_pos + 1) % self.play_order.len();
         self.current = self.play_order[self.play_pos];
-        self.selected = self.current;
-        self.start_track(Duration::ZERO)
+        true
+    }
+
+    fn index_of_key(&self, key: &TrackKey) -> Option<usize> {
+        self.tracks.iter().position(|t| t.key() == *key)
+    }
+
+    /// Adds `indices` to the end of the queue.
+    pub(crate) fn enqueue(&mut self, indices: &[usize]) {
+        let keys = indices.iter().filter_map(|&i| self.tracks.get(i)).map(Track::key);
+        self.queue.extend(keys);
+        self.prepare_next_track();
+    }
+
+    /// Plays the first of `indices` now and queues the rest in place of the
+    /// current queue.
+    pub(crate) fn play_all(&mut self, indices: &[usize]) -> Result<()> {
+        let Some((&first, rest)) = indices.split_first() else {
+            return Ok(());
+        };
+        self.queue.clear();
+        self.queue
+            .extend(rest.iter().filter_map(|&i| self.tracks.get(i)).map(Track::key));
+        self.audio_ctl.clear_next_source();
+        self.current = first;
+        self.selected = first;
+        self.sync_play_pos();
+        self.start_track()
     }
 
     pub(crate) fn prev_track(&mut self) -> Result<()> {
-        if !self.has_tracks() {
+        if self.play_order.is_empty() {
             return Ok(());
         }
         self.audio_ctl.clear_next_source();
         self.play_pos = (self.play_pos + self.play_order.len() - 1) % self.play_order.len();
         self.current = self.play_order[self.play_pos];
         self.selected = self.current;
-        self.start_track(Duration::ZERO)
+        self.start_track()
     }
 
     pub(crate) fn seek_relative(&mut self, delta: i64) -> Result<()> {
@@ -363,7 +951,79 @@ impl Player {
             }
         }
 
-        self.start_track(target)
+        self.seek_to(target)
+    }
+
+    fn set_lyrics(&mut self, lyrics: Option<Lyrics>) {
+        // Seeking reloads the same lyrics; keep the scroll position and timing.
+        if self.lyrics != lyrics {
+            self.lyrics_scroll = 0;
+            self.lyrics_offset = 0;
+        }
+        self.lyrics = lyrics;
+    }
+
+    /// Playback position on the lyrics' timeline.
+    pub(crate) fn lyrics_position(&self) -> Duration {
+        lrc::shift(self.position(), self.lyrics_offset)
+    }
+
+    /// Shifts synced lyrics by `ms` (positive shows them sooner).
+    pub(crate) fn nudge_lyrics(&mut self, ms: i64) {
+        if matches!(self.lyrics, Some(Lyrics::Synced(_))) {
+            self.lyrics_offset += ms;
+        }
+    }
+
+    /// Seeks to where synced lyrics line `index` starts.
+    pub(crate) fn seek_to_lyric(&mut self, index: usize) -> Result<()> {
+        let Some(Lyrics::Synced(entries)) = &self.lyrics else {
+            bail!("no synced lyrics");
+        };
+        let entry = entries.get(index).context("no such lyrics line")?;
+        let mut target = lrc::shift(entry.time, -self.lyrics_offset);
+        if let Some(total) = self.total_duration {
+            target = target.min(total);
+        }
+        self.seek_to(target)
+    }
+
+    /// Writes the timing adjustment into the track's `.lrc` file and reloads it.
+    pub(crate) fn save_lyrics_offset(&mut self) -> Result<PathBuf> {
+        let Some(Lyrics::Synced(entries)) = &self.lyrics else {
+            bail!("no synced lyrics");
+        };
+        let track = self.current_track().context("No track playing")?;
+        // The .lrc file is timed for the whole file, not one track of it.
+        if track.cue.is_some() {
+            bail!("CUE tracks can't save a lyrics offset: they share one audio file");
+        }
+        let path = lrc::save_offset(&track.path, entries, self.lyrics_offset)?;
+        let lyrics = track.load_lyrics();
+        self.set_lyrics(lyrics);
+        self.lyrics_offset = 0;
+        Ok(path)
+    }
+
+    /// Reloads the playing track's lyrics, e.g. after writing a `.lrc` for it.
+    pub(crate) fn reload_lyrics(&mut self) {
+        if let Some(audio) = self.current_track().map(|t| t.path.clone()) {
+            self.lyrics_index.retain(|(p, _), _| *p != audio);
+        }
+        let lyrics = self.current_track().and_then(Track::load_lyrics);
+        self.set_lyrics(lyrics);
+    }
+
+    /// Scrolls unsynced lyrics by `delta` lines.
+    pub(crate) fn scroll_lyrics(&mut self, delta: i16) {
+        let Some(Lyrics::Unsynced(text)) = &self.lyrics else {
+            return;
+        };
+        let max = text.lines().count().saturating_sub(1).min(u16::MAX as usize) as u16;
+        self.lyrics_scroll = self
+            .lyrics_scroll
+            .saturating_add_signed(delta)
+            .min(max);
     }
 
     pub(crate) fn position(&self) -> Duration {
@@ -406,118 +1066,372 @@ impl Player {
         if !self.has_tracks() {
             return;
         }
-        if self.show_favorites && !self.play_order.is_empty() {
-            let current_pos = self
-                .play_order
-                .iter()
-                .position(|&i| i == self.selected)
-                .unwrap_or(0);
+        if self.narrowed() {
+            let listed = self.listed_indices();
+            if listed.is_empty() {
+                return;
+            }
+            let current_pos = listed.iter().position(|&i| i == self.selected).unwrap_or(0);
             let next_pos = if current_pos == 0 {
-                self.play_order.len() - 1
+                listed.len() - 1
             } else {
                 current_pos - 1
             };
-            self.selected = self.play_order[next_pos];
+            self.selected = listed[next_pos];
         } else {
             if self.selected > 0 {
                 self.selected -= 1;
             }
         }
+        self.update_visual();
     }
 
     pub(crate) fn select_down(&mut self) {
         if !self.has_tracks() {
             return;
         }
-        if self.show_favorites && !self.play_order.is_empty() {
-            let current_pos = self
-                .play_order
-                .iter()
-                .position(|&i| i == self.selected)
-                .unwrap_or(0);
-            let next_pos = (current_pos + 1) % self.play_order.len();
-            self.selected = self.play_order[next_pos];
+        if self.narrowed() {
+            let listed = self.listed_indices();
+            if listed.is_empty() {
+                return;
+            }
+            let current_pos = listed.iter().position(|&i| i == self.selected).unwrap_or(0);
+            let next_pos = (current_pos + 1) % listed.len();
+            self.selected = listed[next_pos];
         } else {
             if self.selected + 1 < self.tracks.len() {
                 self.selected += 1;
             }
         }
+        self.update_visual();
     }
 
+    pub(crate) fn select_first(&mut self) {
+        if let Some(&first) = self.listed_indices().first() {
+            self.selected = first;
+        }
+        self.update_visual();
+    }
+
+    pub(crate) fn select_last(&mut self) {
+        if let Some(&last) = self.listed_indices().last() {
+            self.selected = last;
+        }
+        self.update_visual();
+    }
+
+    /// Adds the selected (or marked) tracks to the favorites, or removes them
+    /// when all of them are favorites already.
     pub(crate) fn toggle_favorite_selected(&mut self) {
-        if let Some(track) = self.tracks.get(self.selected) {
-            let path = track.path.clone();
-            if self.favorites.contains(&path) {
-                self.favorites.remove(&path);
-            } else {
-                self.favorites.insert(path);
+        let keys: Vec<TrackKey> = self
+            .selection()
+            .into_iter()
+            .map(|i| self.tracks[i].key())
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+        if keys.iter().all(|k| self.favorites.contains(k)) {
+            for key in &keys {
+                self.favorites.remove(key);
+            }
+        } else {
+            self.favorites.extend(keys);
+        }
+        self.save_favorites();
+        if self.narrowed() {
+            self.renarrow();
+        }
+    }
+
+    /// Rescans the library right away and applies the result with `changes`.
+    fn rescan(&mut self, changes: LibraryChanges) {
+        if let Ok(discovery) = library::discover_tracks(&self.library) {
+            self.apply_library_changes(changes, discovery);
+        }
+    }
+
+    /// Reconciles `tracks` with `discovery`, a fresh scan of the library.
+    ///
+    /// Additions and removals are picked up by the scan itself; `changes` carries
+    /// what a scan can't see: renames (so favorites and the playing track follow
+    /// the file) and in-place rewrites (so edited tags of the playing track show up).
+    pub(crate) fn apply_library_changes(&mut self, changes: LibraryChanges, discovery: Discovery) {
+        self.skipped = discovery.skipped;
+
+        let renamed = |path: &PathBuf| changes.new_path(path);
+
+        // Favorites, play stats, history, playlists and marks follow renamed
+        // files and directories.
+        let moved_favorites: Vec<(TrackKey, TrackKey)> = self
+            .favorites
+            .iter()
+            .filter_map(|(p, start)| renamed(p).map(|to| ((p.clone(), *start), (to, *start))))
+            .collect();
+        if !moved_favorites.is_empty() {
+            for (from, to) in moved_favorites {
+                self.favorites.remove(&from);
+                self.favorites.insert(to);
             }
             self.save_favorites();
-            if self.show_favorites {
-                self.rebuild_play_order();
-                if !self.play_order.is_empty() && !self.play_order.contains(&self.selected) {
-                    self.selected = self.play_order[0];
+        }
+        let moved_stats: Vec<(TrackKey, TrackKey)> = self
+            .play_stats
+            .keys()
+            .filter_map(|(p, start)| renamed(p).map(|to| ((p.clone(), *start), (to, *start))))
+            .collect();
+        if !moved_stats.is_empty() {
+            for (from, to) in moved_stats {
+                if let Some(stats) = self.play_stats.remove(&from) {
+                    self.play_stats.entry(to).or_default().merge(stats);
                 }
             }
+            save_play_stats(&self.play_stats);
         }
-    }
+        if let Some(listen) = &mut self.listen {
+            if let Some(to) = renamed(&listen.track.0) {
+                listen.track.0 = to;
+            }
+        }
+        if !changes.renamed.is_empty() {
+            let mut moved = false;
+            for entry in &mut self.history {
+                if let Some(to) = renamed(&entry.path) {
+                    entry.path = to;
+                    moved = true;
+                }
+            }
+            if moved {
+                save_history(&self.history);
+            }
+            library::rename_in_playlists(|p| changes.new_path(p));
+        }
+        self.marked = std::mem::take(&mut self.marked)
+            .into_iter()
+            .map(|(path, start)| (renamed(&path).unwrap_or(path), start))
+            .collect();
+
+        let follow = |t: Option<&Track>| {
+            t.cloned().map(|mut t| {
+                if let Some(to) = renamed(&t.path) {
+                    t.path = to;
+                }
+                t
+            })
+        };
+        let current = follow(self.tracks.get(self.current));
+        let selected = follow(self.tracks.get(self.selected));
+
+        // Keep already loaded tags, except for files that were rewritten.
+        let mut known_tags: HashMap<(PathBuf, Duration), TrackMeta> = HashMap::new();
+        for t in &mut self.tracks {
+            let path = renamed(&t.path).unwrap_or_else(|| t.path.clone());
+            if let Some(tags) = t.tags.take().filter(|_| !changes.modified.contains(&path)) {
+                known_tags.insert((path, t.start_offset()), tags);
+            }
+        }
+        let mut tracks = discovery.tracks;
+        for t in &mut tracks {
+            t.tags = known_tags.remove(&(t.path.clone(), t.start_offset()));
+        }
+        let perm = library::sort_permutation(&tracks, self.sort, &self.play_stats);
+        let tracks = permute(tracks, &perm);
 
-    /// Re-discover tracks from the library directory and merge new ones in.
-    pub(crate) fn refresh_tracks(&mut self) {
-        use crate::library::discover_tracks;
+        let unchanged = self.tracks.len() == tracks.len()
+            && self
+                .tracks
+                .iter()
+                .zip(&tracks)
+                .all(|(a, b)| a.same_item(b));
+        self.tracks = tracks;
+        self.revision += 1;
+        self.load_tags_in_background(true);
+        self.lyrics_index
+            .retain(|(path, _), _| !changes.modified.contains(path));
+        if !self.marked.is_empty() {
+            let keys: HashSet<TrackKey> = self.tracks.iter().map(Track::key).collect();
+            self.marked.retain(|k| keys.contains(k));
+        }
 
-        let Ok(fresh) = discover_tracks(&self.library_path) else {
+        if self.tracks.is_empty() {
+            self.stop_playback();
+            self.current = 0;
+            self.selected = 0;
+            self.rebuild_play_order();
             return;
-        };
+        }
 
-        // Collect existing paths for O(n) lookup.
-        let existing: std::collections::HashSet<PathBuf> =
-            self.tracks.iter().map(|t| t.path.clone()).collect();
+        let last = self.tracks.len() - 1;
+        let current_idx = current.as_ref().and_then(|t| self.index_of(t));
+        self.selected = selected
+            .as_ref()
+            .and_then(|t| self.index_of(t))
+            .or(current_idx)
+            .unwrap_or(self.selected.min(last));
+        let restored = |t: &Track| is_restored(&self.restored, &t.path);
+        if let Some(i) = self.tracks.iter().position(restored) {
+            self.selected = i;
+            self.restored.clear();
+        }
 
-        let mut added = false;
-        for track in fresh {
-            if !existing.contains(&track.path) {
-                self.tracks.push(track);
-                added = true;
+        match (current_idx, current) {
+            (Some(idx), Some(track)) => {
+                self.current = idx;
+                if changes.modified.contains(&track.path) {
+                    self.now_meta = self.tracks[idx].read_meta();
+                    self.set_lyrics(self.tracks[idx].load_lyrics());
+                }
+                // Keep the shuffle order stable when only tags/contents changed.
+                if !unchanged {
+                    self.rebuild_play_order();
+                }
+            }
+            _ => {
+                // The playing file is gone. Its decoder may still hold the file open,
+                // but the list no longer has an entry for it, so stop.
+                self.stop_playback();
+                self.now_meta = TrackMeta::default();
+                self.set_lyrics(None);
+                self.total_duration = None;
+                self.current = self.current.min(last);
+                self.rebuild_play_order();
             }
         }
+    }
 
-        if added {
-            // Re-sort the full list.
-            self.tracks.sort_by(|a, b| a.path.cmp(&b.path));
+    /// The files that deleting `indices` removes, each with how many tracks of
+    /// the library it holds. A CUE track can only go together with its whole
+    /// file, so this can be more tracks than were picked.
+    pub(crate) fn files_to_delete(&self, indices: &[usize]) -> Vec<(PathBuf, usize)> {
+        let mut paths: Vec<&Path> = indices
+            .iter()
+            .filter_map(|&i| self.tracks.get(i))
+            .map(|t| t.path.as_path())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+            .into_iter()
+            .map(|path| {
+                let tracks = self.tracks.iter().filter(|t| t.path == path).count();
+                (path.to_path_buf(), tracks)
+            })
+            .collect()
+    }
 
-            self.rebuild_play_order();
+    /// Moves the files behind `indices` to the trash (or, with `hard_delete`,
+    /// deletes them) and drops them from the library, including other CUE tracks
+    /// of the same files. Every file is attempted; the error lists the ones that
+    /// failed. Returns how many files went.
+    pub(crate) fn delete_tracks(&mut self, indices: &[usize]) -> Result<usize> {
+        let paths: Vec<PathBuf> = self
+            .files_to_delete(indices)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        if paths.is_empty() {
+            return Ok(0);
         }
-    }
 
-    pub(crate) fn delete_selected(&mut self) -> Result<()> {
-        if !self.has_tracks() {
-            return Ok(());
+        // Remove from disk first; a file that can't be removed keeps its entry.
+        let mut deleted: HashSet<PathBuf> = HashSet::new();
+        let mut trashed: Vec<Trashed> = Vec::new();
+        let mut failures: Vec<String> = Vec::new();
+        for path in paths {
+            let result = if fs::symlink_metadata(&path).is_err() {
+                // Already gone.
+                Ok(())
+            } else if self.hard_delete {
+                fs::remove_file(&path).map_err(anyhow::Error::from)
+            } else {
+                library::move_to_trash(&path).map(|t| trashed.push(t))
+            };
+            match result {
+                Ok(()) => {
+                    deleted.insert(path);
+                }
+                Err(e) => failures.push(format!(
+                    "{}: {e:#}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                )),
+            }
         }
 
-        let idx = self.selected;
-        let path = self
-            .tracks
-            .get(idx)
-            .context("No track selected")?
-            .path
-            .clone();
+        if !self.hard_delete {
+            let favorites = self
+                .favorites
+                .iter()
+                .filter(|(p, _)| deleted.contains(p))
+                .cloned()
+                .collect();
+            self.last_deleted = Some(DeletedFiles {
+                files: trashed,
+                favorites,
+            });
+        }
+        let count = deleted.len();
+        if !deleted.is_empty() {
+            self.forget_deleted(&deleted)?;
+        }
 
-        let deleting_current = idx == self.current;
-        let was_playing_or_paused = matches!(self.state, PlayState::Playing | PlayState::Paused);
+        match failures.len() {
+            0 => Ok(count),
+            1 => bail!("Failed to delete {}", failures[0]),
+            n => bail!("Failed to delete {n} files; {}", failures.join("; ")),
+        }
+    }
 
-        // Remove from disk first; if it fails, keep the entry.
-        match fs::remove_file(&path) {
-            Ok(()) => {}
-            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
-            Err(e) => {
-                return Err(e)
-                    .with_context(|| format!("Failed to delete file: {}", path.display()));
+    /// Brings back the files the last delete moved to the trash, with their
+    /// favorite status. The library watcher lists them again; the first is
+    /// selected when it does. Returns how many came back.
+    pub(crate) fn undo_delete(&mut self) -> Result<usize> {
+        let Some(last) = self.last_deleted.take() else {
+            bail!("Nothing to undo");
+        };
+        let mut restored: Vec<PathBuf> = Vec::new();
+        let mut failures: Vec<String> = Vec::new();
+        for file in &last.files {
+            match file.restore() {
+                Ok(()) => restored.push(file.original.clone()),
+                Err(e) => failures.push(format!("{e:#}")),
             }
         }
 
-        self.favorites.remove(&path);
+        let favorites: Vec<TrackKey> = last
+            .favorites
+            .into_iter()
+            .filter(|(p, _)| is_restored(&restored, p))
+            .collect();
+        if !favorites.is_empty() {
+            self.favorites.extend(favorites);
+            self.save_favorites();
+        }
+        let count = restored.len();
+        self.restored = restored;
+
+        match failures.len() {
+            0 => Ok(count),
+            1 => bail!("{}", failures[0]),
+            n => bail!("{n} files failed; {}", failures.join("; ")),
+        }
+    }
+
+    /// Drops the tracks of `deleted` files from the library, stopping playback
+    /// if the current track was one of them.
+    fn forget_deleted(&mut self, deleted: &HashSet<PathBuf>) -> Result<()> {
+        let was_playing_or_paused = matches!(self.state, PlayState::Playing | PlayState::Paused);
+
+        self.favorites.retain(|(p, _)| !deleted.contains(p));
         self.save_favorites();
+        self.marked.retain(|(p, _)| !deleted.contains(p));
+        self.visual = None;
+
+        // A CUE sheet slices one file into several tracks; deleting the file removes
+        // all of them. The current track is affected if it lives in a deleted file.
+        let deleting_current = self
+            .tracks
+            .get(self.current)
+            .is_some_and(|t| deleted.contains(&t.path));
 
         if deleting_current {
             self.audio_ctl.stop_now();
@@ -529,10 +1443,19 @@ impl Player {
             self.total_pause = Duration::ZERO;
             self.total_duration = None;
             self.now_meta = TrackMeta::default();
-            self.lrc = None;
+            self.set_lyrics(None);
         }
 
-        self.tracks.remove(idx);
+        let removed_before = |i: usize, tracks: &[Track]| {
+            tracks[..i.min(tracks.len())]
+                .iter()
+                .filter(|t| deleted.contains(&t.path))
+                .count()
+        };
+        let current_shift = removed_before(self.current, &self.tracks);
+        let selected_shift = removed_before(self.selected, &self.tracks);
+        self.tracks.retain(|t| !deleted.contains(&t.path));
+        self.revision += 1;
 
         if self.tracks.is_empty() {
             self.play_order.clear();
@@ -544,21 +1467,20 @@ impl Player {
         }
 
         // Recompute current index and selection bounds.
-        if !deleting_current {
-            if idx < self.current {
-                self.current = self.current.saturating_sub(1);
-            }
-        } else {
-            self.current = idx.min(self.tracks.len().saturating_sub(1));
-        }
-
-        self.selected = self.selected.min(self.tracks.len().saturating_sub(1));
+        self.current = self
+            .current
+            .saturating_sub(current_shift)
+            .min(self.tracks.len().saturating_sub(1));
+        self.selected = self
+            .selected
+            .saturating_sub(selected_shift)
+            .min(self.tracks.len().saturating_sub(1));
 
         self.rebuild_play_order();
 
         if deleting_current && was_playing_or_paused {
             self.selected = self.current;
-            self.start_track(Duration::ZERO)?;
+            self.start_track()?;
         } else {
             self.prepare_next_track();
         }
@@ -574,6 +1496,12 @@ impl Player {
         if !self.has_tracks() {
             return None;
         }
+        if let Some(i) = self.queue.iter().find_map(|k| self.index_of_key(k)) {
+            return Some(i);
+        }
+        if self.play_order.is_empty() {
+            return None;
+        }
         let next_play_pos = (self.play_pos + 1) % self.play_order.len();
         Some(self.play_order[next_play_pos])
     }
@@ -586,8 +1514,7 @@ impl Player {
 
         if let Some(next_idx) = self.peek_next_track_index() {
             if let Some(track) = self.tracks.get(next_idx) {
-                let path = track.path.clone();
-                match open_source(&path, Duration::ZERO, false) {
+                match open_source(track, Duration::ZERO, false) {
                     Ok((source, _)) => {
                         self.audio_ctl.set_next_source(
                             source,
@@ -609,26 +1536,30 @@ impl Player {
         if !self.has_tracks() {
             return Ok(());
         }
-        self.play_pos = (self.play_pos + 1) % self.play_order.len();
-        self.current = self.play_order[self.play_pos];
+        if !self.advance() {
+            return Ok(());
+        }
         self.selected = self.current;
+        // The previous track played to its end.
+        self.end_listen(ListenEnd::Completed);
 
-        let track = self
-            .current_track()
-            .context("No track selected")?
-            .path
-            .clone();
+        let track = self.current_track().context("No track selected")?.clone();
 
-        let meta = meta::probe_track_meta(&track).unwrap_or_default();
-        let lrc = lrc::load_lrc(&track);
+        let meta = track.read_meta();
+        let lyrics = track.load_lyrics();
         self.now_meta = meta.clone();
-        self.total_duration = meta.duration.or_else(|| meta::probe_duration(&track).ok());
-        self.lrc = lrc;
+        self.total_duration = meta.duration.or_else(|| {
+            meta::probe_duration(&track.path)
+                .ok()
+                .map(|d| d.saturating_sub(track.start_offset()))
+        });
+        self.set_lyrics(lyrics);
         self.base_pos = Duration::ZERO;
         self.started_at = Some(Instant::now());
         self.paused_at = None;
         self.total_pause = Duration::ZERO;
         self.state = PlayState::Playing;
+        self.begin_listen();
 
         self.prepare_next_track();
         Ok(())
@@ -643,6 +1574,9 @@ impl Drop for Player {
     }
 }
 
+pub(crate) const DEFAULT_ORGANIZE_TEMPLATE: &str =
+    "{albumartist}/{year} - {album}/{track:02} {title}.{ext}";
+
 fn favorites_path() -> Option<PathBuf> {
     let base = env::var_os("XDG_CONFIG_HOME")
         .map(PathBuf::from)
@@ -651,14 +1585,21 @@ fn favorites_path() -> Option<PathBuf> {
     Some(base.join("trix").join("favorites.txt"))
 }
 
-fn load_favorites() -> HashSet<PathBuf> {
+/// Reads `start_ms<TAB>path` lines, where `start_ms` tells the CUE tracks of
+/// one file apart (0 for whole files). Lines of older versions hold just the
+/// path.
+fn load_favorites() -> HashSet<TrackKey> {
     let mut favs = HashSet::new();
     if let Some(path) = favorites_path() {
         if let Ok(content) = fs::read_to_string(&path) {
             for line in content.lines() {
-                let p = PathBuf::from(line.trim());
+                let (start, p) = line
+                    .split_once('\t')
+                    .and_then(|(start, p)| Some((start.parse().ok()?, p)))
+                    .unwrap_or((0, line.trim()));
+                let p = PathBuf::from(p);
                 if p.exists() {
-                    favs.insert(p);
+                    favs.insert((p, Duration::from_millis(start)));
                 }
             }
         }
@@ -675,19 +1616,72 @@ impl Player {
             let content: Vec<String> = self
                 .favorites
                 .iter()
-                .map(|p| p.display().to_string())
+                .map(|(p, start)| format!("{}\t{}", start.as_millis(), p.display()))
                 .collect();
             let _ = fs::write(path, content.join("\n"));
         }
     }
 }
 
+/// Reorders `tracks` so that entry `i` of the result is `tracks[perm[i]]`.
+fn permute(tracks: Vec<Track>, perm: &[usize]) -> Vec<Track> {
+    let mut slots: Vec<Option<Track>> = tracks.into_iter().map(Some).collect();
+    perm.iter().filter_map(|&i| slots[i].take()).collect()
+}
+
+fn sort_order_path() -> Option<PathBuf> {
+    favorites_path().map(|p| p.with_file_name("sort.txt"))
+}
+
+fn load_sort_order() -> SortOrder {
+    sort_order_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| SortOrder::parse(&content))
+        .unwrap_or_default()
+}
+
+impl Player {
+    fn save_sort_order(&self) {
+        if let Some(path) = sort_order_path() {
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(path, self.sort.to_config_string());
+        }
+    }
+}
+
 fn open_source(
-    path: &Path,
+    track: &Track,
     start_pos: Duration,
     loop_enabled: bool,
 ) -> Result<(Box<dyn Source<Item = f32> + Send>, Option<Duration>)> {
-    let (source, total) = audio::open_source(path, start_pos, loop_enabled)?;
-    let total = total.or_else(|| meta::probe_duration(path).ok());
+    let path = track.path.as_path();
+    let Some(cue) = &track.cue else {
+        let (source, total) = audio::open_source(path, start_pos, loop_enabled)?;
+        let total = total.or_else(|| meta::probe_duration(path).ok());
+        return Ok((source, total));
+    };
+
+    // CUE track: play a slice of the shared file. Looping replays the slice, so it
+    // has to be opened from the slice start and skipped forward afterwards.
+    let open_at = if loop_enabled { cue.start } else { cue.start + start_pos };
+    let (source, file_total) = audio::open_source(path, open_at, false)?;
+    let file_total = file_total.or_else(|| meta::probe_duration(path).ok());
+    let total = cue
+        .length()
+        .or_else(|| file_total.map(|t| t.saturating_sub(cue.start)));
+
+    let source: Box<dyn Source<Item = f32> + Send> = match (total, loop_enabled) {
+        (Some(len), true) => Box::new(
+            source
+                .take_duration(len)
+                .repeat_infinite()
+                .skip_duration(start_pos),
+        ),
+        (None, true) => Box::new(source.repeat_infinite().skip_duration(start_pos)),
+        (Some(len), false) => Box::new(source.take_duration(len.saturating_sub(start_pos))),
+        (None, false) => source,
+    };
     Ok((source, total))
 }
@@ -0,0 +1,258 @@
+//! SRT and WebVTT subtitle files as timed lyrics.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::lrc::LrcEntry;
+
+/// Loads `.srt`/`.vtt` subtitles next to `audio_path`.
+///
+/// Tries `track.srt`, `track.vtt`, then language-tagged files like yt-dlp's
+/// `track.en.vtt`. Returns `None` if nothing usable is found.
+pub(crate) fn load_subtitles(audio_path: &Path) -> Option<Vec<LrcEntry>> {
+    candidates(audio_path).into_iter().find_map(|path| {
+        let content = fs::read_to_string(&path).ok()?;
+        let entries = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("vtt") => parse_vtt(&content),
+            _ => parse_srt(&content),
+        };
+        (!entries.is_empty()).then_some(entries)
+    })
+}
+
+fn is_subtitle(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("srt") || e.eq_ignore_ascii_case("vtt"))
+}
+
+/// `track.srt`, `track.vtt` and language-tagged `track.<lang>.srt`/`.vtt` files
+/// next to `audio_path`, in the order they are tried.
+pub(crate) fn candidates(audio_path: &Path) -> Vec<PathBuf> {
+    let mut out = vec![
+        audio_path.with_extension("srt"),
+        audio_path.with_extension("vtt"),
+    ];
+    let (Some(dir), Some(stem)) = (audio_path.parent(), audio_path.file_stem()) else {
+        return out;
+    };
+    let prefix = format!("{}.", stem.to_string_lossy());
+    let mut tagged: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| is_subtitle(p))
+        .filter(|p| {
+            // `track.<lang>.vtt`: exactly one extra dot-separated part.
+            let Some(inner) = p.file_stem().and_then(|s| s.to_str()) else {
+                return false;
+            };
+            inner
+                .strip_prefix(&prefix)
+                .is_some_and(|lang| !lang.is_empty() && !lang.contains('.'))
+        })
+        .collect();
+    tagged.sort();
+    out.extend(tagged);
+    out
+}
+
+/// Parses SubRip cues: an optional counter line, `00:00:01,000 --> 00:00:04,000`,
+/// then text lines up to a blank line.
+fn parse_srt(content: &str) -> Vec<LrcEntry> {
+    parse_cues(content.trim_start_matches('\u{feff}'))
+}
+
+/// Parses WebVTT cues, skipping the header and `NOTE`, `STYLE` and `REGION` blocks.
+fn parse_vtt(content: &str) -> Vec<LrcEntry> {
+    let content = content.trim_start_matches('\u{feff}');
+    if !content.starts_with("WEBVTT") {
+        return Vec::new();
+    }
+    parse_cues(content)
+}
+
+/// Both formats are blocks separated by blank lines, where a cue block has a
+/// `start --> end` timing line followed by its text.
+fn parse_cues(content: &str) -> Vec<LrcEntry> {
+    let mut entries: Vec<LrcEntry> = Vec::new();
+
+    // Lines holding only spaces separate blocks too.
+    let lines: Vec<&str> = content.lines().collect();
+    for block in lines.split(|l| l.trim().is_empty()) {
+        let mut lines = block.iter();
+        let Some(timing) = lines.by_ref().find(|l| l.contains("-->")) else {
+            continue;
+        };
+        let Some((start, end)) = parse_timing(timing) else {
+            continue;
+        };
+        let text = lines
+            .map(|l| clean_text(l.trim()))
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.is_empty() {
+            continue;
+        }
+
+        // Auto-generated captions repeat a line in back-to-back cues; merge them.
+        if let Some(prev) = entries.last_mut() {
+            if prev.text == text && prev.end.is_some_and(|e| e >= start) {
+                prev.end = Some(end.max(prev.end.unwrap_or(end)));
+                continue;
+            }
+        }
+        entries.push(LrcEntry {
+            time: start,
+            text,
+            words: Vec::new(),
+            end: Some(end),
+        });
+    }
+
+    entries.sort_by_key(|e| e.time);
+    entries
+}
+
+/// `00:00:01,000 --> 00:00:04,000` (SRT) or `00:01.000 --> 00:04.000 align:start` (VTT).
+fn parse_timing(line: &str) -> Option<(Duration, Duration)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    let start = parse_cue_time(start.trim())?;
+    let end = parse_cue_time(end)?;
+    Some((start, end.max(start)))
+}
+
+/// `hh:mm:ss,mmm`, `hh:mm:ss.mmm` or `mm:ss.mmm`.
+fn parse_cue_time(s: &str) -> Option<Duration> {
+    let (clock, frac) = s.split_once([',', '.']).unwrap_or((s, "0"));
+    let mut secs: u64 = 0;
+    for part in clock.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    if !frac.chars().all(|c| c.is_ascii_digit()) || frac.is_empty() {
+        return None;
+    }
+    let frac = &frac[..frac.len().min(3)];
+    let ms = frac.parse::<u64>().ok()? * 10u64.pow(3 - frac.len() as u32);
+    Some(Duration::from_secs(secs) + Duration::from_millis(ms))
+}
+
+/// Drops markup (`<i>`, `<c.colour>`, VTT inline timestamps, `{\an8}`) and
+/// decodes the common HTML entities.
+fn clean_text(line: &str) -> String {
+    let mut out = String::new();
+    let mut closing: Option<char> = None;
+    for ch in line.chars() {
+        match (closing, ch) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (Some(close), c) if c == close => closing = None,
+            (Some(_), _) => {}
+            (None, c) => out.push(c),
+        }
+    }
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues(entries: &[LrcEntry]) -> Vec<(u64, u64, &str)> {
+        entries
+            .iter()
+            .map(|e| {
+                let end = e.end.map_or(0, |d| d.as_millis() as u64);
+                (e.time.as_millis() as u64, end, e.text.as_str())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_srt_with_crlf_and_a_byte_order_mark() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\nthere\r\n\r\n\
+                   2\r\n00:00:03,000 --> 00:00:04,000\r\n<i>Bye</i>\r\n";
+        assert_eq!(
+            cues(&parse_srt(srt)),
+            [(1000, 2500, "Hello there"), (3000, 4000, "Bye")]
+        );
+    }
+
+    #[test]
+    fn skips_vtt_header_note_style_and_region_blocks() {
+        let vtt = "WEBVTT - captions\nKind: captions\n\n\
+                   NOTE written by hand\nover two lines\n\n\
+                   STYLE\n::cue { color: yellow }\n\n\
+                   REGION\nid:top width:40%\n\n\
+                   intro\n00:01.000 --> 00:02.000 align:start position:10%\n\
+                   <c.yellow>Hi</c> &amp; <00:01.500>welcome\n";
+        assert_eq!(cues(&parse_vtt(vtt)), [(1000, 2000, "Hi & welcome")]);
+    }
+
+    #[test]
+    fn needs_the_webvtt_signature() {
+        assert!(parse_vtt("00:01.000 --> 00:02.000\nHi\n").is_empty());
+        assert_eq!(
+            parse_vtt("\u{feff}WEBVTT\n\n00:01.000 --> 00:02.000\nHi\n").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn merges_repeated_back_to_back_cues() {
+        let vtt = "WEBVTT\n\n\
+                   00:01.000 --> 00:02.000\nsame\n\n\
+                   00:02.000 --> 00:03.000\nsame\n\n\
+                   00:05.000 --> 00:06.000\nsame\n";
+        assert_eq!(
+            cues(&parse_vtt(vtt)),
+            [(1000, 3000, "same"), (5000, 6000, "same")]
+        );
+    }
+
+    #[test]
+    fn splits_blocks_on_whitespace_only_lines() {
+        let srt =
+            "1\n00:00:01,000 --> 00:00:02,000\nOne\n  \n2\n00:00:03,000 --> 00:00:04,000\nTwo\n";
+        assert_eq!(
+            cues(&parse_srt(srt)),
+            [(1000, 2000, "One"), (3000, 4000, "Two")]
+        );
+    }
+
+    #[test]
+    fn skips_cues_without_text_or_timing() {
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\n<i></i>\n\n2\nnot a timing\nText\n";
+        assert!(parse_srt(srt).is_empty());
+    }
+
+    #[test]
+    fn reads_cue_times() {
+        let ms = Duration::from_millis;
+        assert_eq!(parse_cue_time("01:02:03,004"), Some(ms(3_723_004)));
+        assert_eq!(parse_cue_time("00:01.5"), Some(ms(1500)));
+        assert_eq!(parse_cue_time("00:01.25"), Some(ms(1250)));
+        assert_eq!(parse_cue_time("00:01"), Some(ms(1000)));
+        assert_eq!(parse_cue_time("00:01."), None);
+        assert_eq!(parse_cue_time("00:xx.000"), None);
+        // An end before the start is clamped to it.
+        assert_eq!(
+            parse_timing("00:05.000 --> 00:04.000"),
+            Some((ms(5000), ms(5000)))
+        );
+    }
+
+    #[test]
+    fn cleans_markup_and_entities() {
+        assert_eq!(clean_text("{\\an8}<b>Loud</b>&nbsp;&lt;3&gt;"), "Loud <3>");
+    }
+}
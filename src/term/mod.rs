@@ -1,7 +1,7 @@
 use std::io::{self, Write};
 
 use anyhow::{Context, Result};
-use crossterm::{cursor, terminal};
+use crossterm::{cursor, event, terminal};
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 pub(crate) type AppTerminal = Terminal<CrosstermBackend<io::Stdout>>;
@@ -12,8 +12,13 @@ pub(crate) use shell::hide_to_shell_toggleable;
 pub(crate) fn init_terminal() -> Result<AppTerminal> {
     terminal::enable_raw_mode().context("enable raw mode")?;
     let mut stdout = io::stdout();
-    crossterm::execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)
-        .context("enter alternate screen")?;
+    crossterm::execute!(
+        stdout,
+        terminal::EnterAlternateScreen,
+        event::EnableMouseCapture,
+        cursor::Hide
+    )
+    .context("enter alternate screen")?;
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend).context("create terminal")?;
@@ -24,7 +29,12 @@ pub(crate) fn init_terminal() -> Result<AppTerminal> {
 fn restore_terminal_minimal() {
     let _ = terminal::disable_raw_mode();
     let mut stdout = io::stdout();
-    let _ = crossterm::execute!(stdout, terminal::LeaveAlternateScreen, cursor::Show);
+    let _ = crossterm::execute!(
+        stdout,
+        event::DisableMouseCapture,
+        terminal::LeaveAlternateScreen,
+        cursor::Show
+    );
     let _ = stdout.flush();
 }
 
@@ -13,6 +13,7 @@ use std::{
 use anyhow::{Context, Result};
 use crossterm::{
     cursor,
+    event,
     terminal,
 };
 use nix::poll::{poll, PollFd, PollFlags};
@@ -29,8 +30,14 @@ pub(crate) fn hide_to_shell_toggleable(terminal: &mut AppTerminal) -> Result<()>
     // Leave the TUI so the normal terminal screen is visible.
     {
         let backend = terminal.backend_mut();
-        crossterm::execute!(backend, terminal::LeaveAlternateScreen, cursor::Show)
-            .context("leave alternate screen")?;
+        // The shell gets the mouse back.
+        crossterm::execute!(
+            backend,
+            event::DisableMouseCapture,
+            terminal::LeaveAlternateScreen,
+            cursor::Show
+        )
+        .context("leave alternate screen")?;
         let _ = std::io::Write::flush(backend);
     }
 
@@ -252,7 +259,7 @@ pub(crate) fn hide_to_shell_toggleable(terminal: &mut AppTerminal) -> Result<()>
     // Restore the TUI.
     {
         let backend = terminal.backend_mut();
-        crossterm::execute!(backend, terminal::EnterAlternateScreen, terminal::Clear(terminal::ClearType::All), cursor::Hide)
+        crossterm::execute!(backend, terminal::EnterAlternateScreen, terminal::Clear(terminal::ClearType::All), event::EnableMouseCapture, cursor::Hide)
             .context("enter alternate screen")?;
         let _ = std::io::Write::flush(backend);
     }
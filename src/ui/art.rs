@@ -0,0 +1,413 @@
+//! The cover art panel.
+//!
+//! Images are drawn with the kitty graphics protocol or sixel when the terminal
+//! supports them, and as Unicode half blocks otherwise. Half blocks are ordinary
+//! cells in the ratatui buffer; the graphics protocols are written straight to the
+//! terminal after each frame, over cells that ratatui is told to leave alone.
+
+use std::{
+    cell::{Cell, RefCell},
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+use crossterm::{cursor::MoveTo, queue, style::SetBackgroundColor, style::ResetColor};
+use image::{
+    imageops::{self, colorops::ColorMap, FilterType},
+    Rgb, RgbImage,
+};
+use ratatui::{prelude::*, Frame};
+
+use crate::{
+    config::AlbumArtMode, library::BackgroundLoader, meta, player::Player, term::AppTerminal,
+};
+
+/// Decoded covers are kept at most this large; the panel is never bigger.
+const MAX_SIDE: u32 = 600;
+/// Used when the terminal doesn't report its size in pixels.
+const FALLBACK_CELL: (u32, u32) = (10, 20);
+const KITTY_IMAGE_ID: u32 = 7_437;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Kitty,
+    Sixel,
+    Blocks,
+}
+
+impl Protocol {
+    /// Guesses from the environment: querying the terminal would race with the
+    /// event reader.
+    fn detect() -> Self {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        // Inside tmux/screen the escape sequences would need wrapping.
+        if env::var_os("TMUX").is_some() || var("TERM").starts_with("screen") {
+            return Protocol::Blocks;
+        }
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            Protocol::Kitty
+        } else if term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.contains("sixel")
+            || env::var_os("KONSOLE_VERSION").is_some()
+            || program == "iTerm.app"
+        {
+            Protocol::Sixel
+        } else {
+            Protocol::Blocks
+        }
+    }
+}
+
+/// The current track's cover and where it is on screen.
+#[derive(Debug)]
+pub(crate) struct AlbumArt {
+    protocol: Option<Protocol>,
+    /// Track whose cover is loaded or loading (`None` before the first load).
+    source: Option<PathBuf>,
+    image: Option<RgbImage>,
+    /// Reads the cover of `source`; the previous image stays up meanwhile.
+    loader: Option<BackgroundLoader<Option<RgbImage>>>,
+    /// Half-block rendering cached for the panel size it was made for.
+    blocks: RefCell<Option<(Rect, RgbImage)>>,
+    /// Where this frame wants a protocol image; set while drawing.
+    target: Cell<Option<(Rect, Color)>>,
+    /// Where a protocol image currently is on screen.
+    shown: Option<Rect>,
+    /// The cover changed since it was last sent.
+    dirty: bool,
+}
+
+impl AlbumArt {
+    pub(crate) fn new(mode: AlbumArtMode) -> Self {
+        let protocol = match mode {
+            AlbumArtMode::Auto => Some(Protocol::detect()),
+            AlbumArtMode::Kitty => Some(Protocol::Kitty),
+            AlbumArtMode::Sixel => Some(Protocol::Sixel),
+            AlbumArtMode::Blocks => Some(Protocol::Blocks),
+            AlbumArtMode::Off => None,
+        };
+        Self {
+            protocol,
+            source: None,
+            image: None,
+            loader: None,
+            blocks: RefCell::new(None),
+            target: Cell::new(None),
+            shown: None,
+            dirty: false,
+        }
+    }
+
+    pub(crate) fn has_image(&self) -> bool {
+        self.image.is_some()
+    }
+
+    /// Starts loading the cover of the current track when it changes, and
+    /// shows it once it has been read.
+    pub(crate) fn update(&mut self, player: &Player) {
+        if self.protocol.is_none() {
+            return;
+        }
+        let track = player.current_track();
+        let path = track.map(|t| t.path.clone());
+        if path != self.source {
+            self.source = path;
+            self.loader = BackgroundLoader::spawn(track.cloned().into_iter().collect(), |t| {
+                load_cover(&t.path)
+            });
+            if self.loader.is_none() {
+                self.set_image(None);
+            }
+        }
+
+        let Some(loader) = &self.loader else { return };
+        let (loaded, done) = loader.poll();
+        if done {
+            self.loader = None;
+        }
+        if let Some((_, image)) = loaded.into_iter().next() {
+            self.set_image(image);
+        }
+    }
+
+    fn set_image(&mut self, image: Option<RgbImage>) {
+        self.image = image;
+        self.blocks.replace(None);
+        self.dirty = true;
+    }
+
+    /// Draws the cover into `area` (the panel's inner area). With `hidden` the
+    /// panel is covered by a popup: protocol images are taken down meanwhile.
+    pub(crate) fn render(&self, f: &mut Frame, area: Rect, bg: Color, hidden: bool) {
+        let Some(image) = &self.image else { return };
+        match self.protocol {
+            Some(Protocol::Blocks) => self.render_blocks(f, area, image),
+            Some(_) if !hidden && area.width > 0 && area.height > 0 => {
+                // Keep ratatui from painting over the image.
+                let buf = f.buffer_mut();
+                for y in area.top()..area.bottom() {
+                    for x in area.left()..area.right() {
+                        if let Some(cell) = buf.cell_mut((x, y)) {
+                            cell.set_symbol(" ").set_bg(bg).set_skip(true);
+                        }
+                    }
+                }
+                self.target.set(Some((area, bg)));
+            }
+            _ => {}
+        }
+    }
+
+    fn render_blocks(&self, f: &mut Frame, area: Rect, image: &RgbImage) {
+        // Each cell shows two vertically stacked pixels, so pixels come out square.
+        let (cols, rows) = fit(image.dimensions(), area, 2.0);
+        if cols == 0 || rows == 0 {
+            return;
+        }
+        let mut cache = self.blocks.borrow_mut();
+        if cache.as_ref().is_none_or(|(r, _)| *r != area) {
+            let scaled = imageops::resize(image, cols, rows * 2, FilterType::Triangle);
+            *cache = Some((area, scaled));
+        }
+        let Some((_, scaled)) = cache.as_ref() else { return };
+
+        let (x0, y0) = centered(area, cols, rows);
+        let buf = f.buffer_mut();
+        for row in 0..rows {
+            for col in 0..cols {
+                let top = scaled.get_pixel(col, row * 2);
+                let bottom = scaled.get_pixel(col, row * 2 + 1);
+                if let Some(cell) = buf.cell_mut((x0 + col as u16, y0 + row as u16)) {
+                    cell.set_symbol("▀")
+                        .set_fg(Color::Rgb(top[0], top[1], top[2]))
+                        .set_bg(Color::Rgb(bottom[0], bottom[1], bottom[2]));
+                }
+            }
+        }
+    }
+
+    /// Forces the image to be sent again after the next frame (after a resize or
+    /// when returning from the shell, both of which clear the screen).
+    pub(crate) fn invalidate(&mut self) {
+        self.shown = None;
+    }
+
+    /// Brings the terminal in line with the frame just drawn: places, moves or
+    /// removes the protocol image. Call after every `terminal.draw`.
+    pub(crate) fn present(&mut self, terminal: &mut AppTerminal) -> io::Result<()> {
+        let target = self.target.take();
+        let Some(protocol) = self.protocol.filter(|p| *p != Protocol::Blocks) else {
+            return Ok(());
+        };
+        let wanted = target.map(|(r, _)| r);
+        if wanted == self.shown && !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+
+        match protocol {
+            Protocol::Kitty => {
+                let out = terminal.backend_mut();
+                write!(out, "\x1b_Ga=d,d=I,i={KITTY_IMAGE_ID},q=2\x1b\\")?;
+                Write::flush(out)?;
+            }
+            // Sixel pixels stay until their cells are repainted, and ratatui
+            // believes those cells are blank: repaint everything on the next frame.
+            // (A new image in the same place simply paints over the old one.)
+            _ if self.shown.is_some() && wanted != self.shown => {
+                terminal.clear()?;
+                self.shown = None;
+                self.dirty = wanted.is_some();
+                return Ok(());
+            }
+            _ => {}
+        }
+        self.shown = None;
+
+        let (Some((area, bg)), Some(image)) = (target, &self.image) else {
+            return Ok(());
+        };
+        let cell = cell_pixels();
+        let (cols, rows) = fit(image.dimensions(), area, cell.1 as f64 / cell.0 as f64);
+        if cols == 0 || rows == 0 {
+            return Ok(());
+        }
+        let (x0, y0) = centered(area, cols, rows);
+        let scaled = imageops::resize(image, cols * cell.0, rows * cell.1, FilterType::Triangle);
+
+        let out = terminal.backend_mut();
+        // Wipe whatever the cells showed before they were handed over to us.
+        queue!(out, SetBackgroundColor(bg.into()))?;
+        for y in area.top()..area.bottom() {
+            queue!(out, MoveTo(area.x, y))?;
+            write!(out, "{:1$}", "", area.width as usize)?;
+        }
+        queue!(out, ResetColor, MoveTo(x0, y0))?;
+        match protocol {
+            Protocol::Kitty => write_kitty(out, &scaled, cols, rows)?,
+            _ => write_sixel(out, scaled)?,
+        }
+        Write::flush(out)?;
+        self.shown = Some(area);
+        Ok(())
+    }
+}
+
+fn load_cover(path: &Path) -> Option<RgbImage> {
+    let bytes = meta::cover_art(path)?;
+    let image = image::load_from_memory(&bytes).ok()?;
+    let image = if image.width() > MAX_SIDE || image.height() > MAX_SIDE {
+        image.thumbnail(MAX_SIDE, MAX_SIDE)
+    } else {
+        image
+    };
+    Some(image.to_rgb8())
+}
+
+/// The largest `(columns, rows)` that fits `area` with the image's aspect ratio,
+/// where a cell is `cell_aspect` times taller than it is wide.
+fn fit((w, h): (u32, u32), area: Rect, cell_aspect: f64) -> (u32, u32) {
+    if w == 0 || h == 0 {
+        return (0, 0);
+    }
+    let ratio = h as f64 / w as f64 / cell_aspect;
+    let cols = u32::from(area.width);
+    let rows = (cols as f64 * ratio).round() as u32;
+    if rows <= u32::from(area.height) {
+        return (cols, rows.max(1));
+    }
+    let rows = u32::from(area.height);
+    let cols = ((rows as f64 / ratio).round() as u32).clamp(1, u32::from(area.width));
+    (cols, rows)
+}
+
+fn centered(area: Rect, cols: u32, rows: u32) -> (u16, u16) {
+    (
+        area.x + (area.width - cols as u16) / 2,
+        area.y + (area.height - rows as u16) / 2,
+    )
+}
+
+/// The size of one cell in pixels.
+fn cell_pixels() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(s) if s.width > 0 && s.height > 0 && s.columns > 0 && s.rows > 0 => (
+            u32::from(s.width / s.columns).max(1),
+            u32::from(s.height / s.rows).max(1),
+        ),
+        _ => FALLBACK_CELL,
+    }
+}
+
+/// Transmits and places raw RGB data, scaled by the terminal to `cols`×`rows`.
+fn write_kitty(out: &mut impl Write, image: &RgbImage, cols: u32, rows: u32) -> io::Result<()> {
+    let data = base64::engine::general_purpose::STANDARD.encode(image.as_raw());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=24,s={},v={},c={cols},r={rows},C=1,q=2,i={KITTY_IMAGE_ID},m={more};",
+                image.width(),
+                image.height(),
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={more};")?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// A fixed 6×6×6 color cube; with dithering it is plenty for a thumbnail.
+struct ColorCube;
+
+const CUBE_LEVELS: [u8; 6] = [0, 51, 102, 153, 204, 255];
+
+impl ColorMap for ColorCube {
+    type Color = Rgb<u8>;
+
+    fn index_of(&self, color: &Rgb<u8>) -> usize {
+        let level = |c: u8| (usize::from(c) + 25) / 51;
+        level(color[0]) * 36 + level(color[1]) * 6 + level(color[2])
+    }
+
+    fn map_color(&self, color: &mut Rgb<u8>) {
+        let idx = self.index_of(color);
+        *color = Rgb([
+            CUBE_LEVELS[idx / 36],
+            CUBE_LEVELS[idx / 6 % 6],
+            CUBE_LEVELS[idx % 6],
+        ]);
+    }
+}
+
+fn write_sixel(out: &mut impl Write, mut image: RgbImage) -> io::Result<()> {
+    let (w, h) = image.dimensions();
+    if w >= 2 && h >= 2 {
+        imageops::dither(&mut image, &ColorCube);
+    }
+    let indices: Vec<u8> = image.pixels().map(|p| ColorCube.index_of(p) as u8).collect();
+
+    let mut data = format!("\x1bPq\"1;1;{w};{h}");
+    for i in 0..216 {
+        let pct = |l: u8| u32::from(l) * 100 / 255;
+        data.push_str(&format!(
+            "#{i};2;{};{};{}",
+            pct(CUBE_LEVELS[i / 36]),
+            pct(CUBE_LEVELS[i / 6 % 6]),
+            pct(CUBE_LEVELS[i % 6]),
+        ));
+    }
+
+    let mut row = vec![0u8; w as usize];
+    for band in (0..h).step_by(6) {
+        let band_rows = (h - band).min(6);
+        let mut used = [false; 216];
+        for y in band..band + band_rows {
+            for x in 0..w {
+                used[indices[(y * w + x) as usize] as usize] = true;
+            }
+        }
+        for color in (0..216).filter(|&c| used[c]) {
+            for (x, bits) in row.iter_mut().enumerate() {
+                *bits = (0..band_rows)
+                    .filter(|&dy| indices[((band + dy) * w) as usize + x] as usize == color)
+                    .fold(0, |acc, dy| acc | (1 << dy));
+            }
+            data.push_str(&format!("#{color}"));
+            push_sixel_run(&mut data, &row);
+            data.push('$');
+        }
+        data.push('-');
+    }
+    data.push_str("\x1b\\");
+    out.write_all(data.as_bytes())
+}
+
+/// Appends one color's sixels for a band, run-length encoded.
+fn push_sixel_run(data: &mut String, row: &[u8]) {
+    let mut i = 0;
+    while i < row.len() {
+        let bits = row[i];
+        let run = row[i..].iter().take_while(|&&b| b == bits).count();
+        let ch = char::from(63 + bits);
+        if run > 3 {
+            data.push_str(&format!("!{run}{ch}"));
+        } else {
+            (0..run).for_each(|_| data.push(ch));
+        }
+        i += run;
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    library::{self, ConflictPolicy, SortKey, SortOrder, Track, TrackFilter},
+    player::Player,
+    util::{fmt_time, parse_timestamp},
+};
+
+use super::{
+    input::{UiState, YtDlStatus},
+    search::Search,
+};
+
+/// Commands, with what they take, for completion and help.
+pub(crate) const COMMANDS: [(&str, &str); 12] = [
+    ("seek", "TIME, +TIME or -TIME (1:30, +10)"),
+    ("vol", "volume in percent, or +N / -N"),
+    ("add", "playlist NAME | queue: add the selected (or marked) tracks"),
+    ("shuffle", "on | off | toggle"),
+    ("sort", "KEY [asc | desc]"),
+    ("w", "FILE.m3u: write the listed tracks as a playlist"),
+    ("mv", "DIR: move the selected (or marked) files into DIR"),
+    ("search", "QUERY: fuzzy search"),
+    ("filter", "QUERY: narrow the list (no query clears it)"),
+    ("yt", "URL: download audio with yt-dlp"),
+    ("help", "toggle the help overlay"),
+    ("q", "quit"),
+];
+
+const HISTORY_LIMIT: usize = 100;
+/// How long a command's message stays in the input box.
+pub(crate) const MESSAGE_TTL: Duration = Duration::from_secs(4);
+
+/// The `:` prompt.
+#[derive(Debug, Default)]
+pub(crate) struct CommandLine {
+    pub(crate) input: String,
+    /// Why the last command failed; the prompt stays open to fix it.
+    pub(crate) error: Option<String>,
+    /// Position in the history while browsing it with ↑/↓.
+    history_pos: Option<usize>,
+    /// What was typed before browsing the history.
+    draft: String,
+    /// Tab cycles through these for the word starting at `stem.len()`.
+    completion: Option<Completion>,
+}
+
+#[derive(Debug)]
+struct Completion {
+    stem: String,
+    candidates: Vec<String>,
+    index: usize,
+}
+
+pub(crate) enum CommandKey {
+    Handled,
+    Close,
+    Run(String),
+}
+
+/// What a command did.
+pub(crate) enum Outcome {
+    Done,
+    Message(String),
+    Quit,
+}
+
+impl CommandLine {
+    /// Opens the prompt with `input` already typed.
+    pub(crate) fn with_input(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent, history: &[String]) -> CommandKey {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if !matches!(key.code, KeyCode::Tab | KeyCode::BackTab) {
+            self.completion = None;
+        }
+        match key.code {
+            KeyCode::Esc => return CommandKey::Close,
+            KeyCode::Enter => return CommandKey::Run(self.input.trim().to_string()),
+            KeyCode::Backspace if self.input.is_empty() => return CommandKey::Close,
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.error = None;
+            }
+            KeyCode::Tab => self.complete(true),
+            KeyCode::BackTab => self.complete(false),
+            KeyCode::Up => self.browse_history(history, true),
+            KeyCode::Down => self.browse_history(history, false),
+            KeyCode::Char('u') if ctrl => {
+                self.input.clear();
+                self.error = None;
+            }
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.input.push(c);
+                self.error = None;
+            }
+            _ => {}
+        }
+        CommandKey::Handled
+    }
+
+    fn browse_history(&mut self, history: &[String], back: bool) {
+        let pos = match (self.history_pos, back) {
+            (None, true) if !history.is_empty() => {
+                self.draft = self.input.clone();
+                history.len() - 1
+            }
+            (Some(p), true) => p.saturating_sub(1),
+            (Some(p), false) if p + 1 < history.len() => p + 1,
+            (Some(_), false) => {
+                self.history_pos = None;
+                self.input = std::mem::take(&mut self.draft);
+                return;
+            }
+            _ => return,
+        };
+        self.history_pos = Some(pos);
+        self.input = history[pos].clone();
+        self.error = None;
+    }
+
+    /// Completes the word being typed; repeated presses cycle through the
+    /// candidates (`forward` false goes backwards).
+    fn complete(&mut self, forward: bool) {
+        if let Some(c) = &mut self.completion {
+            let n = c.candidates.len();
+            c.index = if forward {
+                (c.index + 1) % n
+            } else {
+                (c.index + n - 1) % n
+            };
+            self.input = format!("{}{}", c.stem, c.candidates[c.index]);
+            return;
+        }
+
+        let word_start = self.input.rfind(' ').map_or(0, |i| i + 1);
+        let (stem, word) = self.input.split_at(word_start);
+        let candidates: Vec<String> = candidates_for(stem)
+            .into_iter()
+            .filter(|c| c.starts_with(word))
+            .collect();
+        match candidates.len() {
+            0 => {}
+            1 => self.input = format!("{stem}{} ", candidates[0]),
+            _ => {
+                let stem = stem.to_string();
+                let index = if forward { 0 } else { candidates.len() - 1 };
+                self.input = format!("{stem}{}", candidates[index]);
+                self.completion = Some(Completion {
+                    stem,
+                    candidates,
+                    index,
+                });
+            }
+        }
+    }
+}
+
+/// What the next word can be, given the words before it.
+fn candidates_for(stem: &str) -> Vec<String> {
+    let words: Vec<&str> = stem.split_whitespace().collect();
+    let owned = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+    match words.as_slice() {
+        [] => COMMANDS.iter().map(|(name, _)| name.to_string()).collect(),
+        ["add"] => owned(&["playlist", "queue"]),
+        ["add", "playlist"] => library::playlist_names(),
+        ["shuffle"] => owned(&["on", "off", "toggle"]),
+        ["sort"] => SortKey::ALL.iter().map(|k| k.label().to_string()).collect(),
+        ["sort", _] => owned(&["asc", "desc"]),
+        _ => Vec::new(),
+    }
+}
+
+/// Remembers `line` as the latest command.
+pub(crate) fn remember(history: &mut Vec<String>, line: &str) {
+    if line.is_empty() || history.last().is_some_and(|last| last == line) {
+        return;
+    }
+    history.push(line.to_string());
+    if history.len() > HISTORY_LIMIT {
+        history.remove(0);
+    }
+}
+
+/// Runs a command line like `seek 1:30`.
+pub(crate) fn run(line: &str, player: &mut Player, ui: &mut UiState) -> Result<Outcome, String> {
+    let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+    let args = args.trim();
+    match name {
+        "" => Ok(Outcome::Done),
+        "seek" => seek(args, player),
+        "vol" | "volume" => volume(args, player),
+        "add" => add(args, player),
+        "shuffle" => {
+            let on = match args {
+                "" | "toggle" => !player.shuffle,
+                "on" | "yes" | "1" => true,
+                "off" | "no" | "0" => false,
+                _ => {
+                    return Err(format!(
+                        "shuffle: expected on, off or toggle, got \"{args}\""
+                    ))
+                }
+            };
+            player.set_shuffle(on);
+            Ok(Outcome::Done)
+        }
+        "sort" => {
+            let sort = SortOrder::parse(args).ok_or_else(|| {
+                let keys: Vec<&str> = SortKey::ALL.iter().map(|k| k.label()).collect();
+                format!("sort: expected one of {}", keys.join(", "))
+            })?;
+            player.set_sort(sort);
+            Ok(Outcome::Done)
+        }
+        "w" | "write" => write(args, player),
+        "mv" | "move" => {
+            if args.is_empty() {
+                return Err("mv: expected a folder, e.g. :mv Favourites/2024".to_string());
+            }
+            if !player.has_tracks() {
+                return Err("no track selected".to_string());
+            }
+            ui.organize = Some(player.plan_move_to(&expand_home(args), ConflictPolicy::Skip));
+            ui.organize_scroll = 0;
+            ui.organize_error = None;
+            Ok(Outcome::Done)
+        }
+        "search" => {
+            let mut search = Search::open(player);
+            search.set_query(args, player);
+            ui.search = Some(search);
+            Ok(Outcome::Done)
+        }
+        "filter" => {
+            player.set_filter(TrackFilter::parse(args)?);
+            Ok(Outcome::Done)
+        }
+        "yt" | "youtube" => start_youtube_download(args, player, ui),
+        "help" | "h" => {
+            ui.show_help = !ui.show_help;
+            ui.help_scroll = 0;
+            Ok(Outcome::Done)
+        }
+        "q" | "quit" => Ok(Outcome::Quit),
+        _ => Err(format!("unknown command \"{name}\" (Tab lists them)")),
+    }
+}
+
+fn seek(args: &str, player: &mut Player) -> Result<Outcome, String> {
+    let relative = match args.chars().next() {
+        Some('+') => Some(1),
+        Some('-') => Some(-1),
+        _ => None,
+    };
+    if let Some(sign) = relative {
+        let by = parse_timestamp(&args[1..])?;
+        player
+            .seek_relative(sign * by.as_millis() as i64)
+            .map_err(|e| format!("Failed to seek: {e}"))?;
+        return Ok(Outcome::Done);
+    }
+
+    let target = parse_timestamp(args)?;
+    if let Some(total) = player.total_duration {
+        if target > total {
+            return Err(format!(
+                "Timestamp is past track length ({}).",
+                fmt_time(total)
+            ));
+        }
+    }
+    player
+        .seek_to(target)
+        .map_err(|e| format!("Failed to seek: {e}"))?;
+    Ok(Outcome::Done)
+}
+
+fn volume(args: &str, player: &mut Player) -> Result<Outcome, String> {
+    let percent: f32 = args
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("vol: expected a percentage, got \"{args}\""))?;
+    if args.starts_with(['+', '-']) {
+        player.adjust_volume(percent / 100.0);
+    } else {
+        player.set_volume(percent / 100.0);
+    }
+    Ok(Outcome::Message(format!(
+        "volume {:.0}%",
+        player.volume.display() * 100.0
+    )))
+}
+
+fn add(args: &str, player: &mut Player) -> Result<Outcome, String> {
+    let targets = player.selection();
+    if targets.is_empty() {
+        return Err("no track selected".to_string());
+    }
+    let (what, name) = args.split_once(' ').unwrap_or((args, ""));
+    match what {
+        "queue" | "q" => {
+            player.enqueue(&targets);
+            Ok(Outcome::Message(format!(
+                "queued {}",
+                tracks_count(targets.len())
+            )))
+        }
+        "playlist" | "p" if !name.trim().is_empty() => {
+            let tracks: Vec<&Track> = targets.iter().map(|&i| &player.tracks[i]).collect();
+            let (_, added) =
+                library::add_to_playlist(name, &tracks).map_err(|e| format!("{e:#}"))?;
+            Ok(Outcome::Message(match added {
+                0 => format!("already in {}", name.trim()),
+                n => format!("added {} to {}", tracks_count(n), name.trim()),
+            }))
+        }
+        _ => Err("add: expected \"playlist NAME\" or \"queue\"".to_string()),
+    }
+}
+
+fn write(args: &str, player: &mut Player) -> Result<Outcome, String> {
+    if args.is_empty() {
+        return Err("w: expected a file name, e.g. :w mix.m3u".to_string());
+    }
+    let path = expand_home(args);
+    let tracks: Vec<&Track> = player
+        .listed_indices()
+        .into_iter()
+        .map(|i| &player.tracks[i])
+        .collect();
+    library::write_m3u(&path, &tracks).map_err(|e| format!("{e:#}"))?;
+    Ok(Outcome::Message(format!(
+        "wrote {} to {}",
+        tracks_count(tracks.len()),
+        path.display()
+    )))
+}
+
+fn tracks_count(n: usize) -> String {
+    if n == 1 {
+        "1 track".to_string()
+    } else {
+        format!("{n} tracks")
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Downloads `url` into the library directory in the background; progress
+/// shows in the input box.
+fn start_youtube_download(url: &str, player: &Player, ui: &UiState) -> Result<Outcome, String> {
+    if url.is_empty() {
+        return Err("yt: expected a URL".to_string());
+    }
+    if matches!(
+        *ui.youtube_dl_status.lock().unwrap(),
+        YtDlStatus::Downloading(_)
+    ) {
+        return Err("a download is already running".to_string());
+    }
+
+    let url = url.to_string();
+    let dest = player.library_path.clone();
+    let status = Arc::clone(&ui.youtube_dl_status);
+    *status.lock().unwrap() = YtDlStatus::Downloading(url.clone());
+
+    // Spawn yt-dlp in a background thread.
+    std::thread::spawn(move || {
+        let result = std::process::Command::new("yt-dlp")
+            .arg("--no-playlist")
+            .arg("-f")
+            .arg("bestaudio[ext=m4a]/bestaudio") // prefer native AAC (no transcode)
+            .arg("-x") // extract audio
+            .arg("--audio-format")
+            .arg("m4a") // ensure m4a output (Symphonia-compatible)
+            .arg("--audio-quality")
+            .arg("0") // best quality if conversion needed
+            .arg("--embed-metadata")
+            .arg("-o")
+            .arg(format!("{}/%(title)s.%(ext)s", dest.display()))
+            .arg(&url)
+            .output();
+
+        let mut s = status.lock().unwrap();
+        match result {
+            Ok(output) if output.status.success() => {
+                *s = YtDlStatus::Done("Download complete!".to_string(), Instant::now());
+            }
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let msg = stderr.lines().last().unwrap_or("yt-dlp failed").to_string();
+                *s = YtDlStatus::Error(msg);
+            }
+            Err(e) => {
+                *s = YtDlStatus::Error(format!("Failed to run yt-dlp: {e}"));
+            }
+        }
+    });
+    Ok(Outcome::Done)
+}
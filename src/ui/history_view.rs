@@ -0,0 +1,76 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{config::Action, library::HistoryEntry, player::Player};
+
+use super::lyrics_view::ViewKey;
+
+/// The play history popup, latest listen first.
+#[derive(Debug, Default)]
+pub(crate) struct HistoryView {
+    /// Highlighted row; row 0 is the latest listen.
+    pub(crate) cursor: usize,
+}
+
+impl HistoryView {
+    /// Actions that keep working over the popup; the history action closes it.
+    pub(crate) const PASS_THROUGH: &[Action] = &[
+        Action::PlayPause,
+        Action::SeekBack,
+        Action::SeekForward,
+        Action::SeekBackLong,
+        Action::SeekForwardLong,
+        Action::PrevTrack,
+        Action::NextTrack,
+        Action::Restart,
+        Action::History,
+        Action::Help,
+    ];
+
+    /// The listens in display order.
+    pub(crate) fn entries(player: &Player) -> impl Iterator<Item = &HistoryEntry> {
+        player.history.iter().rev()
+    }
+
+    /// Library index of the track the highlighted listen was of. `None` once
+    /// the file has left the library.
+    fn track(&self, player: &Player) -> Option<usize> {
+        let entry = Self::entries(player).nth(self.cursor)?;
+        player.tracks.iter().position(|t| entry.is_of(t))
+    }
+
+    fn step(&mut self, delta: isize, player: &Player) {
+        let last = player.history.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// A key, with the action it is bound to.
+    pub(crate) fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        action: Option<Action>,
+        player: &mut Player,
+    ) -> ViewKey {
+        match (key.code, action) {
+            (KeyCode::Esc, _) | (_, Some(Action::Quit)) => return ViewKey::Close,
+            (KeyCode::PageUp, _) => self.step(-10, player),
+            (KeyCode::PageDown, _) => self.step(10, player),
+            (_, Some(Action::SelectUp)) => self.step(-1, player),
+            (_, Some(Action::SelectDown)) => self.step(1, player),
+            (_, Some(Action::SelectFirst)) => self.cursor = 0,
+            (_, Some(Action::SelectLast)) => self.step(isize::MAX, player),
+            (KeyCode::Enter, _) => {
+                if let Some(i) = self.track(player) {
+                    player.selected = i;
+                    let _ = player.play_selected();
+                }
+            }
+            (_, Some(Action::Enqueue)) => {
+                if let Some(i) = self.track(player) {
+                    player.enqueue(&[i]);
+                }
+            }
+            _ => return ViewKey::PassThrough,
+        }
+        ViewKey::Handled
+    }
+}
@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -9,35 +10,81 @@ use crossterm::{
 use ratatui::prelude::Rect;
 
 use crate::{
+    config::{Action, AlbumArtMode, KeyChord, KeyMap, KeyMatch, KeyScope},
+    library::{ConflictPolicy, OrganizePlan, Track, TrackFilter},
+    meta::TrackMeta,
     player::Player,
-    util::{fmt_time, parse_timestamp},
 };
 
-use super::render::{help_overlay_rect, help_wrapped_lines};
+use super::{
+    art::AlbumArt,
+    command::{self, CommandKey, CommandLine, Outcome},
+    history_view::HistoryView,
+    lyrics_recorder::LyricsRecorder,
+    lyrics_view::{LyricsView, ViewKey},
+    mouse::ScreenAreas,
+    render::{help_overlay_rect, help_wrapped_lines, track_info_rows},
+    search::Search,
+    stats_view::StatsView,
+    tag_editor::TagEditor,
+};
 
 #[derive(Debug)]
 pub(crate) struct UiState {
     pub(crate) volume_mode: bool,
     pub(crate) show_help: bool,
     pub(crate) help_scroll: u16,
-    pub(crate) search_mode: bool,
-    pub(crate) search_query: String,
-    pub(crate) move_mode: bool,
-    pub(crate) move_query: String,
-    pub(crate) move_error: Option<String>,
-    last_seek_key: Option<KeyCode>,
+    pub(crate) show_skipped: bool,
+    pub(crate) skipped_scroll: u16,
+    /// Track shown in the info popup, with its freshly probed metadata.
+    pub(crate) track_info: Option<(Track, TrackMeta)>,
+    pub(crate) info_scroll: u16,
+    pub(crate) history: Option<HistoryView>,
+    pub(crate) stats: Option<StatsView>,
+    pub(crate) tag_editor: Option<TagEditor>,
+    pub(crate) lyrics_view: Option<LyricsView>,
+    pub(crate) lyrics_recorder: Option<LyricsRecorder>,
+    pub(crate) art: AlbumArt,
+    /// Dry run of an organize action, shown before any file is moved.
+    pub(crate) organize: Option<OrganizePlan>,
+    pub(crate) organize_scroll: u16,
+    pub(crate) organize_error: Option<String>,
+    /// Outcome of saving a lyrics offset, shown briefly in the lyrics panel.
+    pub(crate) lyrics_message: Option<(Result<String, String>, Instant)>,
+    pub(crate) search: Option<Search>,
+    /// The `:` prompt, while open.
+    pub(crate) command: Option<CommandLine>,
+    pub(crate) command_history: Vec<String>,
+    /// What the last command reported, shown briefly in the input box.
+    pub(crate) command_message: Option<(String, Instant)>,
+    pub(crate) filter_mode: bool,
+    pub(crate) filter_query: String,
+    /// Why the query as typed doesn't parse; the last valid filter stays applied.
+    pub(crate) filter_error: Option<String>,
+    /// The filter from before editing, restored on Esc.
+    filter_before: Option<TrackFilter>,
+    pub(crate) keys: KeyMap,
+    /// The start of a multi-key binding (like `gg`) typed so far.
+    pub(crate) pending_keys: Vec<KeyChord>,
+    pending_at: Instant,
+    last_seek_key: Option<Action>,
     last_seek_at: Instant,
+    /// Filled in by each frame for mouse hit testing.
+    pub(crate) areas: Cell<ScreenAreas>,
+    /// Track last clicked in the list, to tell a double click.
+    pub(crate) last_click: Option<(usize, Instant)>,
+    /// Where the progress bar is being dragged to, as a fraction of the track.
+    pub(crate) seek_drag: Option<f64>,
     pub(crate) delete_confirm: Option<DeleteConfirm>,
     pub(crate) last_tick: Instant,
-    // YouTube download mode
-    pub(crate) youtube_dl_mode: bool,
-    pub(crate) youtube_dl_url: String,
+    // YouTube download started with `:yt`
     pub(crate) youtube_dl_status: Arc<Mutex<YtDlStatus>>,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct DeleteConfirm {
-    pub(crate) index: usize,
+    /// The tracks the first press would delete.
+    pub(crate) targets: Vec<usize>,
     pub(crate) started_at: Instant,
 }
 
@@ -55,17 +102,38 @@ impl UiState {
             volume_mode: false,
             show_help: false,
             help_scroll: 0,
-            search_mode: false,
-            search_query: String::new(),
-            move_mode: false,
-            move_query: String::new(),
-            move_error: None,
+            show_skipped: false,
+            skipped_scroll: 0,
+            track_info: None,
+            info_scroll: 0,
+            history: None,
+            stats: None,
+            tag_editor: None,
+            lyrics_view: None,
+            lyrics_recorder: None,
+            art: AlbumArt::new(AlbumArtMode::Off),
+            organize: None,
+            organize_scroll: 0,
+            organize_error: None,
+            lyrics_message: None,
+            search: None,
+            command: None,
+            command_history: Vec::new(),
+            command_message: None,
+            filter_mode: false,
+            filter_query: String::new(),
+            filter_error: None,
+            filter_before: None,
+            keys: KeyMap::default(),
+            pending_keys: Vec::new(),
+            pending_at: Instant::now(),
             last_seek_key: None,
             last_seek_at: Instant::now() - Duration::from_millis(500),
+            areas: Cell::default(),
+            last_click: None,
+            seek_drag: None,
             delete_confirm: None,
             last_tick: Instant::now(),
-            youtube_dl_mode: false,
-            youtube_dl_url: String::new(),
             youtube_dl_status: Arc::new(Mutex::new(YtDlStatus::Idle)),
         }
     }
@@ -75,17 +143,58 @@ impl UiState {
         self.volume_mode = false;
         self.show_help = false;
         self.help_scroll = 0;
-        self.search_mode = false;
-        self.search_query.clear();
-        self.move_mode = false;
-        self.move_query.clear();
-        self.move_error = None;
+        self.show_skipped = false;
+        self.skipped_scroll = 0;
+        self.track_info = None;
+        self.info_scroll = 0;
+        self.history = None;
+        self.stats = None;
+        self.tag_editor = None;
+        self.organize = None;
+        self.organize_error = None;
+        self.search = None;
+        self.command = None;
+        self.filter_mode = false;
+        self.filter_error = None;
+        self.filter_before = None;
         self.delete_confirm = None;
-        self.youtube_dl_mode = false;
-        self.youtube_dl_url.clear();
+        self.seek_drag = None;
+    }
+
+    /// Adds `key` to the pending keys and looks them up, in the bindings of
+    /// the view `scope` first and then in the main ones. A key that doesn't
+    /// continue the pending sequence starts a new one.
+    fn resolve_key(
+        &mut self,
+        scope: KeyScope,
+        mut pending: Vec<KeyChord>,
+        key: KeyEvent,
+    ) -> KeyMatch {
+        if self.pending_at.elapsed() > SEQUENCE_TIMEOUT {
+            pending.clear();
+        }
+        let lookup = |keys: &KeyMap, seq: &[KeyChord]| match keys.resolve(scope, seq) {
+            KeyMatch::None if scope != KeyScope::Main => keys.resolve(KeyScope::Main, seq),
+            found => found,
+        };
+        let chord = KeyChord::from_event(key);
+        pending.push(chord);
+        let mut found = lookup(&self.keys, &pending);
+        if found == KeyMatch::None && pending.len() > 1 {
+            pending = vec![chord];
+            found = lookup(&self.keys, &pending);
+        }
+        if found == KeyMatch::Prefix {
+            self.pending_keys = pending;
+            self.pending_at = Instant::now();
+        }
+        found
     }
 }
 
+/// How long the next key of a multi-key binding is waited for.
+const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum UiAction {
     None,
@@ -110,83 +219,127 @@ pub(crate) fn handle_key(key: KeyEvent, player: &mut Player, ui: &mut UiState) -
         return Ok(UiAction::Quit);
     }
 
-    // Search mode captures all typing so it doesn't trigger other bindings.
-    if ui.search_mode {
-        match key.code {
-            KeyCode::Esc => {
-                ui.search_mode = false;
-                ui.search_query.clear();
+    // Any layer that takes this key drops a half-typed key sequence.
+    let pending = std::mem::take(&mut ui.pending_keys);
+
+    // The tag editor form captures all typing.
+    if let Some(editor) = &mut ui.tag_editor {
+        let chord = KeyChord::from_event(key);
+        let action = match ui.keys.resolve(KeyScope::TagEditor, &[chord]) {
+            KeyMatch::Action(action) => Some(action),
+            _ => None,
+        };
+        if !editor.handle_key(key, action, player) {
+            ui.tag_editor = None;
+        }
+        return Ok(UiAction::None);
+    }
+
+    if ui.organize.is_some() {
+        let action = match ui.resolve_key(KeyScope::Organize, pending, key) {
+            KeyMatch::Action(action) => Some(action),
+            KeyMatch::Prefix => return Ok(UiAction::None),
+            KeyMatch::None => None,
+        };
+        let Some(plan) = &ui.organize else {
+            return Ok(UiAction::None);
+        };
+        let max_scroll = plan.moves.len().saturating_sub(1).min(u16::MAX as usize) as u16;
+        match (key.code, action) {
+            (KeyCode::Esc, _) | (_, Some(Action::Quit)) => {
+                ui.organize = None;
+                ui.organize_error = None;
+            }
+            (KeyCode::Enter, _) => {
+                let (template, root, policy) =
+                    (plan.template.clone(), plan.root.clone(), plan.policy);
+                match player.organize(plan) {
+                    Ok(_) => {
+                        ui.organize = None;
+                        ui.organize_error = None;
+                    }
+                    Err(e) => {
+                        // Show what is left to do, with the failures.
+                        ui.organize = Some(player.plan_moves(&template, &root, policy));
+                        ui.organize_error = Some(format!("{e:#}"));
+                    }
+                }
             }
-            KeyCode::Enter => {
-                // Confirm selection (and play) then exit search.
-                let _ = player.play_selected();
-                ui.search_mode = false;
+            (_, Some(Action::OrganizeConflicts)) => {
+                let replanned = player.plan_moves(&plan.template, &plan.root, plan.policy.toggle());
+                ui.organize = Some(replanned);
             }
-            KeyCode::Backspace => {
-                ui.search_query.pop();
-                apply_search_selection(player, &ui.search_query);
+            (_, Some(Action::SelectUp)) => {
+                ui.organize_scroll = ui.organize_scroll.saturating_sub(1);
             }
-            KeyCode::Char(c) => {
-                // Ignore control chords; accept everything else as input.
-                if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    ui.search_query.push(c);
-                    apply_search_selection(player, &ui.search_query);
-                }
+            (_, Some(Action::SelectDown)) => {
+                ui.organize_scroll = ui.organize_scroll.saturating_add(1).min(max_scroll);
+            }
+            (KeyCode::PageUp, _) => {
+                ui.organize_scroll = ui.organize_scroll.saturating_sub(10);
+            }
+            (KeyCode::PageDown, _) => {
+                ui.organize_scroll = ui.organize_scroll.saturating_add(10).min(max_scroll);
             }
             _ => {}
         }
-
         return Ok(UiAction::None);
     }
 
-    // Move mode captures all typing so it doesn't trigger other bindings.
-    if ui.move_mode {
-        match key.code {
-            KeyCode::Esc => {
-                ui.move_mode = false;
-                ui.move_query.clear();
-                ui.move_error = None;
-            }
-            KeyCode::Enter => {
-                match parse_timestamp(&ui.move_query) {
-                    Ok(target) => {
-                        if let Some(total) = player.total_duration {
-                            if target > total {
-                                ui.move_error = Some(format!(
-                                    "Timestamp is past track length ({}).",
-                                    fmt_time(total)
-                                ));
-                                return Ok(UiAction::None);
+    // The command line captures all typing.
+    if let Some(prompt) = &mut ui.command {
+        match prompt.handle_key(key, &ui.command_history) {
+            CommandKey::Handled => {}
+            CommandKey::Close => ui.command = None,
+            CommandKey::Run(line) => {
+                command::remember(&mut ui.command_history, &line);
+                match command::run(&line, player, ui) {
+                    Ok(outcome) => {
+                        ui.command = None;
+                        match outcome {
+                            Outcome::Done => {}
+                            Outcome::Message(msg) => {
+                                ui.command_message = Some((msg, Instant::now()));
+                            }
+                            Outcome::Quit => {
+                                player.stop_playback();
+                                return Ok(UiAction::Quit);
                             }
                         }
-
-                        if let Err(e) = player.start_track(target) {
-                            ui.move_error = Some(format!("Failed to seek: {e}"));
-                            return Ok(UiAction::None);
-                        }
-
-                        ui.move_mode = false;
-                        ui.move_query.clear();
-                        ui.move_error = None;
                     }
-                    Err(msg) => {
-                        ui.move_error = Some(msg);
+                    Err(e) => {
+                        if let Some(prompt) = &mut ui.command {
+                            prompt.error = Some(e);
+                        }
                     }
                 }
             }
+        }
+        return Ok(UiAction::None);
+    }
+
+    // Filter mode captures all typing; the list narrows as the query changes.
+    if ui.filter_mode {
+        match key.code {
+            KeyCode::Esc => {
+                ui.filter_mode = false;
+                ui.filter_error = None;
+                player.set_filter(ui.filter_before.take());
+            }
+            KeyCode::Enter if ui.filter_error.is_none() => {
+                ui.filter_mode = false;
+                ui.filter_before = None;
+            }
             KeyCode::Backspace => {
-                ui.move_query.pop();
-                ui.move_error = None;
+                ui.filter_query.pop();
+                apply_filter_query(player, ui);
             }
-            KeyCode::Char(c) => {
+            KeyCode::Char(c)
                 if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    ui.move_query.push(c);
-                    ui.move_error = None;
-                }
+                    && !key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                ui.filter_query.push(c);
+                apply_filter_query(player, ui);
             }
             _ => {}
         }
@@ -194,153 +347,179 @@ pub(crate) fn handle_key(key: KeyEvent, player: &mut Player, ui: &mut UiState) -
         return Ok(UiAction::None);
     }
 
-    // YouTube download mode captures all typing.
-    if ui.youtube_dl_mode {
-        match key.code {
-            KeyCode::Esc => {
-                ui.youtube_dl_mode = false;
-                ui.youtube_dl_url.clear();
-            }
-            KeyCode::Enter => {
-                let url = ui.youtube_dl_url.trim().to_string();
-                if url.is_empty() {
+    // Typing the search query captures all keys, unless a view is over it.
+    let over_search = ui.history.is_some()
+        || ui.stats.is_some()
+        || ui.lyrics_recorder.is_some()
+        || ui.lyrics_view.is_some();
+    if let Some(search) = ui.search.as_mut().filter(|s| s.editing && !over_search) {
+        if let ViewKey::Close = search.handle_query_key(key, player) {
+            ui.search = None;
+        }
+        return Ok(UiAction::None);
+    }
+
+    // The history and stats popups, the full-screen lyrics views and the
+    // search results, unless a popup on top of them has the keys. Their own
+    // bindings come first; keys they pass through only trigger the actions
+    // they allow.
+    let popup_open = ui.show_help || ui.show_skipped || ui.track_info.is_some();
+    let scope = if popup_open || ui.history.is_some() {
+        KeyScope::Main
+    } else if ui.stats.is_some() {
+        KeyScope::Stats
+    } else if ui.lyrics_recorder.is_some() {
+        KeyScope::LyricsSync
+    } else if ui.lyrics_view.is_none() && ui.search.is_some() {
+        KeyScope::Search
+    } else {
+        KeyScope::Main
+    };
+    let action = match ui.resolve_key(scope, pending, key) {
+        KeyMatch::Action(action) => Some(action),
+        // Wait for the rest of a multi-key binding.
+        KeyMatch::Prefix => return Ok(UiAction::None),
+        KeyMatch::None => None,
+    };
+
+    let mut allowed: Option<&[Action]> = None;
+    if let Some(view) = &mut ui.history {
+        if !popup_open {
+            match view.handle_key(key, action, player) {
+                ViewKey::Handled => return Ok(UiAction::None),
+                ViewKey::Close => {
+                    ui.history = None;
                     return Ok(UiAction::None);
                 }
-
-                // Check if already downloading
-                {
-                    let status = ui.youtube_dl_status.lock().unwrap();
-                    if matches!(*status, YtDlStatus::Downloading(_)) {
-                        return Ok(UiAction::None);
-                    }
+                ViewKey::PassThrough => allowed = Some(HistoryView::PASS_THROUGH),
+            }
+        }
+    } else if let Some(view) = &mut ui.stats {
+        if !popup_open {
+            match view.handle_key(key, action) {
+                ViewKey::Handled => return Ok(UiAction::None),
+                ViewKey::Close => {
+                    ui.stats = None;
+                    return Ok(UiAction::None);
                 }
-
-                let dest = player.library_path.clone();
-                let status = Arc::clone(&ui.youtube_dl_status);
-
-                {
-                    let mut s = status.lock().unwrap();
-                    *s = YtDlStatus::Downloading(url.clone());
+                ViewKey::PassThrough => allowed = Some(StatsView::PASS_THROUGH),
+            }
+        }
+    } else if let Some(recorder) = &mut ui.lyrics_recorder {
+        if !popup_open {
+            match recorder.handle_key(key, action, player) {
+                ViewKey::Handled => return Ok(UiAction::None),
+                ViewKey::Close => {
+                    ui.lyrics_recorder = None;
+                    return Ok(UiAction::None);
                 }
-
-                // Spawn yt-dlp in a background thread.
-                std::thread::spawn(move || {
-                    let result = std::process::Command::new("yt-dlp")
-                        .arg("--no-playlist")
-                        .arg("-f")
-                        .arg("bestaudio[ext=m4a]/bestaudio") // prefer native AAC (no transcode)
-                        .arg("-x")                          // extract audio
-                        .arg("--audio-format")
-                        .arg("m4a")                         // ensure m4a output (Symphonia-compatible)
-                        .arg("--audio-quality")
-                        .arg("0")                           // best quality if conversion needed
-                        .arg("--embed-metadata")
-                        .arg("-o")
-                        .arg(format!("{}/%(title)s.%(ext)s", dest.display()))
-                        .arg(&url)
-                        .output();
-
-                    let mut s = status.lock().unwrap();
-                    match result {
-                        Ok(output) if output.status.success() => {
-                            *s = YtDlStatus::Done("Download complete!".to_string(), Instant::now());
-                        }
-                        Ok(output) => {
-                            let stderr = String::from_utf8_lossy(&output.stderr);
-                            let msg = stderr.lines().last().unwrap_or("yt-dlp failed").to_string();
-                            *s = YtDlStatus::Error(msg);
-                        }
-                        Err(e) => {
-                            *s = YtDlStatus::Error(format!("Failed to run yt-dlp: {e}"));
-                        }
-                    }
-                });
-
-                ui.youtube_dl_url.clear();
+                ViewKey::PassThrough => allowed = Some(LyricsRecorder::PASS_THROUGH),
             }
-            KeyCode::Backspace => {
-                ui.youtube_dl_url.pop();
+        }
+    } else if let Some(view) = &mut ui.lyrics_view {
+        if !popup_open {
+            match view.handle_key(key, action, player) {
+                ViewKey::Handled => return Ok(UiAction::None),
+                ViewKey::Close => {
+                    ui.lyrics_view = None;
+                    return Ok(UiAction::None);
+                }
+                ViewKey::PassThrough => allowed = Some(LyricsView::PASS_THROUGH),
             }
-            KeyCode::Char(c) => {
-                if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    ui.youtube_dl_url.push(c);
+        }
+    } else if let Some(search) = &mut ui.search {
+        // Browsing the results lets playback keys through.
+        if !popup_open {
+            match search.handle_results_key(key, action, player) {
+                ViewKey::Handled => return Ok(UiAction::None),
+                ViewKey::Close => {
+                    ui.search = None;
+                    return Ok(UiAction::None);
                 }
+                ViewKey::PassThrough => {}
             }
-            _ => {}
         }
-
-        return Ok(UiAction::None);
     }
 
+    let action = action
+        .filter(|a| a.scope() == KeyScope::Main)
+        .filter(|a| allowed.is_none_or(|list| list.contains(a)));
+
     // Enter search mode.
-    if key.code == KeyCode::Char('S') {
-        ui.search_mode = true;
-        ui.search_query.clear();
-        ui.move_mode = false;
-        ui.move_query.clear();
-        ui.move_error = None;
+    if action == Some(Action::Search) {
+        ui.search = Some(Search::open(player));
         ui.delete_confirm = None;
         return Ok(UiAction::None);
     }
 
-    // Enter move mode.
-    if key.code == KeyCode::Char('m') {
-        ui.move_mode = true;
-        ui.move_query.clear();
-        ui.move_error = None;
-        ui.search_mode = false;
-        ui.search_query.clear();
+    // Enter filter mode, editing the current filter.
+    if action == Some(Action::Filter) {
+        ui.filter_mode = true;
+        ui.filter_query = player
+            .filter
+            .as_ref()
+            .map(|f| f.query().to_string())
+            .unwrap_or_default();
+        ui.filter_error = None;
+        ui.filter_before = player.filter.clone();
+        ui.search = None;
         ui.delete_confirm = None;
         return Ok(UiAction::None);
     }
 
-    // Enter YouTube download mode.
-    if key.code == KeyCode::Char('y') {
-        ui.youtube_dl_mode = true;
-        ui.youtube_dl_url.clear();
-        // Reset status if last download is done/errored so we start fresh.
+    // Clear the filter.
+    if action == Some(Action::ClearFilter) {
+        player.set_filter(None);
+        return Ok(UiAction::None);
+    }
+
+    // Open the command line; the other actions here open it with their
+    // command already typed.
+    let prefill = match action {
+        Some(Action::CommandLine) => Some(""),
+        Some(Action::MoveToTime) => Some("seek "),
+        Some(Action::YoutubeDownload) => Some("yt "),
+        Some(Action::AddToPlaylist) => Some("add playlist "),
+        Some(Action::MoveToFolder) => Some("mv "),
+        _ => None,
+    };
+    if let Some(input) = prefill {
+        ui.command = Some(CommandLine::with_input(input));
+        // A finished or failed download's status makes way for the prompt.
         {
             let mut s = ui.youtube_dl_status.lock().unwrap();
             if !matches!(*s, YtDlStatus::Downloading(_)) {
                 *s = YtDlStatus::Idle;
             }
         }
-        ui.search_mode = false;
-        ui.search_query.clear();
-        ui.move_mode = false;
-        ui.move_query.clear();
-        ui.move_error = None;
+        ui.search = None;
         ui.delete_confirm = None;
         return Ok(UiAction::None);
     }
 
-    // Tab toggles favorites view
-    if key.code == KeyCode::Tab {
+    // Toggle favorites view
+    if action == Some(Action::FavoritesView) {
         player.set_favorites_view(!player.show_favorites);
         return Ok(UiAction::None);
     }
 
     // Add selected track to favorites
-    if key.code == KeyCode::Char('a') {
+    if action == Some(Action::Favorite) {
         player.toggle_favorite_selected();
         return Ok(UiAction::None);
     }
 
     // Quit
-    if key.code == KeyCode::Char('q') {
+    if action == Some(Action::Quit) {
         player.stop_playback();
         return Ok(UiAction::Quit);
     }
 
     // Cancel pending delete confirmation.
-    if key.code == KeyCode::Esc {
-        ui.delete_confirm = None;
-    }
+    let cancelled_delete = key.code == KeyCode::Esc && ui.delete_confirm.take().is_some();
 
     // Help overlay toggle.
-    if matches!(key.code, KeyCode::Char('h') | KeyCode::Char('?')) {
+    if action == Some(Action::Help) {
         ui.show_help = !ui.show_help;
         if ui.show_help {
             ui.help_scroll = 0;
@@ -350,47 +529,28 @@ pub(crate) fn handle_key(key: KeyEvent, player: &mut Player, ui: &mut UiState) -
 
     // While help is open, keep playback running; allow scrolling + close.
     if ui.show_help {
-        // Determine current page size and scroll limits based on terminal size.
-        let (page, max_scroll) = if let Ok((cols, rows)) = terminal::size() {
-            let area = Rect {
-                x: 0,
-                y: 0,
-                width: cols,
-                height: rows,
-            };
-            let overlay = help_overlay_rect(area);
-            let inner_h = overlay.height.saturating_sub(2) as usize;
-            let inner_w = overlay.width.saturating_sub(2);
-            let lines = help_wrapped_lines(ui, inner_w);
-            let max_scroll = lines.len().saturating_sub(inner_h);
-            (
-                (inner_h.saturating_sub(1).max(1)).min(u16::MAX as usize) as u16,
-                max_scroll.min(u16::MAX as usize) as u16,
-            )
-        } else {
-            (10, 0)
-        };
+        let (page, max_scroll) = help_scroll_limits(ui);
 
-        match key.code {
-            KeyCode::Esc => {
+        match (key.code, action) {
+            (KeyCode::Esc, _) => {
                 ui.show_help = false;
             }
-            KeyCode::Up | KeyCode::Char('k') => {
+            (_, Some(Action::SelectUp)) => {
                 ui.help_scroll = ui.help_scroll.saturating_sub(1);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            (_, Some(Action::SelectDown)) => {
                 ui.help_scroll = ui.help_scroll.saturating_add(1);
             }
-            KeyCode::PageUp => {
+            (KeyCode::PageUp, _) => {
                 ui.help_scroll = ui.help_scroll.saturating_sub(page);
             }
-            KeyCode::PageDown => {
+            (KeyCode::PageDown, _) => {
                 ui.help_scroll = ui.help_scroll.saturating_add(page);
             }
-            KeyCode::Home => {
+            (_, Some(Action::SelectFirst)) => {
                 ui.help_scroll = 0;
             }
-            KeyCode::End => {
+            (_, Some(Action::SelectLast)) => {
                 ui.help_scroll = max_scroll;
             }
             _ => {}
@@ -400,19 +560,113 @@ pub(crate) fn handle_key(key: KeyEvent, player: &mut Player, ui: &mut UiState) -
         return Ok(UiAction::None);
     }
 
-    // Volume mode: toggle with `v`, adjust with Up/Down until toggled off.
+    // Skipped (undecodable) files overlay.
+    if action == Some(Action::SkippedFiles) {
+        ui.show_skipped = !ui.show_skipped;
+        ui.skipped_scroll = 0;
+        return Ok(UiAction::None);
+    }
+
+    if ui.show_skipped {
+        let max_scroll = player.skipped.len().saturating_sub(1).min(u16::MAX as usize) as u16;
+        match (key.code, action) {
+            (KeyCode::Esc, _) => {
+                ui.show_skipped = false;
+            }
+            (_, Some(Action::SelectUp)) => {
+                ui.skipped_scroll = ui.skipped_scroll.saturating_sub(1);
+            }
+            (_, Some(Action::SelectDown)) => {
+                ui.skipped_scroll = ui.skipped_scroll.saturating_add(1);
+            }
+            (KeyCode::PageUp, _) => {
+                ui.skipped_scroll = ui.skipped_scroll.saturating_sub(10);
+            }
+            (KeyCode::PageDown, _) => {
+                ui.skipped_scroll = ui.skipped_scroll.saturating_add(10);
+            }
+            (_, Some(Action::SelectFirst)) => {
+                ui.skipped_scroll = 0;
+            }
+            (_, Some(Action::SelectLast)) => {
+                ui.skipped_scroll = max_scroll;
+            }
+            _ => {}
+        }
+
+        ui.skipped_scroll = ui.skipped_scroll.min(max_scroll);
+        return Ok(UiAction::None);
+    }
+
+    // Play history popup.
+    if action == Some(Action::History) {
+        ui.history = match ui.history {
+            Some(_) => None,
+            None => Some(HistoryView::default()),
+        };
+        return Ok(UiAction::None);
+    }
+
+    // Listening stats popup.
+    if action == Some(Action::Stats) {
+        ui.stats = match ui.stats {
+            Some(_) => None,
+            None => Some(StatsView::open(player)),
+        };
+        return Ok(UiAction::None);
+    }
+
+    // Track info popup for the selected track.
+    if action == Some(Action::TrackInfo) {
+        ui.track_info = match ui.track_info {
+            Some(_) => None,
+            None => player
+                .tracks
+                .get(player.selected)
+                .map(|t| (t.clone(), t.read_meta())),
+        };
+        ui.info_scroll = 0;
+        return Ok(UiAction::None);
+    }
+
+    if let Some((track, meta)) = &ui.track_info {
+        let max_scroll = track_info_rows(track, meta, player).len().min(u16::MAX as usize) as u16;
+        match (key.code, action) {
+            (KeyCode::Esc, _) => {
+                ui.track_info = None;
+            }
+            (_, Some(Action::SelectUp)) => {
+                ui.info_scroll = ui.info_scroll.saturating_sub(1);
+            }
+            (_, Some(Action::SelectDown)) => {
+                ui.info_scroll = ui.info_scroll.saturating_add(1).min(max_scroll);
+            }
+            (_, Some(Action::SelectFirst)) => {
+                ui.info_scroll = 0;
+            }
+            _ => {}
+        }
+        return Ok(UiAction::None);
+    }
+
+    // Volume mode: toggle it, then the select up/down keys change the volume
+    // until it is toggled off.
     // Note: terminals don't provide a "v is held" modifier, so this is the most reliable UX.
     if ui.volume_mode {
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
+        match action {
+            Some(Action::SelectUp) => {
                 player.adjust_volume(0.05);
                 return Ok(UiAction::None);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            Some(Action::SelectDown) => {
                 player.adjust_volume(-0.05);
                 return Ok(UiAction::None);
             }
-            KeyCode::Esc | KeyCode::Char('v') => {
+            Some(Action::VolumeMode) => {
+                ui.volume_mode = false;
+                return Ok(UiAction::None);
+            }
+            _ if key.code == KeyCode::Esc => {
                 ui.volume_mode = false;
                 return Ok(UiAction::None);
             }
@@ -422,119 +676,200 @@ pub(crate) fn handle_key(key: KeyEvent, player: &mut Player, ui: &mut UiState) -
         }
     }
 
-    match key.code {
-        // Requested bindings
-        KeyCode::Char('p') => {
-            // 10s back
+    // Esc ends a visual selection, or else clears the marks.
+    if key.code == KeyCode::Esc && action.is_none() && !cancelled_delete {
+        if player.visual.is_some() {
+            player.end_visual();
+        } else {
+            player.marked.clear();
+        }
+        return Ok(UiAction::None);
+    }
+
+    let Some(action) = action else {
+        return Ok(UiAction::None);
+    };
+    match action {
+        Action::SeekBackLong => {
             if is_press {
                 let _ = player.seek_relative(-10_000);
             }
         }
-        KeyCode::Char('n') => {
-            // 10s forward
+        Action::SeekForwardLong => {
             if is_press {
                 let _ = player.seek_relative(10_000);
             }
         }
-        KeyCode::Char('P') => {
+        Action::PrevTrack => {
             let _ = player.prev_track();
         }
-        KeyCode::Char('N') => {
+        Action::NextTrack => {
             let _ = player.next_track();
         }
-        KeyCode::Left => {
+        Action::SeekBack | Action::SeekForward => {
             if is_repeat {
                 return Ok(UiAction::None);
             }
 
             // Ignore continuous holds even if the terminal reports them as Press.
             let cooldown = Duration::from_millis(180);
-            if ui.last_seek_key == Some(KeyCode::Left) && ui.last_seek_at.elapsed() < cooldown {
-                return Ok(UiAction::None);
-            }
-
-            if is_press {
-                ui.last_seek_key = Some(KeyCode::Left);
-                ui.last_seek_at = Instant::now();
-                let _ = player.seek_relative(-5_000);
-            }
-        }
-        KeyCode::Right => {
-            if is_repeat {
-                return Ok(UiAction::None);
-            }
-
-            let cooldown = Duration::from_millis(180);
-            if ui.last_seek_key == Some(KeyCode::Right) && ui.last_seek_at.elapsed() < cooldown {
+            if ui.last_seek_key == Some(action) && ui.last_seek_at.elapsed() < cooldown {
                 return Ok(UiAction::None);
             }
 
             if is_press {
-                ui.last_seek_key = Some(KeyCode::Right);
+                ui.last_seek_key = Some(action);
                 ui.last_seek_at = Instant::now();
-                let _ = player.seek_relative(5_000);
+                let delta = if action == Action::SeekBack { -5_000 } else { 5_000 };
+                let _ = player.seek_relative(delta);
             }
         }
-        KeyCode::Char('v') => {
+        Action::VolumeMode => {
             ui.volume_mode = !ui.volume_mode;
         }
-        KeyCode::Char('r') => {
+        Action::Restart => {
             let _ = player.restart_current();
         }
-        KeyCode::Char('l') => {
+        Action::Loop => {
             let _ = player.toggle_loop_selected();
         }
-        KeyCode::Char('s') => {
+        Action::Shuffle => {
             player.toggle_shuffle();
         }
-        KeyCode::Char('D') => {
+        Action::EditTags => {
+            ui.tag_editor = TagEditor::open(player);
+        }
+        Action::Mark => {
+            player.toggle_mark_selected();
+        }
+        Action::Organize if player.has_tracks() => {
+            ui.organize = Some(player.plan_organize(ConflictPolicy::Skip));
+            ui.organize_scroll = 0;
+            ui.organize_error = None;
+        }
+        Action::LyricsSooner => {
+            player.nudge_lyrics(100);
+        }
+        Action::LyricsLater => {
+            player.nudge_lyrics(-100);
+        }
+        Action::SaveLyricsOffset if player.lyrics_offset != 0 => {
+            let result = player
+                .save_lyrics_offset()
+                .map(|path| {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    format!("saved to {name}")
+                })
+                .map_err(|e| format!("{e:#}"));
+            ui.lyrics_message = Some((result, Instant::now()));
+        }
+        Action::LyricsSync => match LyricsRecorder::open(player, &ui.keys) {
+            Ok(recorder) => ui.lyrics_recorder = Some(recorder),
+            Err(e) => ui.lyrics_message = Some((Err(e), Instant::now())),
+        },
+        Action::LyricsView => {
+            ui.lyrics_view = match ui.lyrics_view {
+                Some(_) => None,
+                None => Some(LyricsView::default()),
+            };
+        }
+        Action::LyricsScrollDown => {
+            player.scroll_lyrics(1);
+        }
+        Action::LyricsScrollUp => {
+            player.scroll_lyrics(-1);
+        }
+        Action::CycleSort => {
+            player.cycle_sort_key();
+        }
+        Action::ReverseSort => {
+            player.toggle_sort_direction();
+        }
+        Action::Delete => {
             if !player.has_tracks() {
                 return Ok(UiAction::None);
             }
 
             let ttl = Duration::from_millis(2500);
-            let idx = player.selected;
-
-            if let Some(confirm) = &ui.delete_confirm {
-                if confirm.index == idx && confirm.started_at.elapsed() <= ttl {
-                    ui.delete_confirm = None;
-                    player.delete_selected()?;
-                } else {
-                    ui.delete_confirm = Some(DeleteConfirm {
-                        index: idx,
-                        started_at: Instant::now(),
-                    });
-                }
+            let targets = player.selection();
+
+            let confirmed = ui
+                .delete_confirm
+                .take()
+                .is_some_and(|c| c.targets == targets && c.started_at.elapsed() <= ttl);
+            if confirmed {
+                let message = match player.delete_tracks(&targets) {
+                    Ok(n) if player.hard_delete => format!("deleted {}", files_count(n)),
+                    Ok(n) => format!(
+                        "moved {} to the trash ({} undoes)",
+                        files_count(n),
+                        ui.keys.label(Action::UndoDelete)
+                    ),
+                    Err(e) => format!("{e:#}"),
+                };
+                ui.command_message = Some((message, Instant::now()));
             } else {
                 ui.delete_confirm = Some(DeleteConfirm {
-                    index: idx,
+                    targets,
                     started_at: Instant::now(),
                 });
             }
         }
 
+        Action::UndoDelete => {
+            let message = match player.undo_delete() {
+                Ok(n) => format!("restored {}", files_count(n)),
+                Err(e) => format!("{e:#}"),
+            };
+            ui.command_message = Some((message, Instant::now()));
+            ui.delete_confirm = None;
+        }
+
         // Nice-to-have navigation
-        KeyCode::Up | KeyCode::Char('k') => {
-            if ui.volume_mode {
-                player.adjust_volume(0.05);
+        Action::SelectUp => {
+            player.select_up();
+            ui.delete_confirm = None;
+        }
+        Action::SelectDown => {
+            player.select_down();
+            ui.delete_confirm = None;
+        }
+        Action::SelectFirst => {
+            player.select_first();
+            ui.delete_confirm = None;
+        }
+        Action::SelectLast => {
+            player.select_last();
+            ui.delete_confirm = None;
+        }
+        Action::VisualMode => {
+            if player.visual.is_some() {
+                player.end_visual();
             } else {
-                player.select_up();
-                ui.delete_confirm = None;
+                player.start_visual();
             }
+            ui.delete_confirm = None;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if ui.volume_mode {
-                player.adjust_volume(-0.05);
+        Action::ExtendUp | Action::ExtendDown => {
+            if player.visual.is_none() {
+                player.start_visual();
+            }
+            if action == Action::ExtendUp {
+                player.select_up();
             } else {
                 player.select_down();
-                ui.delete_confirm = None;
             }
+            ui.delete_confirm = None;
         }
-        KeyCode::Enter => {
+        Action::Enqueue => {
+            let targets = player.selection();
+            player.enqueue(&targets);
+        }
+        Action::PlaySelected => {
             player.play_selected()?;
             ui.delete_confirm = None;
         }
-        KeyCode::Char(' ') => {
+        Action::PlayPause => {
             player.toggle_pause();
         }
         _ => {}
@@ -543,19 +878,42 @@ pub(crate) fn handle_key(key: KeyEvent, player: &mut Player, ui: &mut UiState) -
     Ok(UiAction::None)
 }
 
-fn apply_search_selection(player: &mut Player, query: &str) {
-    let q = query.trim();
-    if q.is_empty() {
-        return;
+fn files_count(n: usize) -> String {
+    if n == 1 {
+        "1 file".to_string()
+    } else {
+        format!("{n} files")
     }
+}
 
-    let q = q.to_ascii_lowercase();
-    if let Some((idx, _)) = player
-        .tracks
-        .iter()
-        .enumerate()
-        .find(|(_, t)| t.display_name.to_ascii_lowercase().contains(&q))
-    {
-        player.selected = idx;
+/// Page size and scroll limit of the help overlay at the current terminal size.
+pub(super) fn help_scroll_limits(ui: &UiState) -> (u16, u16) {
+    let Ok((cols, rows)) = terminal::size() else {
+        return (10, 0);
+    };
+    let area = Rect {
+        x: 0,
+        y: 0,
+        width: cols,
+        height: rows,
+    };
+    let overlay = help_overlay_rect(area);
+    let inner_h = overlay.height.saturating_sub(2) as usize;
+    let inner_w = overlay.width.saturating_sub(2);
+    let lines = help_wrapped_lines(ui, inner_w);
+    let max_scroll = lines.len().saturating_sub(inner_h);
+    (
+        (inner_h.saturating_sub(1).max(1)).min(u16::MAX as usize) as u16,
+        max_scroll.min(u16::MAX as usize) as u16,
+    )
+}
+
+fn apply_filter_query(player: &mut Player, ui: &mut UiState) {
+    match TrackFilter::parse(&ui.filter_query) {
+        Ok(filter) => {
+            ui.filter_error = None;
+            player.set_filter(filter);
+        }
+        Err(e) => ui.filter_error = Some(e),
     }
 }
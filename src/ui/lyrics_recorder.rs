@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{
+    config::{Action, KeyMap},
+    library::TrackKey,
+    lrc::{self, LrcEntry, Lyrics},
+    player::Player,
+};
+
+use super::lyrics_view::ViewKey;
+
+/// Sync mode: stamps plain lyrics line by line while the track plays, then
+/// writes them out as `track.lrc`.
+#[derive(Debug)]
+pub(crate) struct LyricsRecorder {
+    pub(crate) track: TrackKey,
+    pub(crate) lines: Vec<String>,
+    /// Start times of the first `stamps.len()` lines.
+    pub(crate) stamps: Vec<Duration>,
+    pub(crate) message: Option<String>,
+    /// The stamps as they are have been written out.
+    saved: bool,
+    /// Esc or the write key was pressed once and needs confirming.
+    confirm: Option<Confirm>,
+    /// Label of the write key, for the messages.
+    write_key: String,
+}
+
+/// What a second press confirms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirm {
+    Discard,
+    Replace,
+}
+
+impl LyricsRecorder {
+    /// Starts on the playing track, with lyrics from `track.txt` or its tags.
+    pub(crate) fn open(player: &Player, keys: &KeyMap) -> Result<Self, String> {
+        let track = player
+            .current_track()
+            .filter(|_| player.state != crate::player::PlayState::Stopped)
+            .ok_or("start playing the track to sync first")?;
+        // A .lrc file belongs to the whole audio file, not one track of it.
+        if track.cue.is_some() {
+            return Err("CUE tracks can't be synced: they share one audio file".to_string());
+        }
+        let text = lrc::load_plain_text(&track.path)
+            .or_else(|| match &player.lyrics {
+                Some(Lyrics::Unsynced(text)) => Some(text.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                let name = track.path.with_extension("txt");
+                let name = name.file_name().unwrap_or_default().to_string_lossy();
+                format!("no plain lyrics to sync (put them in {name})")
+            })?;
+        let lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Self {
+            track: track.key(),
+            lines,
+            stamps: Vec::new(),
+            message: None,
+            saved: false,
+            confirm: None,
+            write_key: keys.label(Action::SyncWrite),
+        })
+    }
+
+    /// Pausing and seeking back to redo a line keep working.
+    pub(crate) const PASS_THROUGH: &[Action] = &[
+        Action::PlayPause,
+        Action::SeekBack,
+        Action::SeekForward,
+        Action::SeekBackLong,
+        Action::SeekForwardLong,
+        Action::Help,
+    ];
+
+    /// A key, with the action it is bound to.
+    pub(crate) fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        action: Option<Action>,
+        player: &mut Player,
+    ) -> ViewKey {
+        let confirm = self.confirm.take();
+        self.message = None;
+
+        match (key.code, action) {
+            (KeyCode::Enter, _) | (_, Some(Action::SyncStamp)) => self.stamp(player),
+            (KeyCode::Backspace, _) | (_, Some(Action::SyncUndo)) => {
+                self.saved &= self.stamps.pop().is_none();
+            }
+            (_, Some(Action::SyncWrite)) => self.write(player, confirm == Some(Confirm::Replace)),
+            (KeyCode::Esc, _)
+                if self.stamps.is_empty() || self.saved || confirm == Some(Confirm::Discard) =>
+            {
+                return ViewKey::Close
+            }
+            (KeyCode::Esc, _) => {
+                self.confirm = Some(Confirm::Discard);
+                self.message = Some("press Esc again to discard the timings".to_string());
+            }
+            _ => return ViewKey::PassThrough,
+        }
+        ViewKey::Handled
+    }
+
+    fn stamp(&mut self, player: &Player) {
+        if self.stamps.len() >= self.lines.len() {
+            self.message = Some(format!(
+                "all lines are stamped: {} writes the .lrc",
+                self.write_key
+            ));
+            return;
+        }
+        if player.current_track().is_none_or(|t| t.key() != self.track) {
+            self.message = Some("a different track is playing".to_string());
+            return;
+        }
+        // Lines keep their order even after seeking back.
+        let pos = player.position();
+        let last = self.stamps.last().copied().unwrap_or_default();
+        self.stamps.push(pos.max(last));
+        self.saved = false;
+    }
+
+    fn write(&mut self, player: &mut Player, confirmed: bool) {
+        if self.stamps.len() < self.lines.len() {
+            let left = self.lines.len() - self.stamps.len();
+            self.message = Some(format!("{left} lines still need a timestamp"));
+            return;
+        }
+        let path = self.track.0.with_extension("lrc");
+        if path.exists() && !confirmed {
+            self.confirm = Some(Confirm::Replace);
+            self.message = Some(format!(
+                "{} exists: press {} again to replace it",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                self.write_key
+            ));
+            return;
+        }
+
+        let entries: Vec<LrcEntry> = self
+            .lines
+            .iter()
+            .zip(&self.stamps)
+            .map(|(text, &time)| LrcEntry {
+                time,
+                text: text.clone(),
+                words: Vec::new(),
+                end: None,
+            })
+            .collect();
+        let meta = &player.now_meta;
+        let tags: Vec<(&str, String)> = [
+            ("ti", &meta.title),
+            ("ar", &meta.artist),
+            ("al", &meta.album),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.clone()?)))
+        .collect();
+
+        self.message = Some(match lrc::write_lrc(&path, &tags, &entries) {
+            Ok(()) => {
+                self.saved = true;
+                if player
+                    .current_track()
+                    .is_some_and(|t| t.key() == self.track)
+                {
+                    player.reload_lyrics();
+                }
+                format!(
+                    "saved {}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                )
+            }
+            Err(e) => format!("{e:#}"),
+        });
+    }
+}
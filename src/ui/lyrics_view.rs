@@ -0,0 +1,97 @@
+use std::cell::Cell;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{
+    config::Action,
+    lrc::{self, Lyrics},
+    player::Player,
+};
+
+/// The full-screen lyrics mode.
+#[derive(Debug, Default)]
+pub(crate) struct LyricsView {
+    /// Synced line picked with the arrow keys; `None` follows playback.
+    pub(crate) cursor: Option<usize>,
+    /// Row shown at the top, eased toward its target each frame so the view
+    /// glides instead of jumping. `None` until the first frame.
+    pub(crate) top: Cell<Option<f32>>,
+}
+
+pub(crate) enum ViewKey {
+    Handled,
+    Close,
+    /// Not a key of this view: bound actions it lets through work as usual.
+    PassThrough,
+}
+
+impl LyricsView {
+    /// Actions that keep working over the view; the lyrics view action closes it.
+    pub(crate) const PASS_THROUGH: &[Action] = &[
+        Action::PlayPause,
+        Action::SeekBack,
+        Action::SeekForward,
+        Action::SeekBackLong,
+        Action::SeekForwardLong,
+        Action::PrevTrack,
+        Action::NextTrack,
+        Action::LyricsSooner,
+        Action::LyricsLater,
+        Action::SaveLyricsOffset,
+        Action::LyricsSync,
+        Action::LyricsView,
+        Action::Help,
+        Action::TrackInfo,
+        Action::Favorite,
+    ];
+
+    /// A key, with the action it is bound to.
+    pub(crate) fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        action: Option<Action>,
+        player: &mut Player,
+    ) -> ViewKey {
+        let entries = match &player.lyrics {
+            Some(Lyrics::Synced(entries)) => Some(entries),
+            _ => None,
+        };
+        let last = entries.map_or(0, |e| e.len().saturating_sub(1));
+        let from = |view: &Self| {
+            view.cursor
+                .or_else(|| entries.and_then(|e| lrc::active_index(e, player.lyrics_position())))
+        };
+
+        let synced = entries.is_some();
+        match (key.code, action) {
+            (_, Some(Action::Quit)) => return ViewKey::Close,
+            (KeyCode::Esc, _) if self.cursor.is_some() => self.cursor = None,
+            (KeyCode::Esc, _) => return ViewKey::Close,
+            (_, Some(Action::SelectUp)) if synced => {
+                self.cursor = Some(from(self).map_or(0, |i| i.saturating_sub(1)));
+            }
+            (_, Some(Action::SelectDown)) if synced => {
+                self.cursor = Some(from(self).map_or(0, |i| (i + 1).min(last)));
+            }
+            (KeyCode::PageUp, _) if synced => {
+                self.cursor = Some(from(self).map_or(0, |i| i.saturating_sub(10)));
+            }
+            (KeyCode::PageDown, _) if synced => {
+                self.cursor = Some(from(self).map_or(0, |i| (i + 10).min(last)));
+            }
+            (_, Some(Action::SelectFirst)) if synced => self.cursor = Some(0),
+            (_, Some(Action::SelectLast)) if synced => self.cursor = Some(last),
+            (_, Some(Action::SelectUp)) => player.scroll_lyrics(-1),
+            (_, Some(Action::SelectDown)) => player.scroll_lyrics(1),
+            (KeyCode::PageUp, _) => player.scroll_lyrics(-10),
+            (KeyCode::PageDown, _) => player.scroll_lyrics(10),
+            (KeyCode::Enter, _) => {
+                if let Some(i) = self.cursor.take() {
+                    let _ = player.seek_to_lyric(i);
+                }
+            }
+            _ => return ViewKey::PassThrough,
+        }
+        ViewKey::Handled
+    }
+}
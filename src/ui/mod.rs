@@ -1,5 +1,16 @@
+mod art;
+mod command;
+mod history_view;
 mod input;
+mod lyrics_recorder;
+mod lyrics_view;
+mod mouse;
 mod render;
+mod search;
+mod stats_view;
+mod tag_editor;
 
+pub(crate) use art::AlbumArt;
 pub(crate) use input::{handle_key, UiAction, UiState, YtDlStatus};
+pub(crate) use mouse::handle_mouse;
 pub(crate) use render::draw_ui;
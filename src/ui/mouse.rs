@@ -0,0 +1,155 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+use crate::player::Player;
+
+use super::input::{help_scroll_limits, UiState};
+
+/// Two clicks on the same row within this time play it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// Rows moved per scroll wheel step.
+const SCROLL_ROWS: usize = 3;
+const VOLUME_STEP: f32 = 0.05;
+
+/// Where the last frame drew the parts of the main view that take the mouse.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ScreenAreas {
+    pub(crate) title: Rect,
+    /// Rows of the library list (or search results), inside the border.
+    pub(crate) list: Rect,
+    /// Position of the entry on the first row.
+    pub(crate) list_offset: usize,
+    /// The progress bar, inside the border.
+    pub(crate) progress: Rect,
+}
+
+pub(crate) fn handle_mouse(event: MouseEvent, player: &mut Player, ui: &mut UiState) {
+    let pos = Position::new(event.column, event.row);
+    let up = match event.kind {
+        MouseEventKind::ScrollUp => Some(true),
+        MouseEventKind::ScrollDown => Some(false),
+        _ => None,
+    };
+
+    if ui.show_help {
+        if let Some(up) = up {
+            let (_, max_scroll) = help_scroll_limits(ui);
+            let rows = SCROLL_ROWS as u16;
+            ui.help_scroll = if up {
+                ui.help_scroll.saturating_sub(rows)
+            } else {
+                ui.help_scroll.saturating_add(rows)
+            }
+            .min(max_scroll);
+        }
+        return;
+    }
+
+    // Popups and the full-screen views keep to the keyboard.
+    let covered = ui.tag_editor.is_some()
+        || ui.organize.is_some()
+        || ui.show_skipped
+        || ui.track_info.is_some()
+        || ui.history.is_some()
+        || ui.stats.is_some()
+        || ui.lyrics_view.is_some()
+        || ui.lyrics_recorder.is_some();
+    if covered {
+        ui.seek_drag = None;
+        return;
+    }
+
+    let areas = ui.areas.get();
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) if areas.progress.contains(pos) => {
+            ui.seek_drag = seek_ratio(areas.progress, event.column, player);
+        }
+        MouseEventKind::Drag(MouseButton::Left) if ui.seek_drag.is_some() => {
+            ui.seek_drag = seek_ratio(areas.progress, event.column, player);
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            if let (Some(ratio), Some(total)) = (ui.seek_drag.take(), player.total_duration) {
+                let _ = player.seek_to(total.mul_f64(ratio));
+            }
+        }
+        MouseEventKind::Down(MouseButton::Left) if areas.list.contains(pos) => {
+            let row = areas.list_offset + (pos.y - areas.list.y) as usize;
+            click_row(row, player, ui);
+        }
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown if areas.list.contains(pos) => {
+            scroll_list(up == Some(true), player, ui);
+        }
+        MouseEventKind::ScrollUp if areas.title.contains(pos) => {
+            player.adjust_volume(VOLUME_STEP);
+        }
+        MouseEventKind::ScrollDown if areas.title.contains(pos) => {
+            player.adjust_volume(-VOLUME_STEP);
+        }
+        _ => {}
+    }
+}
+
+/// Fraction of the track under `column` of the progress bar, or `None` when
+/// the track length is unknown.
+fn seek_ratio(bar: Rect, column: u16, player: &Player) -> Option<f64> {
+    player.total_duration?;
+    let width = bar.width.max(1);
+    let col = column.clamp(bar.x, bar.x + width - 1) - bar.x;
+    Some(((col as f64 + 0.5) / width as f64).clamp(0.0, 1.0))
+}
+
+/// Selects the track on list row `row`; a second click on it plays it.
+fn click_row(row: usize, player: &mut Player, ui: &mut UiState) {
+    let index = match ui.search.as_mut().filter(|s| !s.query.trim().is_empty()) {
+        Some(search) => {
+            if row >= search.hits.len() {
+                return;
+            }
+            search.pick(row, player);
+            search.current()
+        }
+        None => player.listed_indices().get(row).copied(),
+    };
+    let Some(index) = index else {
+        return;
+    };
+
+    player.selected = index;
+    player.update_visual();
+    ui.delete_confirm = None;
+    let double = ui
+        .last_click
+        .is_some_and(|(i, at)| i == index && at.elapsed() <= DOUBLE_CLICK);
+    if double {
+        ui.last_click = None;
+        let _ = player.play_selected();
+    } else {
+        ui.last_click = Some((index, Instant::now()));
+    }
+}
+
+fn scroll_list(up: bool, player: &mut Player, ui: &mut UiState) {
+    if let Some(search) = ui.search.as_mut().filter(|s| !s.query.trim().is_empty()) {
+        let to = if up {
+            search.cursor.saturating_sub(SCROLL_ROWS)
+        } else {
+            search.cursor + SCROLL_ROWS
+        };
+        search.pick(to.min(search.hits.len().saturating_sub(1)), player);
+        return;
+    }
+    let listed = player.listed_indices();
+    let Some(pos) = listed.iter().position(|&i| i == player.selected) else {
+        return;
+    };
+    let to = if up {
+        pos.saturating_sub(SCROLL_ROWS)
+    } else {
+        (pos + SCROLL_ROWS).min(listed.len() - 1)
+    };
+    player.selected = listed[to];
+    player.update_visual();
+    ui.delete_confirm = None;
+}
@@ -1,20 +1,28 @@
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use ratatui::{
     prelude::*,
     text::{Span, Text},
     widgets::block::Title,
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, BorderType, Borders, Clear, List, ListItem, Paragraph,
+        Wrap,
+    },
 };
 
 use crate::{
-    config::Theme,
-    lrc,
+    config::{Action, KeyMap, Theme, SECTIONS},
+    library::{MoveStatus, OrganizePlan, Ranked, SearchField, Track},
+    lrc::{self, Lyrics},
+    meta::TrackMeta,
     player::{PlayState, Player},
-    util::fmt_time,
+    util::{fmt_span, fmt_time, local_datetime},
 };
 
-use super::input::UiState;
+use super::{
+    command, history_view::HistoryView, input::UiState, lyrics_recorder::LyricsRecorder, lyrics_view::LyricsView, mouse::ScreenAreas,
+    search::Search, stats_view::StatsView, tag_editor::TagEditor,
+};
 
 pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Theme) {
     let area = f.area();
@@ -26,6 +34,17 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
         area,
     );
 
+    if let Some(recorder) = &ui.lyrics_recorder {
+        draw_lyrics_recorder(f, area, player, recorder, &ui.keys, theme);
+        draw_overlays(f, player, ui, theme);
+        return;
+    }
+    if let Some(view) = &ui.lyrics_view {
+        draw_lyrics_view(f, area, player, view, ui, theme);
+        draw_overlays(f, player, ui, theme);
+        return;
+    }
+
     let root = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(3), Constraint::Min(0)])
@@ -64,14 +83,7 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
     let list_rect = left[0];
     let visible_rows = list_rect.height.saturating_sub(2) as usize;
 
-    let active_indices: Vec<usize> = if player.show_favorites {
-        player.tracks.iter().enumerate()
-            .filter(|(_, t)| player.favorites.contains(&t.path))
-            .map(|(i, _)| i)
-            .collect()
-    } else {
-        (0..player.tracks.len()).collect()
-    };
+    let active_indices = player.listed_indices();
 
     let total = active_indices.len();
     let selected_local = if total == 0 {
@@ -110,14 +122,20 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
                 } else {
                     Style::default().fg(theme.text_primary).bg(theme.background)
                 };
+                // Marked for a batch action.
+                let name_style = if player.marked.contains(&t.key()) {
+                    name_style.add_modifier(Modifier::REVERSED)
+                } else {
+                    name_style
+                };
 
-                let fav_prefix = if player.favorites.contains(&t.path) { "♥ " } else { "  " };
+                let fav_prefix = if player.favorites.contains(&t.key()) { "♥ " } else { "  " };
                 let fav_style = Style::default().fg(theme.playing_indicator).bg(theme.background);
 
                 ListItem::new(Line::from(vec![
                     Span::styled(prefix, prefix_style),
                     Span::styled(fav_prefix.to_string(), fav_style),
-                    Span::styled(t.display_name.clone(), name_style),
+                    Span::styled(player.list_name(t), name_style),
                 ]))
             })
             .collect();
@@ -137,7 +155,21 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
                 .border_style(Style::default().fg(theme.library_accent))
                 .style(Style::default().bg(theme.background))
                 .title(Title::from(Line::styled(
-                    if player.show_favorites { "Favorites" } else { "Library" },
+                    format!(
+                        "{}{} • {} {}",
+                        if player.show_favorites { "Favorites" } else { "Library" },
+                        match &player.filter {
+                            Some(filter) => format!(
+                                " • {}/{} • {}",
+                                total,
+                                player.tracks.len(),
+                                filter.query()
+                            ),
+                            None => String::new(),
+                        },
+                        player.sort.key.label(),
+                        if player.sort.descending { "↓" } else { "↑" },
+                    ),
                     Style::default()
                         .fg(theme.library_accent)
                         .bg(theme.background)
@@ -152,33 +184,44 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
         )
         .highlight_symbol("» ");
 
-    f.render_stateful_widget(list, list_rect, &mut state);
+    // A search with a query shows its results in place of the library.
+    let list_offset = match ui.search.as_ref().filter(|s| !s.query.trim().is_empty()) {
+        Some(search) => draw_search_results(f, list_rect, player, search, theme),
+        None => {
+            f.render_stateful_widget(list, list_rect, &mut state);
+            offset
+        }
+    };
 
-    let (box_title, box_border, box_style, box_text) = if ui.youtube_dl_mode {
+    let yt_status = {
         use super::input::YtDlStatus;
 
-        let status_line = {
-            let status = ui.youtube_dl_status.lock().unwrap();
-            match &*status {
-                YtDlStatus::Idle => None,
-                YtDlStatus::Downloading(url) => {
-                    let short = if url.len() > 40 { &url[..40] } else { url.as_str() };
-                    Some((format!("⏳ Downloading {short}…"), theme.title_accent))
-                }
-                YtDlStatus::Done(msg, _) => Some((msg.clone(), theme.library_accent)),
-                YtDlStatus::Error(msg) => Some((msg.clone(), theme.error)),
+        let status = ui.youtube_dl_status.lock().unwrap();
+        match &*status {
+            YtDlStatus::Idle => None,
+            YtDlStatus::Downloading(url) => {
+                let short = if url.len() > 40 { &url[..40] } else { url.as_str() };
+                Some((format!("⏳ Downloading {short}…"), theme.title_accent))
             }
-        };
+            YtDlStatus::Done(msg, _) => Some((msg.clone(), theme.library_accent)),
+            YtDlStatus::Error(msg) => Some((msg.clone(), theme.error)),
+        }
+    };
+    let message = ui
+        .command_message
+        .as_ref()
+        .filter(|(_, at)| at.elapsed() < command::MESSAGE_TTL)
+        .map(|(msg, _)| msg.clone());
 
-        let input = if ui.youtube_dl_url.is_empty() {
-            "Paste or type a YouTube URL…".to_string()
-        } else {
-            ui.youtube_dl_url.clone()
-        };
+    let (box_title, box_border, box_style, box_text) = if let Some(prompt) = &ui.command {
+        let input = format!(":{}▏", prompt.input);
 
-        let text = if let Some((msg, color)) = status_line {
+        let text = if let Some(err) = &prompt.error {
             Text::from(vec![
-                Line::styled(msg, Style::default().fg(color).bg(theme.background)),
+                Line::styled(
+                    err.clone(),
+                    Style::default().fg(theme.error).bg(theme.background),
+                ),
                 Line::raw(input),
             ])
         } else {
@@ -186,22 +229,22 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
         };
 
         (
-            "YouTube Download",
-            theme.now_accent,
+            "Command",
+            theme.move_accent,
             Style::default()
                 .fg(theme.text_primary)
                 .bg(theme.background)
                 .add_modifier(Modifier::BOLD),
             text,
         )
-    } else if ui.move_mode {
-        let input = if ui.move_query.is_empty() {
-            "Type a timestamp (e.g. 1:30)".to_string()
+    } else if ui.filter_mode {
+        let input = if ui.filter_query.is_empty() {
+            "artist:foo album:\"bar\" dur:>5m fav:yes".to_string()
         } else {
-            ui.move_query.clone()
+            format!("{}▏", ui.filter_query)
         };
 
-        let text = if let Some(err) = &ui.move_error {
+        let text = if let Some(err) = &ui.filter_error {
             Text::from(vec![
                 Line::styled(
                     err.clone(),
@@ -214,26 +257,39 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
         };
 
         (
-            "Move",
-            theme.move_accent,
+            "Filter",
+            theme.library_accent,
             Style::default()
                 .fg(theme.text_primary)
                 .bg(theme.background)
                 .add_modifier(Modifier::BOLD),
             text,
         )
+    } else if let Some((msg, color)) = yt_status {
+        (
+            "YouTube Download",
+            theme.now_accent,
+            Style::default().fg(color).bg(theme.background),
+            Text::from(msg),
+        )
+    } else if let Some(msg) = message.filter(|_| ui.search.is_none()) {
+        (
+            "Command",
+            theme.move_accent,
+            Style::default().fg(theme.text_primary).bg(theme.background),
+            Text::from(msg),
+        )
     } else {
-        let text = if ui.search_mode {
-            if ui.search_query.is_empty() {
-                Text::from("Type to search…".to_string())
-            } else {
-                Text::from(ui.search_query.clone())
+        let text = match &ui.search {
+            Some(search) if search.query.is_empty() => {
+                Text::from("Name, title, artist, album or lyrics…".to_string())
             }
-        } else {
-            Text::from("Press S to search".to_string())
+            Some(search) if search.editing => Text::from(format!("{}▏", search.query)),
+            Some(search) => Text::from(search.query.clone()),
+            None => Text::from(format!("Press {} to search", ui.keys.label(Action::Search))),
         };
 
-        let style = if ui.search_mode {
+        let style = if ui.search.as_ref().is_some_and(|s| s.editing) {
             Style::default()
                 .fg(theme.text_primary)
                 .bg(theme.background)
@@ -265,13 +321,17 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
 
     f.render_widget(input_widget, left[1]);
 
-    // Now playing
+    // Now playing. Plain lyrics get a taller panel to read them in.
+    let lyrics_height = match player.lyrics {
+        Some(Lyrics::Unsynced(_)) => 12,
+        _ => 5,
+    };
     let right = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(6),
             Constraint::Length(3),
-            Constraint::Length(5),
+            Constraint::Length(lyrics_height),
             Constraint::Min(0),
         ])
         .split(mid[1]);
@@ -296,7 +356,14 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
         );
     f.render_widget(now_widget, right[0]);
 
-    let (ratio, label) = progress(player);
+    let (ratio, label) = match (ui.seek_drag, player.total_duration) {
+        // Preview where dragging on the bar will seek to.
+        (Some(ratio), Some(total)) => (
+            ratio,
+            format!("→ {} / {}", fmt_time(total.mul_f64(ratio)), fmt_time(total)),
+        ),
+        _ => progress(player),
+    };
 
     // Render a titled block and manually draw the progress fill so we can
     // precisely control which cells are filled and which are empty. This
@@ -349,9 +416,37 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
 
     draw_progress_label(f, right[1], ratio, &label, theme);
 
-    draw_lyrics(f, right[2], player, theme);
+    ui.areas.set(ScreenAreas {
+        title: root[0],
+        list: list_rect.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        }),
+        list_offset,
+        progress: inner,
+    });
+
+    draw_lyrics(f, right[2], player, ui, theme);
 
     let hints = hints_lines(player, ui, theme);
+
+    // Cover art takes the space above the hints, which shrink to fit their text.
+    let hints_rect = if ui.art.has_image() {
+        let inner_width = right[3].width.saturating_sub(2).max(1) as usize;
+        let hint_rows: usize = hints
+            .iter()
+            .map(|l| l.width().div_ceil(inner_width).max(1))
+            .sum();
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(hint_rows as u16 + 2)])
+            .split(right[3]);
+        draw_cover(f, split[0], ui, theme);
+        split[1]
+    } else {
+        right[3]
+    };
+
     let help_widget = Paragraph::new(Text::from(hints))
         .wrap(Wrap { trim: true })
         .style(Style::default().fg(theme.text_primary).bg(theme.background))
@@ -369,13 +464,166 @@ pub(crate) fn draw_ui(f: &mut Frame, player: &Player, ui: &UiState, theme: &Them
                         .add_modifier(Modifier::BOLD),
                 ))),
         );
-    f.render_widget(help_widget, right[3]);
+    f.render_widget(help_widget, hints_rect);
+
+    draw_overlays(f, player, ui, theme);
+}
+
+fn draw_overlays(f: &mut Frame, player: &Player, ui: &UiState, theme: &Theme) {
+    if ui.show_skipped {
+        draw_skipped_overlay(f, player, ui, theme);
+    }
+
+    if let Some((track, meta)) = &ui.track_info {
+        draw_track_info_overlay(f, player, track, meta, ui, theme);
+    }
+
+    if let Some(view) = &ui.history {
+        draw_history_overlay(f, player, view, ui, theme);
+    }
+
+    if let Some(view) = &ui.stats {
+        draw_stats_overlay(f, view, ui, theme);
+    }
+
+    if let Some(editor) = &ui.tag_editor {
+        draw_tag_editor(f, editor, &ui.keys, theme);
+    }
+
+    if let Some(plan) = &ui.organize {
+        draw_organize_overlay(f, plan, ui, theme);
+    }
 
     if ui.show_help {
         draw_help_overlay(f, player, ui, theme);
     }
 }
 
+fn draw_cover(f: &mut Frame, area: Rect, ui: &UiState, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.now_accent))
+        .style(Style::default().bg(theme.background))
+        .title(Title::from(Line::styled(
+            "Cover",
+            Style::default()
+                .fg(theme.now_accent)
+                .bg(theme.background)
+                .add_modifier(Modifier::BOLD),
+        )));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // Terminal graphics would sit on top of any popup, so they step aside.
+    let popup_open = ui.show_help
+        || ui.show_skipped
+        || ui.track_info.is_some()
+        || ui.history.is_some()
+        || ui.stats.is_some()
+        || ui.tag_editor.is_some()
+        || ui.organize.is_some();
+    ui.art.render(f, inner, theme.background, popup_open);
+}
+
+/// The ranked results of a search, with the matched characters highlighted.
+/// Draws the ranked search results; returns the position of the first one shown.
+fn draw_search_results(
+    f: &mut Frame,
+    area: Rect,
+    player: &Player,
+    search: &Search,
+    theme: &Theme,
+) -> usize {
+    let visible_rows = area.height.saturating_sub(2) as usize;
+    let total = search.hits.len();
+    let max_offset = total.saturating_sub(visible_rows);
+    let offset = search
+        .cursor
+        .saturating_sub(visible_rows.saturating_sub(1) / 2)
+        .min(max_offset);
+
+    let base = Style::default().fg(theme.text_primary).bg(theme.background);
+    let matched = Style::default()
+        .fg(theme.search_accent)
+        .bg(theme.background)
+        .add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(theme.text_muted).bg(theme.background);
+
+    let items: Vec<ListItem> = search
+        .hits
+        .iter()
+        .skip(offset)
+        .take(visible_rows)
+        .filter_map(|hit| {
+            let t = player.tracks.get(hit.index)?;
+            let prefix = if hit.index == player.current { "▶ " } else { "  " };
+            let mut spans = vec![Span::styled(
+                prefix,
+                Style::default().fg(theme.playing_indicator).bg(theme.background),
+            )];
+            spans.extend(highlighted(&player.list_name(t), &hit.name_matches, base, matched));
+            if let Some((field, text, positions)) = &hit.detail {
+                let lead = match field {
+                    SearchField::Lyrics => "  ♪ ".to_string(),
+                    field => format!("  {}: ", field.label()),
+                };
+                spans.push(Span::styled(lead, dim));
+                spans.extend(highlighted(text, positions, dim, matched));
+            }
+            Some(ListItem::new(Line::from(spans)))
+        })
+        .collect();
+
+    let mut state = ratatui::widgets::ListState::default();
+    state.select((total > 0).then(|| search.cursor - offset));
+
+    let status = if player.search_loading() { " • loading…" } else { "" };
+    let list = List::new(items)
+        .style(base)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.search_accent))
+                .style(Style::default().bg(theme.background))
+                .title(Title::from(Line::styled(
+                    match total {
+                        0 => format!("No matches{status}"),
+                        1 => format!("1 match{status}"),
+                        n => format!("{} of {n} matches{status}", search.cursor + 1),
+                    },
+                    Style::default()
+                        .fg(theme.search_accent)
+                        .bg(theme.background)
+                        .add_modifier(Modifier::BOLD),
+                ))),
+        )
+        .highlight_style(Style::default().bg(theme.background).add_modifier(Modifier::REVERSED))
+        .highlight_symbol("» ");
+    f.render_stateful_widget(list, area, &mut state);
+    offset
+}
+
+/// Splits `text` into spans, styling the characters at `positions` with `hl`.
+fn highlighted(text: &str, positions: &[usize], style: Style, hl: Style) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_hl = false;
+    for (i, ch) in text.chars().enumerate() {
+        let is_hl = positions.binary_search(&i).is_ok();
+        if is_hl != run_hl && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), if run_hl { hl } else { style }));
+        }
+        run_hl = is_hl;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_hl { hl } else { style }));
+    }
+    spans
+}
+
 fn title_line(player: &Player, ui: &UiState) -> String {
     let state = match player.state {
         PlayState::Stopped => "stopped",
@@ -384,13 +632,36 @@ fn title_line(player: &Player, ui: &UiState) -> String {
     };
 
     let vol = format!("{:.0}%", player.volume.display() * 100.0);
-    let chord = if ui.volume_mode { " (v: volume mode)" } else { "" };
+    let chord = if ui.volume_mode {
+        format!(" ({}: volume mode)", ui.keys.label(Action::VolumeMode))
+    } else {
+        String::new()
+    };
 
     let lp = if player.loop_current { " • Loop" } else { "" };
     let sh = if player.shuffle { " • Shuffle" } else { "" };
     let backend = player.volume.label();
     let view = if player.show_favorites { " • Fav View" } else { "" };
-    format!("State: {state} • Volume: {vol} [{backend}]{chord}{lp}{sh}{view}")
+    let marked = match (player.marked.len(), player.visual.is_some()) {
+        (n, true) => format!(" • Visual: {n} marked"),
+        (0, false) => String::new(),
+        (n, false) => format!(" • {n} marked (Esc clears)"),
+    };
+    let queued = if player.queue.is_empty() {
+        String::new()
+    } else {
+        format!(" • {} queued", player.queue.len())
+    };
+    let skipped = if player.skipped.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " • {} skipped ({})",
+            player.skipped.len(),
+            ui.keys.label(Action::SkippedFiles)
+        )
+    };
+    format!("State: {state} • Volume: {vol} [{backend}]{chord}{lp}{sh}{view}{marked}{queued}{skipped}")
 }
 
 fn now_playing_lines(player: &Player, _ui: &UiState, theme: &Theme) -> Vec<Line<'static>> {
@@ -417,6 +688,10 @@ fn now_playing_lines(player: &Player, _ui: &UiState, theme: &Theme) -> Vec<Line<
         .as_deref()
         .filter(|s| !s.trim().is_empty())
         .unwrap_or("-");
+    let album = match player.now_meta.year() {
+        Some(year) => format!("{album} ({year})"),
+        None => album.to_string(),
+    };
 
     let key = key_style(theme);
     let title_style = Style::default()
@@ -456,6 +731,15 @@ fn key_style(theme: &Theme) -> Style {
         .add_modifier(Modifier::BOLD)
 }
 
+/// The keys that move up and down a list, for hints: `↑/↓`.
+fn up_down(keys: &KeyMap) -> String {
+    format!(
+        "{}/{}",
+        keys.label(Action::SelectUp),
+        keys.label(Action::SelectDown)
+    )
+}
+
 fn heading_style(theme: &Theme) -> Style {
     Style::default()
         .fg(theme.title_accent)
@@ -466,52 +750,98 @@ fn heading_style(theme: &Theme) -> Style {
 fn hints_lines(player: &Player, ui: &UiState, theme: &Theme) -> Vec<Line<'static>> {
     let key = key_style(theme);
 
-    if ui.youtube_dl_mode {
+    if ui.command.is_some() {
         return vec![Line::from(vec![
+            Span::styled("Tab", key),
+            Span::raw(" complete • "),
+            Span::styled("↑/↓", key),
+            Span::raw(" history • "),
             Span::styled("Enter", key),
-            Span::raw(" download • "),
+            Span::raw(" run • "),
             Span::styled("Esc", key),
-            Span::raw(" cancel • "),
-            Span::styled("Backspace", key),
-            Span::raw(" delete"),
+            Span::raw(" cancel"),
         ])];
     }
 
-    if ui.search_mode {
+    if let Some(search) = &ui.search {
+        if search.editing {
+            return vec![Line::from(vec![
+                Span::styled("Enter", key),
+                Span::raw(" play • "),
+                Span::styled("↑/↓", key),
+                Span::raw(" pick • "),
+                Span::styled("Ctrl+A", key),
+                Span::raw(" play all • "),
+                Span::styled("Ctrl+E", key),
+                Span::raw(" enqueue all • "),
+                Span::styled("Esc", key),
+                Span::raw(" cancel"),
+            ])];
+        }
+        let keys = &ui.keys;
         return vec![Line::from(vec![
+            Span::styled(
+                format!(
+                    "{}/{}",
+                    keys.label(Action::SearchNext),
+                    keys.label(Action::SearchPrev)
+                ),
+                key,
+            ),
+            Span::raw(" next/prev match • "),
             Span::styled("Enter", key),
             Span::raw(" play • "),
+            Span::styled(keys.label(Action::SearchPlayAll), key),
+            Span::raw(" play all • "),
+            Span::styled(keys.label(Action::SearchEnqueueAll), key),
+            Span::raw(" enqueue all • "),
+            Span::styled(keys.label(Action::SearchEdit), key),
+            Span::raw(" edit query • "),
             Span::styled("Esc", key),
-            Span::raw(" cancel • "),
-            Span::styled("Backspace", key),
-            Span::raw(" delete"),
+            Span::raw(" close"),
         ])];
     }
 
-    if ui.move_mode {
+    if ui.filter_mode {
         return vec![Line::from(vec![
             Span::styled("Enter", key),
-            Span::raw(" jump • "),
+            Span::raw(" keep filter • "),
             Span::styled("Esc", key),
-            Span::raw(" cancel • "),
-            Span::styled("Backspace", key),
-            Span::raw(" delete"),
+            Span::raw(" undo changes • "),
+            Span::raw("fields: title artist album albumartist genre name path ext dur year plays skips played rating fav"),
         ])];
     }
 
     if let Some(confirm) = &ui.delete_confirm {
         if confirm.started_at.elapsed() <= Duration::from_millis(2500) {
-            let name = player
-                .tracks
-                .get(confirm.index)
-                .map(|t| t.display_name.as_str())
-                .unwrap_or("(track)");
+            // CUE tracks share a file: say so when others would go with it.
+            let files = player.files_to_delete(&confirm.targets);
+            let tracks: usize = files.iter().map(|(_, n)| n).sum();
+            let name = match (confirm.targets.as_slice(), files.as_slice()) {
+                (_, [(path, n)]) if tracks > confirm.targets.len() => format!(
+                    "{} and its {n} tracks",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                _ if tracks > confirm.targets.len() => {
+                    format!("{} files with {tracks} tracks", files.len())
+                }
+                ([one], _) => player
+                    .tracks
+                    .get(*one)
+                    .map(|t| player.list_name(t))
+                    .unwrap_or_else(|| "(track)".to_string()),
+                (many, _) => format!("{} marked tracks", many.len()),
+            };
             return vec![Line::from(vec![
                 Span::raw("Press "),
-                Span::styled("D", key),
-                Span::raw(" again to delete: "),
+                Span::styled(ui.keys.label(Action::Delete), key),
+                Span::raw(if player.hard_delete {
+                    " again to delete for good: "
+                } else {
+                    " again to move to the trash: "
+                }),
                 Span::styled(
-                    name.to_string(),
+                    name,
                     Style::default()
                         .fg(theme.song_title_accent)
                         .bg(theme.background),
@@ -523,6 +853,35 @@ fn hints_lines(player: &Player, ui: &UiState, theme: &Theme) -> Vec<Line<'static
         }
     }
 
+    if player.visual.is_some() {
+        return vec![Line::from(vec![
+            Span::raw("Visual: "),
+            Span::styled(
+                format!(
+                    "{}/{}",
+                    ui.keys.label(Action::SelectUp),
+                    ui.keys.label(Action::SelectDown)
+                ),
+                key,
+            ),
+            Span::raw(" extend • "),
+            Span::styled(ui.keys.label(Action::Favorite), key),
+            Span::raw(" favorite • "),
+            Span::styled(ui.keys.label(Action::Enqueue), key),
+            Span::raw(" enqueue • "),
+            Span::styled(ui.keys.label(Action::AddToPlaylist), key),
+            Span::raw(" playlist • "),
+            Span::styled(ui.keys.label(Action::MoveToFolder), key),
+            Span::raw(" move • "),
+            Span::styled(ui.keys.label(Action::Delete), key),
+            Span::raw(" delete • "),
+            Span::styled(ui.keys.label(Action::VisualMode), key),
+            Span::raw("/"),
+            Span::styled("Esc", key),
+            Span::raw(" done"),
+        ])];
+    }
+
     if ui.volume_mode {
         let what = if player.volume.is_system() {
             "system volume"
@@ -531,9 +890,16 @@ fn hints_lines(player: &Player, ui: &UiState, theme: &Theme) -> Vec<Line<'static
         };
         return vec![Line::from(vec![
             Span::raw("Volume mode: "),
-            Span::styled("↑/↓", key),
+            Span::styled(
+                format!(
+                    "{}/{}",
+                    ui.keys.label(Action::SelectUp),
+                    ui.keys.label(Action::SelectDown)
+                ),
+                key,
+            ),
             Span::raw(format!(" change {what} • ")),
-            Span::styled("v", key),
+            Span::styled(ui.keys.label(Action::VolumeMode), key),
             Span::raw("/"),
             Span::styled("Esc", key),
             Span::raw(" exit"),
@@ -542,19 +908,19 @@ fn hints_lines(player: &Player, ui: &UiState, theme: &Theme) -> Vec<Line<'static
 
     vec![Line::from(vec![
         Span::raw("Press "),
-        Span::styled("h", key),
+        Span::styled(ui.keys.label(Action::Help), key),
         Span::raw(" for cheatsheet • "),
         Span::styled("F12", key),
         Span::raw(" hide/unhide • "),
-        Span::styled("v", key),
+        Span::styled(ui.keys.label(Action::VolumeMode), key),
         Span::raw(" volume mode • "),
-        Span::styled("S", key),
+        Span::styled(ui.keys.label(Action::Search), key),
         Span::raw(" search • "),
-        Span::styled("m", key),
+        Span::styled(ui.keys.label(Action::MoveToTime), key),
         Span::raw(" move • "),
-        Span::styled("y", key),
+        Span::styled(ui.keys.label(Action::YoutubeDownload), key),
         Span::raw(" youtube dl • "),
-        Span::styled("D", key),
+        Span::styled(ui.keys.label(Action::Delete), key),
         Span::raw(" delete"),
     ])]
 }
@@ -635,19 +1001,41 @@ fn draw_progress_label(f: &mut Frame, area: Rect, ratio: f64, label: &str, theme
     }
 }
 
-fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, theme: &Theme) {
+fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, ui: &UiState, theme: &Theme) {
+    let mut title = vec![Span::styled(
+        "Lyrics",
+        Style::default()
+            .fg(theme.now_accent)
+            .bg(theme.background)
+            .add_modifier(Modifier::BOLD),
+    )];
+    let note = |text: String, color: Color| {
+        Span::styled(format!(" · {text}"), Style::default().fg(color).bg(theme.background))
+    };
+    if player.lyrics_offset != 0 {
+        title.push(note(
+            format!(
+                "{:+}ms ({} to save)",
+                player.lyrics_offset,
+                ui.keys.label(Action::SaveLyricsOffset)
+            ),
+            theme.text_muted,
+        ));
+    }
+    match &ui.lyrics_message {
+        Some((msg, at)) if at.elapsed() < Duration::from_secs(3) => title.push(match msg {
+            Ok(msg) => note(msg.clone(), theme.library_accent),
+            Err(e) => note(e.clone(), theme.error),
+        }),
+        _ => {}
+    }
+
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(theme.now_accent))
         .style(Style::default().bg(theme.background))
-        .title(Title::from(Line::styled(
-            "Lyrics",
-            Style::default()
-                .fg(theme.now_accent)
-                .bg(theme.background)
-                .add_modifier(Modifier::BOLD),
-        )));
+        .title(Title::from(Line::from(title)));
 
     let inner = area.inner(Margin {
         vertical: 1,
@@ -664,6 +1052,10 @@ fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, theme: &Theme) {
         .fg(theme.song_title_accent)
         .bg(theme.background)
         .add_modifier(Modifier::BOLD);
+    let unsung_style = Style::default()
+        .fg(theme.text_primary)
+        .bg(theme.background)
+        .add_modifier(Modifier::BOLD);
 
     let max_w = inner.width as usize;
     let truncate = |s: &str| -> String {
@@ -692,10 +1084,11 @@ fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, theme: &Theme) {
         result
     };
 
-    let text: Vec<Line<'static>> = match &player.lrc {
-        Some(entries) if !entries.is_empty() => {
-            let pos = player.position();
-            let (prev, curr, next) = match lrc::active_index(entries, pos) {
+    let text: Vec<Line<'static>> = match &player.lyrics {
+        Some(Lyrics::Synced(entries)) if !entries.is_empty() => {
+            let pos = player.lyrics_position();
+            let active = lrc::active_index(entries, pos);
+            let (prev, curr, next) = match active {
                 None => (
                     String::new(),
                     String::new(),
@@ -704,6 +1097,15 @@ fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, theme: &Theme) {
                         .map(|e| e.text.clone())
                         .unwrap_or_default(),
                 ),
+                // A subtitle cue that has ended: nothing is sung until the next one.
+                Some(i) if !entries[i].showing(pos) => (
+                    entries[i].text.clone(),
+                    String::new(),
+                    entries
+                        .get(i + 1)
+                        .map(|e| e.text.clone())
+                        .unwrap_or_default(),
+                ),
                 Some(i) => (
                     if i > 0 {
                         entries[i - 1].text.clone()
@@ -718,16 +1120,43 @@ fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, theme: &Theme) {
                     },
                 ),
             };
+            let curr = truncate(&curr);
+            let curr = match active.map(|i| (i, &entries[i])) {
+                Some((_, entry)) if !entry.showing(pos) => Line::default(),
+                // Karaoke: words already sung in the accent colour, the rest plain.
+                Some((i, entry)) if !entry.words.is_empty() => {
+                    let line_end = entries.get(i + 1).map(|e| e.time);
+                    let sung = entry.sung_chars(pos, line_end);
+                    let split = curr
+                        .char_indices()
+                        .nth(sung)
+                        .map_or(curr.len(), |(at, _)| at);
+                    let (done, todo) = curr.split_at(split);
+                    Line::from(vec![
+                        Span::styled(done.to_string(), active_style),
+                        Span::styled(todo.to_string(), unsung_style),
+                    ])
+                }
+                _ => Line::styled(curr, active_style),
+            };
             vec![
                 Line::styled(truncate(&prev), muted_style),
-                Line::styled(truncate(&curr), active_style),
+                curr,
                 Line::styled(truncate(&next), muted_style),
             ]
         }
+        Some(Lyrics::Unsynced(text)) => {
+            let p = Paragraph::new(text.clone())
+                .style(Style::default().fg(theme.text_primary).bg(theme.background))
+                .wrap(Wrap { trim: true })
+                .scroll((player.lyrics_scroll, 0));
+            f.render_widget(p, inner);
+            return;
+        }
         _ => vec![
             Line::styled(String::new(), muted_style),
             Line::styled(
-                "No synced lyrics (.lrc file not found)".to_string(),
+                "No lyrics (no .lrc file or lyrics tag)".to_string(),
                 Style::default().fg(theme.text_muted).bg(theme.background),
             ),
             Line::styled(String::new(), muted_style),
@@ -738,52 +1167,357 @@ fn draw_lyrics(f: &mut Frame, area: Rect, player: &Player, theme: &Theme) {
     f.render_widget(p, inner);
 }
 
+/// Splits `text` into rows at most `width` columns wide, breaking at spaces
+/// where possible. The rows concatenate back to `text`; a space at a break
+/// stays at the end of its row.
+fn wrap_rows(text: &str, width: usize) -> Vec<String> {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    let width = width.max(1);
+    let mut rows = vec![String::new()];
+    let mut row_w = 0;
+    for token in text.split_inclusive(' ') {
+        let token_w = token.trim_end().width();
+        if row_w > 0 && row_w + token_w > width {
+            rows.push(String::new());
+            row_w = 0;
+        }
+        for ch in token.chars() {
+            let ch_w = ch.width().unwrap_or(0);
+            // Words longer than a row are broken anywhere.
+            if row_w > 0 && row_w + ch_w > width && ch != ' ' {
+                rows.push(String::new());
+                row_w = 0;
+            }
+            if let Some(row) = rows.last_mut() {
+                row.push(ch);
+            }
+            row_w += ch_w;
+        }
+    }
+    rows
+}
+
+fn draw_lyrics_view(
+    f: &mut Frame,
+    area: Rect,
+    player: &Player,
+    view: &LyricsView,
+    ui: &UiState,
+    theme: &Theme,
+) {
+    let nonempty = |s: &Option<String>| s.clone().filter(|s| !s.trim().is_empty());
+    let name = nonempty(&player.now_meta.title)
+        .or_else(|| player.current_track().map(|t| t.display_name.clone()));
+    let title = match (name, nonempty(&player.now_meta.artist)) {
+        (Some(name), Some(artist)) => format!("{name} — {artist}"),
+        (Some(name), None) => name,
+        (None, _) => "Lyrics".to_string(),
+    };
+    let back = format!("Esc/{} back", ui.keys.label(Action::LyricsView));
+    let moves = up_down(&ui.keys);
+    let mut hint = match player.lyrics {
+        Some(Lyrics::Synced(_)) => format!("{moves} pick a line · Enter seek · {back}"),
+        Some(Lyrics::Unsynced(_)) => format!("{moves} scroll · {back}"),
+        None => back,
+    };
+    if player.lyrics_offset != 0 {
+        hint = format!(
+            "{:+}ms ({} to save) · {hint}",
+            player.lyrics_offset,
+            ui.keys.label(Action::SaveLyricsOffset)
+        );
+    }
+    if let Some((msg, at)) = &ui.lyrics_message {
+        if at.elapsed() < Duration::from_secs(3) {
+            hint = match msg {
+                Ok(msg) | Err(msg) => format!("{msg} · {hint}"),
+            };
+        }
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.now_accent))
+        .style(Style::default().bg(theme.background))
+        .title_top(Line::styled(
+            format!(" {title} "),
+            Style::default()
+                .fg(theme.now_accent)
+                .bg(theme.background)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(
+            Line::styled(hint, Style::default().fg(theme.text_muted).bg(theme.background))
+                .right_aligned(),
+        );
+    let inner = block.inner(area).inner(Margin {
+        vertical: 0,
+        horizontal: 1,
+    });
+    f.render_widget(block, area);
+    if inner.width == 0 || inner.height == 0 {
+        return;
+    }
+    let width = inner.width as usize;
+
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let plain = Style::default().fg(theme.text_primary).bg(theme.background);
+    let sung = Style::default()
+        .fg(theme.song_title_accent)
+        .bg(theme.background)
+        .add_modifier(Modifier::BOLD);
+    let unsung = plain.add_modifier(Modifier::BOLD);
+
+    let (rows, focus): (Vec<Line<'static>>, Option<usize>) = match &player.lyrics {
+        Some(Lyrics::Synced(entries)) => {
+            let pos = player.lyrics_position();
+            let current = lrc::active_index(entries, pos);
+            // Highlight nothing between subtitle cues, but keep the place.
+            let active = current.filter(|&i| entries[i].showing(pos));
+            let mut rows = Vec::new();
+            let mut focus = None;
+            for (i, entry) in entries.iter().enumerate() {
+                let first_row = rows.len();
+                let wrapped = wrap_rows(&entry.text, width);
+                let row_count = wrapped.len();
+                let mut sung_left = if active == Some(i) {
+                    let line_end = entries.get(i + 1).map(|e| e.time);
+                    if entry.words.is_empty() {
+                        usize::MAX
+                    } else {
+                        entry.sung_chars(pos, line_end)
+                    }
+                } else {
+                    0
+                };
+                for row in wrapped {
+                    let chars = row.chars().count();
+                    let shown = row.trim_end();
+                    let mut line = if active == Some(i) {
+                        let split = shown
+                            .char_indices()
+                            .nth(sung_left)
+                            .map_or(shown.len(), |(at, _)| at);
+                        let (done, todo) = shown.split_at(split);
+                        Line::from(vec![
+                            Span::styled(done.to_string(), sung),
+                            Span::styled(todo.to_string(), unsung),
+                        ])
+                    } else if current.is_some_and(|c| i <= c) {
+                        Line::styled(shown.to_string(), muted)
+                    } else {
+                        Line::styled(shown.to_string(), plain)
+                    };
+                    if view.cursor == Some(i) {
+                        line = line.patch_style(Modifier::REVERSED);
+                    }
+                    rows.push(line.centered());
+                    sung_left = sung_left.saturating_sub(chars);
+                }
+                if view.cursor.or(current) == Some(i) {
+                    focus = Some(first_row + row_count / 2);
+                }
+            }
+            // Before the first line, keep the start of the lyrics centred.
+            (rows, Some(focus.unwrap_or(0)))
+        }
+        Some(Lyrics::Unsynced(text)) => {
+            let rows = text
+                .lines()
+                .flat_map(|l| wrap_rows(l, width))
+                .map(|row| Line::styled(row.trim_end().to_string(), plain).centered())
+                .collect();
+            (rows, None)
+        }
+        None => (
+            vec![Line::styled("No lyrics for this track", muted).centered()],
+            Some(0),
+        ),
+    };
+
+    // Put the focused line in the middle and glide there; plain lyrics scroll by hand.
+    let top = match focus {
+        Some(row) => {
+            let target = row as f32 - (inner.height / 2) as f32;
+            let eased = match view.top.get() {
+                Some(top) if (target - top).abs() > 0.5 => top + (target - top) * 0.35,
+                _ => target,
+            };
+            view.top.set(Some(eased));
+            eased.round() as isize
+        }
+        None => player.lyrics_scroll as isize,
+    };
+
+    for y in 0..inner.height {
+        let index = top + y as isize;
+        if index < 0 {
+            continue;
+        }
+        let Some(row) = rows.get(index as usize) else {
+            break;
+        };
+        let rect = Rect {
+            y: inner.y + y,
+            height: 1,
+            ..inner
+        };
+        f.render_widget(Paragraph::new(row.clone()), rect);
+    }
+}
+
+fn draw_lyrics_recorder(
+    f: &mut Frame,
+    area: Rect,
+    player: &Player,
+    recorder: &LyricsRecorder,
+    keys: &KeyMap,
+    theme: &Theme,
+) {
+    let next = recorder.stamps.len();
+    let title = format!(
+        " Sync lyrics · {} · {next}/{} stamped ",
+        fmt_time(player.position()),
+        recorder.lines.len()
+    );
+    let hint = recorder.message.clone().unwrap_or_else(|| {
+        format!(
+            "{} stamp line · {} undo · {}/{} seek · {} pause · {} write .lrc · Esc close",
+            keys.label(Action::SyncStamp),
+            keys.label(Action::SyncUndo),
+            keys.label(Action::SeekBackLong),
+            keys.label(Action::SeekForwardLong),
+            keys.label(Action::PlayPause),
+            keys.label(Action::SyncWrite)
+        )
+    });
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.now_accent))
+        .style(Style::default().bg(theme.background))
+        .title_top(Line::styled(
+            title,
+            Style::default()
+                .fg(theme.now_accent)
+                .bg(theme.background)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(
+            Line::styled(hint, Style::default().fg(theme.text_muted).bg(theme.background))
+                .right_aligned(),
+        );
+    let inner = block.inner(area).inner(Margin {
+        vertical: 0,
+        horizontal: 1,
+    });
+    f.render_widget(block, area);
+    if inner.height == 0 {
+        return;
+    }
+
+    let stamp_style = Style::default().fg(theme.text_muted).bg(theme.background);
+    let done_style = Style::default().fg(theme.text_primary).bg(theme.background);
+    let next_style = Style::default()
+        .fg(theme.song_title_accent)
+        .bg(theme.background)
+        .add_modifier(Modifier::BOLD);
+
+    // Keep the line waiting for its stamp in the middle.
+    let top = next.saturating_sub(inner.height as usize / 2);
+    let lines: Vec<Line> = recorder
+        .lines
+        .iter()
+        .enumerate()
+        .skip(top)
+        .take(inner.height as usize)
+        .map(|(i, text)| {
+            let (stamp, style) = match recorder.stamps.get(i) {
+                Some(&t) => (format!("[{}] ", lrc::format_lrc_time(t)), done_style),
+                None if i == next => ("▶".to_string() + &" ".repeat(10), next_style),
+                None => (" ".repeat(11), stamp_style),
+            };
+            Line::from(vec![
+                Span::styled(stamp, stamp_style),
+                Span::styled(text.clone(), style),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
 fn help_text(ui: &UiState) -> String {
-    let vol_line = if ui.volume_mode {
-        "v / Esc      exit volume mode"
-    } else {
-        "v           toggle volume mode"
+    let keys = &ui.keys;
+    let entry = |keys: &str, what: &str| {
+        let width = unicode_width::UnicodeWidthStr::width(keys);
+        format!("  {keys}{}{what}", " ".repeat(12usize.saturating_sub(width).max(2)))
     };
 
-    [
-        "General",
-        "  h or ?      toggle this help",
-        "  F12        hide/unhide (shell; press again to return)",
-        "  q           quit",
-        "  s           toggle shuffle order",
-        "  S           search library (type to select)",
-        "  m           move to timestamp (e.g. 1:30)",
-        "  y           youtube download (paste URL, Enter to download)",
-        "  D           delete selected track (press twice)",
-        "  ↑/↓         scroll (PgUp/PgDn, Home/End)",
-        "",
-        "Playback",
-        "  Space       pause/resume",
-        "  P / N       previous/next track",
-        "  r           restart current track",
-        "  l           loop selected/current",
-        "",
-        "Arrows",
-        "  ← / →       seek -5s / +5s",
-        "  ↑ / ↓       select track",
-        "  (vol mode)  ↑ / ↓ changes volume",
-        "",
-        "Seek",
-        "  p / n       -10s / +10s",
-        "",
-        "Volume",
-        "  v           enter/exit volume mode",
-        "  ↑ / ↓       volume (when in volume mode)",
-        &format!("  {vol_line}"),
-        "",
-        "Library",
-        "  Enter       play selected",
-        "",
-        "Favourites",
-        "  a           add/remove selected track from favourites",
-        "  Tab         toggle favourites view",
-    ]
-    .join("\n")
+    let mut lines = Vec::new();
+    for section in SECTIONS {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(section.to_string());
+        if section == "Search" {
+            lines.push(entry(
+                &keys.label(Action::Search),
+                "type a query; results replace the library list",
+            ));
+        }
+        for action in Action::ALL.into_iter().filter(|a| a.section() == section) {
+            let bound = keys.keys_for(action);
+            if !bound.is_empty() {
+                lines.push(entry(&bound.join(" or "), action.description()));
+            }
+        }
+        match section {
+            "General" => {
+                lines.push(entry("F12", "hide/unhide (shell; press again to return)"));
+                lines.push(entry("Ctrl+C", "quit"));
+                lines.push(entry("↑/↓", "scroll popups (PgUp/PgDn, Home/End)"));
+            }
+            "Playback" if ui.volume_mode => lines.push(entry(
+                &format!("{} / Esc", keys.label(Action::VolumeMode)),
+                "exit volume mode",
+            )),
+            "Playback" => lines.push(entry(
+                &format!(
+                    "{} / {}",
+                    keys.label(Action::SelectUp),
+                    keys.label(Action::SelectDown)
+                ),
+                "volume (when in volume mode)",
+            )),
+            "Search" => {
+                lines.push(entry(&up_down(keys), "pick a result (arrows while typing)"));
+                lines.push(entry("Enter", "play the picked result"));
+                lines.push(entry("Esc", "close the search"));
+            }
+            "Listening stats" => lines.push(entry("Tab", "next period (Shift+Tab: previous)")),
+            "Sync recorder" => lines.push(entry("Esc", "close (press twice to discard stamps)")),
+            "Organize" => lines.push(entry("Enter / Esc", "apply the moves / cancel")),
+            _ => {}
+        }
+    }
+
+    lines.push(String::new());
+    lines.push(format!("Commands ({} then Tab to complete)", keys.label(Action::CommandLine)));
+    for (name, what) in command::COMMANDS {
+        lines.push(entry(&format!(":{name}"), what));
+    }
+
+    lines.push(String::new());
+    lines.push("Mouse".to_string());
+    for (k, what) in [
+        ("click", "select a track (double-click plays it)"),
+        ("wheel", "scroll the list, this help, or the volume over the title bar"),
+        ("drag", "seek by clicking or dragging on the progress bar"),
+    ] {
+        lines.push(entry(k, what));
+    }
+    lines.join("\n")
 }
 
 fn draw_help_overlay(f: &mut Frame, player: &Player, ui: &UiState, theme: &Theme) {
@@ -848,6 +1582,545 @@ fn draw_help_overlay(f: &mut Frame, player: &Player, ui: &UiState, theme: &Theme
     f.render_widget(p, overlay);
 }
 
+fn draw_skipped_overlay(f: &mut Frame, player: &Player, ui: &UiState, theme: &Theme) {
+    let overlay = help_overlay_rect(f.area());
+    f.render_widget(Clear, overlay);
+
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let lines: Vec<Line<'static>> = if player.skipped.is_empty() {
+        vec![Line::styled("Every audio file in the library can be played.", muted)]
+    } else {
+        player
+            .skipped
+            .iter()
+            .map(|s| {
+                let path = player
+                    .library
+                    .roots
+                    .iter()
+                    .find_map(|root| s.path.strip_prefix(root).ok())
+                    .unwrap_or(&s.path)
+                    .display()
+                    .to_string();
+                Line::from(vec![
+                    Span::styled(path, Style::default().fg(theme.text_primary)),
+                    Span::raw("  "),
+                    Span::styled(s.reason.clone(), Style::default().fg(theme.error)),
+                ])
+            })
+            .collect()
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title_top(format!("Skipped files ({})", player.skipped.len()))
+        .title_bottom(
+            Line::styled(
+                format!("Esc/{} close • ↑/↓ scroll", ui.keys.label(Action::SkippedFiles)),
+                muted,
+            )
+            .alignment(Alignment::Right),
+        )
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+
+    let p = Paragraph::new(Text::from(lines))
+        .block(block)
+        .scroll((ui.skipped_scroll, 0))
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+    f.render_widget(p, overlay);
+}
+
+fn draw_history_overlay(
+    f: &mut Frame,
+    player: &Player,
+    view: &HistoryView,
+    ui: &UiState,
+    theme: &Theme,
+) {
+    let overlay = help_overlay_rect(f.area());
+    f.render_widget(Clear, overlay);
+
+    let base = Style::default().fg(theme.text_primary).bg(theme.background);
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let visible_rows = overlay.height.saturating_sub(2) as usize;
+    let total = player.history.len();
+    let offset = view
+        .cursor
+        .saturating_sub(visible_rows.saturating_sub(1) / 2)
+        .min(total.saturating_sub(visible_rows));
+
+    // Listens of files no longer in the library can't be played, so they are muted.
+    let items: Vec<ListItem> = HistoryView::entries(player)
+        .skip(offset)
+        .take(visible_rows)
+        .map(|entry| {
+            let (name, style) = match player.tracks.iter().find(|t| entry.is_of(t)) {
+                Some(t) => (player.list_name(t), base),
+                None => (entry.path.display().to_string(), muted),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", fmt_datetime(entry.at)), muted),
+                Span::styled(format!("{:>5}  ", fmt_time(entry.listened)), muted),
+                Span::styled(format!("{:<9}  ", entry.end.label()), muted),
+                Span::styled(name, style),
+            ]))
+        })
+        .collect();
+
+    let mut state = ratatui::widgets::ListState::default();
+    state.select((total > 0).then(|| view.cursor - offset));
+
+    let title = match total {
+        0 => "History (nothing played yet)".to_string(),
+        n => format!("History ({} of {n})", view.cursor + 1),
+    };
+    let list = List::new(items)
+        .style(base)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title_top(title)
+                .title_bottom(
+                    Line::styled(
+                        format!(
+                            "Enter play • {} enqueue • Esc/{} close • {} move",
+                            ui.keys.label(Action::Enqueue),
+                            ui.keys.label(Action::History),
+                            up_down(&ui.keys)
+                        ),
+                        muted,
+                    )
+                    .alignment(Alignment::Right),
+                )
+                .style(base),
+        )
+        .highlight_style(Style::default().bg(theme.background).add_modifier(Modifier::REVERSED))
+        .highlight_symbol("» ");
+    f.render_stateful_widget(list, overlay, &mut state);
+}
+
+fn draw_stats_overlay(f: &mut Frame, view: &StatsView, ui: &UiState, theme: &Theme) {
+    let overlay = help_overlay_rect(f.area());
+    f.render_widget(Clear, overlay);
+
+    let base = Style::default().fg(theme.text_primary).bg(theme.background);
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let heading = Style::default()
+        .fg(theme.library_accent)
+        .bg(theme.background)
+        .add_modifier(Modifier::BOLD);
+    let stats = view.stats.period(view.period);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title_top(format!("Listening stats • {}", view.period.label()))
+        .title_bottom(
+            Line::styled(
+                format!(
+                    "Tab period • {}/{}/{} week/month/all time • Esc/{} close",
+                    ui.keys.label(Action::StatsWeek),
+                    ui.keys.label(Action::StatsMonth),
+                    ui.keys.label(Action::StatsAllTime),
+                    ui.keys.label(Action::Stats)
+                ),
+                muted,
+            )
+            .alignment(Alignment::Right),
+        )
+        .style(base);
+    let inner = block.inner(overlay);
+    f.render_widget(block, overlay);
+
+    let [summary, lists, chart] = Layout::vertical([
+        Constraint::Length(2),
+        // A heading and up to ten entries.
+        Constraint::Max(11),
+        Constraint::Min(6),
+    ])
+    .areas(inner);
+
+    let days = |n: u32| if n == 1 { "1 day".to_string() } else { format!("{n} days") };
+    let listened = fmt_span(Duration::from_secs(stats.listened_secs));
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(listened, heading),
+            Span::styled(format!(" listened over {} listens • ", stats.listens), base),
+            Span::styled(format!("streak {}", days(view.stats.current_streak)), heading),
+            Span::styled(format!(" (longest {})", days(view.stats.longest_streak)), base),
+        ]))
+        .style(base),
+        summary,
+    );
+
+    let columns: [(&str, &[Ranked]); 4] = [
+        ("Top tracks", &stats.top_tracks),
+        ("Top artists", &stats.top_artists),
+        ("Top albums", &stats.top_albums),
+        ("Most skipped", &stats.most_skipped),
+    ];
+    let areas = Layout::horizontal([Constraint::Ratio(1, 4); 4]).spacing(2).split(lists);
+    for ((title, ranked), &area) in columns.into_iter().zip(areas.iter()) {
+        let mut lines = vec![Line::styled(title, heading)];
+        if ranked.is_empty() {
+            lines.push(Line::styled("nothing yet", muted));
+        }
+        lines.extend(ranked.iter().map(|r| {
+            Line::from(vec![
+                Span::styled(format!("{:>3} ", r.count), muted),
+                Span::styled(r.name.clone(), base),
+            ])
+        }));
+        f.render_widget(Paragraph::new(lines).style(base), area);
+    }
+
+    // 24 bars across the width, labelled every three hours.
+    let gap = 1;
+    let bar_width = ((chart.width + gap) / 24).saturating_sub(gap).max(1);
+    let bars: Vec<Bar> = stats
+        .by_hour
+        .iter()
+        .enumerate()
+        .map(|(hour, &secs)| {
+            let label = if hour % 3 == 0 { hour.to_string() } else { String::new() };
+            Bar::default()
+                .value(secs / 60)
+                .label(Line::from(label))
+                .text_value(String::new())
+        })
+        .collect();
+    let chart_widget = BarChart::default()
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .title_top(Line::styled("Minutes by hour of day", heading))
+                .style(base),
+        )
+        .bar_width(bar_width)
+        .bar_gap(gap)
+        .bar_style(Style::default().fg(theme.now_accent).bg(theme.background))
+        .label_style(muted)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart_widget, chart);
+}
+
+/// Label/value rows for the track info popup. Missing fields are left out.
+pub(crate) fn track_info_rows(
+    track: &Track,
+    meta: &TrackMeta,
+    player: &Player,
+) -> Vec<(&'static str, String)> {
+    let of_total = |n: Option<u32>, total: Option<u32>| match (n, total) {
+        (Some(n), Some(t)) => Some(format!("{n}/{t}")),
+        (Some(n), None) => Some(n.to_string()),
+        (None, Some(t)) => Some(format!("?/{t}")),
+        (None, None) => None,
+    };
+
+    let stats = player.play_stats.get(&track.key()).copied().unwrap_or_default();
+
+    let mut rows: Vec<(&'static str, Option<String>)> = vec![
+        ("Title", meta.title.clone()),
+        ("Artist", meta.artist.clone()),
+        ("Album", meta.album.clone()),
+        ("Album artist", meta.album_artist.clone()),
+        ("Composer", meta.composer.clone()),
+        ("Track", of_total(meta.track_number, meta.track_total)),
+        ("Disc", of_total(meta.disc_number, meta.disc_total)),
+        ("Date", meta.date.clone()),
+        ("Genre", meta.genre.clone()),
+        ("Rating", meta.rating.map(|r| format!("{r}/100"))),
+        ("Comment", meta.comment.clone()),
+        ("", None),
+        ("Duration", meta.duration.map(fmt_time)),
+        ("Codec", meta.codec.clone()),
+        ("Sample rate", meta.sample_rate.map(|r| format!("{r} Hz"))),
+        ("Bit depth", meta.bit_depth.map(|b| format!("{b} bit"))),
+        ("Channels", meta.channels.map(|c| c.to_string())),
+        ("Bitrate", meta.bitrate.map(|b| format!("{} kbps", b / 1000))),
+        ("File size", meta.file_size.map(fmt_size)),
+        ("Plays", Some(stats.plays.to_string())),
+        ("Skips", Some(stats.skips.to_string())),
+        ("Last played", stats.last_played.map(fmt_datetime)),
+        ("Path", Some(track.path.display().to_string())),
+    ];
+    if let Some(cue) = &track.cue {
+        rows.push(("CUE start", Some(fmt_time(cue.start))));
+    }
+
+    let mb = &meta.musicbrainz;
+    if [&mb.recording, &mb.release, &mb.release_group, &mb.artist, &mb.album_artist]
+        .iter()
+        .any(|id| id.is_some())
+    {
+        rows.extend([
+            ("", None),
+            ("MB recording", mb.recording.clone()),
+            ("MB release", mb.release.clone()),
+            ("MB release group", mb.release_group.clone()),
+            ("MB artist", mb.artist.clone()),
+            ("MB album artist", mb.album_artist.clone()),
+        ]);
+    }
+
+    // Keep blank separators (label "") but drop empty fields.
+    let mut out: Vec<(&'static str, String)> = Vec::new();
+    for (label, value) in rows {
+        match value {
+            Some(v) if !v.trim().is_empty() => out.push((label, v)),
+            None if label.is_empty() && out.last().is_some_and(|(l, _)| !l.is_empty()) => {
+                out.push(("", String::new()))
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Local date and time, to the minute.
+pub(crate) fn fmt_datetime(t: SystemTime) -> String {
+    let mut s = local_datetime(t).replacen('T', " ", 1);
+    s.truncate(16);
+    s
+}
+
+fn fmt_size(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB)
+    } else {
+        format!("{:.0} KiB", bytes as f64 / 1024.0)
+    }
+}
+
+fn draw_track_info_overlay(
+    f: &mut Frame,
+    player: &Player,
+    track: &Track,
+    meta: &TrackMeta,
+    ui: &UiState,
+    theme: &Theme,
+) {
+    let overlay = help_overlay_rect(f.area());
+    f.render_widget(Clear, overlay);
+
+    let rows = track_info_rows(track, meta, player);
+    let width = rows.iter().map(|(l, _)| l.chars().count()).max().unwrap_or(0);
+    let key = key_style(theme);
+    let lines: Vec<Line<'static>> = rows
+        .into_iter()
+        .map(|(label, value)| {
+            if label.is_empty() {
+                return Line::raw("");
+            }
+            Line::from(vec![
+                Span::styled(format!("{label:<width$}"), key),
+                Span::raw("  "),
+                Span::styled(value, Style::default().fg(theme.text_primary)),
+            ])
+        })
+        .collect();
+
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title_top(format!("Track info • {}", track.display_name))
+        .title_bottom(
+            Line::styled(
+                format!("Esc/{} close • ↑/↓ scroll", ui.keys.label(Action::TrackInfo)),
+                muted,
+            )
+            .alignment(Alignment::Right),
+        )
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+
+    let p = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((ui.info_scroll, 0))
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+    f.render_widget(p, overlay);
+}
+
+fn draw_tag_editor(f: &mut Frame, editor: &TagEditor, keys: &KeyMap, theme: &Theme) {
+    let area = f.area();
+    let width = area.width.saturating_sub(4).min(72);
+    let height = (editor.fields.len() as u16 + 5).min(area.height);
+    let overlay = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, overlay);
+
+    let key = key_style(theme);
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let label_w = editor.fields.iter().map(|f| f.label.len()).max().unwrap_or(0);
+
+    let mut lines: Vec<Line<'static>> = editor
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let focused = i == editor.focus;
+            let marker = if focused { "» " } else { "  " };
+            let mut spans = vec![
+                Span::styled(marker, key),
+                Span::styled(format!("{:<label_w$}  ", field.label), key),
+            ];
+            if field.value.is_empty() && !focused && field.cleared {
+                spans.push(Span::styled("(remove)", muted));
+            } else if field.value.is_empty() && !focused && field.mixed {
+                spans.push(Span::styled("(multiple values)", muted));
+            } else {
+                let style = if field.changed() {
+                    Style::default().fg(theme.song_title_accent)
+                } else {
+                    Style::default().fg(theme.text_primary)
+                };
+                spans.push(Span::styled(field.value.clone(), style));
+            }
+            if focused {
+                spans.push(Span::styled("█", Style::default().fg(theme.text_muted)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    lines.push(Line::raw(""));
+    lines.push(match &editor.error {
+        Some(e) => Line::styled(e.clone(), Style::default().fg(theme.error)),
+        None => Line::styled(
+            format!(
+                "{} clears a field (removes the tag)",
+                keys.label(Action::TagClearField)
+            ),
+            muted,
+        ),
+    });
+
+    let title = match editor.targets.as_slice() {
+        [one] => format!("Edit tags • {}", one.display_name),
+        many => format!("Edit tags • {} tracks", many.len()),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.library_accent))
+        .title_top(title)
+        .title_bottom(
+            Line::styled("Enter save • Esc cancel • Tab/↑/↓ field", muted)
+                .alignment(Alignment::Right),
+        )
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+
+    let p = Paragraph::new(Text::from(lines))
+        .block(block)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+    f.render_widget(p, overlay);
+}
+
+fn draw_organize_overlay(f: &mut Frame, plan: &OrganizePlan, ui: &UiState, theme: &Theme) {
+    let overlay = help_overlay_rect(f.area());
+    f.render_widget(Clear, overlay);
+
+    let muted = Style::default().fg(theme.text_muted).bg(theme.background);
+    let key = key_style(theme);
+    let shown = |p: &std::path::Path| match p.strip_prefix(&plan.root) {
+        Ok(rel) => rel.display().to_string(),
+        Err(_) => p.display().to_string(),
+    };
+
+    let summary = [
+        (MoveStatus::Move, "to move"),
+        (MoveStatus::InPlace, "in place"),
+        (MoveStatus::Conflict, "conflicts"),
+        (MoveStatus::MissingTags, "missing tags"),
+        (MoveStatus::Cue, "CUE"),
+    ]
+    .into_iter()
+    .filter_map(|(status, label)| {
+        let n = plan.count(status);
+        (n > 0).then(|| format!("{n} {label}"))
+    })
+    .collect::<Vec<_>>()
+    .join(" • ");
+
+    let mut lines: Vec<Line<'static>> = vec![
+        Line::from(vec![
+            Span::styled("Into      ", key),
+            Span::raw(plan.root.display().to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Conflicts ", key),
+            Span::raw(plan.policy.label()),
+            Span::styled("  (c to change)", muted),
+        ]),
+        Line::raw(summary),
+    ];
+    if let Some(e) = &ui.organize_error {
+        lines.push(Line::styled(e.clone(), Style::default().fg(theme.error)));
+    }
+    lines.push(Line::raw(""));
+
+    for m in &plan.moves {
+        let (target, style) = match (&m.status, &m.to) {
+            (MoveStatus::Move, Some(to)) => (shown(to), Style::default().fg(theme.song_title_accent)),
+            (MoveStatus::InPlace, _) => ("already in place".to_string(), muted),
+            (MoveStatus::Conflict, Some(to)) => (
+                format!("{} exists, skipped", shown(to)),
+                Style::default().fg(theme.error),
+            ),
+            (MoveStatus::Cue, _) => ("split by a CUE sheet, skipped".to_string(), muted),
+            _ => ("missing tags, skipped".to_string(), muted),
+        };
+        lines.push(Line::styled(
+            shown(&m.from),
+            Style::default().fg(theme.text_primary),
+        ));
+        lines.push(Line::from(vec![
+            Span::styled("  → ", key),
+            Span::styled(target, style),
+        ]));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.library_accent))
+        .title_top("Organize files (dry run)")
+        .title_bottom(
+            Line::styled(
+                format!(
+                    "Enter move • Esc cancel • {} conflicts • {} scroll",
+                    ui.keys.label(Action::OrganizeConflicts),
+                    up_down(&ui.keys)
+                ),
+                muted,
+            )
+            .alignment(Alignment::Right),
+        )
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+
+    // Scroll by entry (two lines each), below the fixed header.
+    let header = lines.len() - plan.moves.len() * 2;
+    let scroll = if ui.organize_scroll == 0 {
+        0
+    } else {
+        (header + usize::from(ui.organize_scroll - 1) * 2).min(u16::MAX as usize) as u16
+    };
+    let p = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+    f.render_widget(p, overlay);
+}
+
 pub(crate) fn help_overlay_rect(area: Rect) -> Rect {
     // Use almost all terminal space, with a small margin, so the cheatsheet is
     // readable even on small terminals.
@@ -0,0 +1,186 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    config::Action,
+    library::{self, SearchHit, TrackKey},
+    player::Player,
+};
+
+use super::lyrics_view::ViewKey;
+
+/// The search panel: a query and its ranked results, shown in place of the
+/// library list.
+#[derive(Debug, Default)]
+pub(crate) struct Search {
+    pub(crate) query: String,
+    /// Keys go to the query; otherwise they browse the results.
+    pub(crate) editing: bool,
+    pub(crate) hits: Vec<SearchHit>,
+    pub(crate) cursor: usize,
+    /// `Player::revision` the hits were ranked at.
+    revision: u64,
+}
+
+impl Search {
+    /// Starts a new search. Tags and lyrics load in the background; results
+    /// fill in as they arrive.
+    pub(crate) fn open(player: &mut Player) -> Self {
+        player.prepare_search();
+        Self {
+            editing: true,
+            revision: player.revision,
+            ..Self::default()
+        }
+    }
+
+    /// Ranks the library again if it changed since the last ranking, keeping
+    /// the highlighted track.
+    pub(crate) fn refresh_if_stale(&mut self, player: &Player) {
+        if self.revision == player.revision {
+            return;
+        }
+        let key = self
+            .current()
+            .and_then(|i| player.tracks.get(i))
+            .map(|t| t.key());
+        self.rank(player);
+        if let Some(pos) = key.and_then(|k| self.position_of(player, &k)) {
+            self.cursor = pos;
+        }
+    }
+
+    fn rank(&mut self, player: &Player) {
+        self.hits = library::search(
+            &player.tracks,
+            &self.query,
+            |t| player.list_name(t),
+            &player.lyrics_index,
+        );
+        self.cursor = self.cursor.min(self.hits.len().saturating_sub(1));
+        self.revision = player.revision;
+    }
+
+    fn position_of(&self, player: &Player, key: &TrackKey) -> Option<usize> {
+        self.hits
+            .iter()
+            .position(|h| player.tracks.get(h.index).is_some_and(|t| t.key() == *key))
+    }
+
+    /// Track index of the highlighted result.
+    pub(crate) fn current(&self) -> Option<usize> {
+        self.hits.get(self.cursor).map(|h| h.index)
+    }
+
+    fn indices(&self) -> Vec<usize> {
+        self.hits.iter().map(|h| h.index).collect()
+    }
+
+    /// Moves the highlight by `delta` results, wrapping around if `wrap`, and
+    /// selects that track in the library.
+    fn step(&mut self, delta: isize, wrap: bool, player: &mut Player) {
+        let len = self.hits.len();
+        if len == 0 {
+            return;
+        }
+        self.cursor = if wrap {
+            (self.cursor as isize + delta).rem_euclid(len as isize) as usize
+        } else {
+            self.cursor.saturating_add_signed(delta).min(len - 1)
+        };
+        self.follow(player);
+    }
+
+    /// Highlights result `pos` and selects its track in the library.
+    pub(crate) fn pick(&mut self, pos: usize, player: &mut Player) {
+        if pos < self.hits.len() {
+            self.cursor = pos;
+            self.follow(player);
+        }
+    }
+
+    fn follow(&self, player: &mut Player) {
+        if let Some(i) = self.current() {
+            player.selected = i;
+        }
+    }
+
+    fn play_current(&mut self, player: &mut Player) {
+        if let Some(i) = self.current() {
+            player.selected = i;
+            let _ = player.play_selected();
+        }
+    }
+
+    /// A key while the query is typed.
+    pub(crate) fn handle_query_key(&mut self, key: KeyEvent, player: &mut Player) -> ViewKey {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return ViewKey::Close,
+            KeyCode::Enter => {
+                self.play_current(player);
+                self.editing = false;
+            }
+            KeyCode::Up => self.step(-1, false, player),
+            KeyCode::Down => self.step(1, false, player),
+            KeyCode::Char('a') if ctrl => {
+                let _ = player.play_all(&self.indices());
+                self.editing = false;
+            }
+            KeyCode::Char('e') if ctrl => {
+                player.enqueue(&self.indices());
+                self.editing = false;
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.requery(player);
+            }
+            // Ignore control chords; accept everything else as input.
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.query.push(c);
+                self.requery(player);
+            }
+            _ => {}
+        }
+        ViewKey::Handled
+    }
+
+    /// Replaces the query and shows its results for browsing.
+    pub(crate) fn set_query(&mut self, query: &str, player: &mut Player) {
+        self.query = query.to_string();
+        self.editing = query.is_empty();
+        self.requery(player);
+    }
+
+    /// Ranks for the edited query and jumps to the best result.
+    fn requery(&mut self, player: &mut Player) {
+        self.cursor = 0;
+        self.rank(player);
+        self.follow(player);
+    }
+
+    /// A key while browsing the results, with the action it is bound to.
+    pub(crate) fn handle_results_key(
+        &mut self,
+        key: KeyEvent,
+        action: Option<Action>,
+        player: &mut Player,
+    ) -> ViewKey {
+        match (key.code, action) {
+            (KeyCode::Esc, _) => return ViewKey::Close,
+            (KeyCode::PageUp, _) => self.step(-10, false, player),
+            (KeyCode::PageDown, _) => self.step(10, false, player),
+            (KeyCode::Enter, _) => self.play_current(player),
+            (_, Some(Action::SearchEdit)) => self.editing = true,
+            (_, Some(Action::SelectUp)) => self.step(-1, false, player),
+            (_, Some(Action::SelectDown)) => self.step(1, false, player),
+            (_, Some(Action::SearchNext)) => self.step(1, true, player),
+            (_, Some(Action::SearchPrev)) => self.step(-1, true, player),
+            (_, Some(Action::SearchPlayAll)) => {
+                let _ = player.play_all(&self.indices());
+            }
+            (_, Some(Action::SearchEnqueueAll)) => player.enqueue(&self.indices()),
+            _ => return ViewKey::PassThrough,
+        }
+        ViewKey::Handled
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{
+    config::Action,
+    library::{ListeningStats, Period, PlayedFile},
+    meta::TrackMeta,
+    player::Player,
+};
+
+use super::lyrics_view::ViewKey;
+
+/// The listening stats popup.
+#[derive(Debug)]
+pub(crate) struct StatsView {
+    pub(crate) stats: ListeningStats,
+    pub(crate) period: Period,
+    /// `Player::revision` and history length the stats were worked out at.
+    revision: u64,
+    listens: usize,
+}
+
+impl StatsView {
+    /// Actions that keep working over the popup; the stats action closes it.
+    pub(crate) const PASS_THROUGH: &[Action] = &[
+        Action::PlayPause,
+        Action::SeekBack,
+        Action::SeekForward,
+        Action::SeekBackLong,
+        Action::SeekForwardLong,
+        Action::PrevTrack,
+        Action::NextTrack,
+        Action::Restart,
+        Action::Stats,
+        Action::Help,
+    ];
+
+    /// Artists and albums come from tags, which load in the background; the
+    /// stats fill in as they arrive.
+    pub(crate) fn open(player: &mut Player) -> Self {
+        player.load_all_tags();
+        Self {
+            stats: compute(player),
+            period: Period::Week,
+            revision: player.revision,
+            listens: player.history.len(),
+        }
+    }
+
+    /// Works the stats out again if tags or the history changed since.
+    pub(crate) fn refresh_if_stale(&mut self, player: &Player) {
+        if self.revision == player.revision && self.listens == player.history.len() {
+            return;
+        }
+        self.stats = compute(player);
+        self.revision = player.revision;
+        self.listens = player.history.len();
+    }
+
+    fn cycle(&mut self, forward: bool) {
+        let all = Period::ALL;
+        let i = all.iter().position(|&p| p == self.period).unwrap_or(0);
+        let step = if forward { 1 } else { all.len() - 1 };
+        self.period = all[(i + step) % all.len()];
+    }
+
+    /// A key, with the action it is bound to.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent, action: Option<Action>) -> ViewKey {
+        match (key.code, action) {
+            (KeyCode::Esc, _) | (_, Some(Action::Quit)) => return ViewKey::Close,
+            (KeyCode::Tab, _) => self.cycle(true),
+            (KeyCode::BackTab, _) => self.cycle(false),
+            (_, Some(Action::StatsWeek)) => self.period = Period::Week,
+            (_, Some(Action::StatsMonth)) => self.period = Period::Month,
+            (_, Some(Action::StatsAllTime)) => self.period = Period::AllTime,
+            _ => return ViewKey::PassThrough,
+        }
+        ViewKey::Handled
+    }
+}
+
+/// Tracks still in the library are described by their loaded tags; others by
+/// their file name.
+fn compute(player: &Player) -> ListeningStats {
+    let tags: HashMap<(&Path, Duration), &TrackMeta> = player
+        .tracks
+        .iter()
+        .filter_map(|t| Some(((t.path.as_path(), t.start_offset()), t.tags.as_ref()?)))
+        .collect();
+    ListeningStats::compute(&player.history, SystemTime::now(), |path, start| {
+        PlayedFile::new(path, tags.get(&(path, start)).copied())
+    })
+}
@@ -0,0 +1,154 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    config::Action,
+    library::Track,
+    meta::{TagEdit, TrackMeta},
+    player::Player,
+};
+
+/// The tag editing form, for one track or a batch of marked tracks.
+#[derive(Debug)]
+pub(crate) struct TagEditor {
+    pub(crate) targets: Vec<Track>,
+    pub(crate) fields: Vec<EditField>,
+    pub(crate) focus: usize,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct EditField {
+    pub(crate) label: &'static str,
+    pub(crate) value: String,
+    original: String,
+    /// The targets disagree on this field; it is left alone unless edited.
+    pub(crate) mixed: bool,
+    /// Cleared with Ctrl+U: an empty value then removes the tag.
+    pub(crate) cleared: bool,
+}
+
+impl EditField {
+    pub(crate) fn changed(&self) -> bool {
+        self.cleared || self.value != self.original
+    }
+}
+
+const LABELS: [&str; 6] = ["Title", "Artist", "Album", "Track", "Year", "Genre"];
+
+fn field_value(meta: &TrackMeta, field: usize) -> String {
+    match field {
+        0 => meta.title.clone().unwrap_or_default(),
+        1 => meta.artist.clone().unwrap_or_default(),
+        2 => meta.album.clone().unwrap_or_default(),
+        3 => match (meta.track_number, meta.track_total) {
+            (Some(n), Some(t)) => format!("{n}/{t}"),
+            (Some(n), None) => n.to_string(),
+            _ => String::new(),
+        },
+        4 => meta.year().map(|y| y.to_string()).unwrap_or_default(),
+        _ => meta.genre.clone().unwrap_or_default(),
+    }
+}
+
+impl TagEditor {
+    /// Opens the form for the player's current selection (marked tracks, or the
+    /// selected one), filled with the values the targets have in common.
+    pub(crate) fn open(player: &Player) -> Option<Self> {
+        let targets: Vec<Track> = player
+            .selection()
+            .into_iter()
+            .filter_map(|i| player.tracks.get(i).cloned())
+            .collect();
+        if targets.is_empty() {
+            return None;
+        }
+        let metas: Vec<TrackMeta> = targets.iter().map(Track::read_meta).collect();
+
+        let fields = LABELS
+            .iter()
+            .enumerate()
+            .map(|(i, &label)| {
+                let first = field_value(&metas[0], i);
+                let mixed = metas.iter().any(|m| field_value(m, i) != first);
+                let value = if mixed { String::new() } else { first };
+                EditField {
+                    label,
+                    original: value.clone(),
+                    value,
+                    mixed,
+                    cleared: false,
+                }
+            })
+            .collect();
+
+        Some(Self {
+            targets,
+            fields,
+            focus: 0,
+            error: None,
+        })
+    }
+
+    fn edit(&self) -> TagEdit {
+        let get = |i: usize| {
+            let f = &self.fields[i];
+            f.changed().then(|| f.value.trim().to_string())
+        };
+        TagEdit {
+            title: get(0),
+            artist: get(1),
+            album: get(2),
+            track: get(3),
+            year: get(4),
+            genre: get(5),
+        }
+    }
+
+    /// Handles a key; returns false once the form should close.
+    /// A key, with the tag editor action it is bound to. Returns false once
+    /// the form is closed.
+    pub(crate) fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        action: Option<Action>,
+        player: &mut Player,
+    ) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match (key.code, action) {
+            (KeyCode::Esc, _) => return false,
+            (KeyCode::Enter, _) => {
+                let edit = self.edit();
+                if edit.is_empty() {
+                    return false;
+                }
+                match player.edit_tags(&self.targets, &edit) {
+                    Ok(()) => return false,
+                    Err(e) => self.error = Some(format!("{e:#}")),
+                }
+            }
+            (KeyCode::Tab | KeyCode::Down, _) => {
+                self.focus = (self.focus + 1) % self.fields.len();
+            }
+            (KeyCode::BackTab | KeyCode::Up, _) => {
+                self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+            }
+            (KeyCode::Backspace, _) => {
+                self.fields[self.focus].value.pop();
+                self.error = None;
+            }
+            // Clearing the field removes the tag on save.
+            (_, Some(Action::TagClearField)) => {
+                let field = &mut self.fields[self.focus];
+                field.value.clear();
+                field.cleared = true;
+                self.error = None;
+            }
+            (KeyCode::Char(c), _) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.fields[self.focus].value.push(c);
+                self.error = None;
+            }
+            _ => {}
+        }
+        true
+    }
+}
@@ -1,4 +1,8 @@
-use std::time::{Duration, Instant};
+use std::{
+    env,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 pub(crate) fn make_shuffled_order(len: usize, current: usize) -> Vec<usize> {
     if len == 0 {
@@ -29,6 +33,49 @@ pub(crate) fn fmt_time(d: Duration) -> String {
     format!("{m:02}:{s:02}")
 }
 
+/// A longer stretch of time as `3h 12m`, or `12m` under an hour.
+pub(crate) fn fmt_span(d: Duration) -> String {
+    let m = d.as_secs() / 60;
+    match m / 60 {
+        0 => format!("{m}m"),
+        h => format!("{h}h {:02}m", m % 60),
+    }
+}
+
+/// `t` as seconds since the epoch, broken down in local time.
+fn local_time(t: SystemTime) -> (libc::time_t, libc::tm) {
+    let secs = t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as libc::time_t;
+    // SAFETY: `tm` is plain data, and `localtime_r` only writes to it.
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&secs, &mut tm);
+        tm
+    };
+    (secs, tm)
+}
+
+/// `t` in local time as `YYYY-MM-DDThh:mm:ss`.
+pub(crate) fn local_datetime(t: SystemTime) -> String {
+    let (_, tm) = local_time(t);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// The local calendar day `t` falls on, counted from the epoch, and the hour
+/// of that day.
+pub(crate) fn local_day_hour(t: SystemTime) -> (i64, u32) {
+    let (secs, tm) = local_time(t);
+    let day = (secs + tm.tm_gmtoff).div_euclid(86_400);
+    (day, tm.tm_hour as u32)
+}
+
 pub(crate) fn parse_timestamp(input: &str) -> std::result::Result<Duration, String> {
     let s = input.trim();
     if s.is_empty() {
@@ -82,6 +129,13 @@ pub(crate) fn parse_timestamp(input: &str) -> std::result::Result<Duration, Stri
     Ok(Duration::from_secs(total_secs))
 }
 
+/// `$XDG_DATA_HOME` (or `~/.local/share`).
+pub(crate) fn data_home() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+}
+
 pub(crate) trait SaturatingDurationSince {
     fn saturating_duration_since(self, earlier: Instant) -> Duration;
 }
@@ -95,3 +149,34 @@ impl SaturatingDurationSince for Instant {
         }
     }
 }
+
+/// A scratch directory for tests, removed when dropped, so also when a test
+/// fails.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// Creates an empty `trix-test-<pid>-<name>` in the system temp directory.
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("trix-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("create scratch directory");
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+
+    pub(crate) fn join(&self, name: impl AsRef<std::path::Path>) -> std::path::PathBuf {
+        self.0.join(name)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}